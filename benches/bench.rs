@@ -4,7 +4,7 @@ use std::env;
 use criterion::{criterion_group, criterion_main, Criterion};
 
 #[allow(unused_imports)]
-use sort_test_tools::ffi_types::{FFIOneKiloByte, FFIString, F128};
+use sort_test_tools::ffi_types::{FFIOneKiloByte, FFIString, F128, F32, F64};
 
 use sort_test_tools::patterns;
 
@@ -212,7 +212,6 @@ fn bench_patterns<T: Ord + std::fmt::Debug>(
         ("random_z2", |len| patterns::random_zipf(len, 2.0)),
         ("random_z3", |len| patterns::random_zipf(len, 3.0)),
         ("random_z4", |len| patterns::random_zipf(len, 4.0)),
-        ("random_s5", |len| patterns::random_sorted(len, 95.0)),
         ("random_s5", |len| patterns::random_sorted(len, 5.0)),
         ("random_s10", |len| patterns::random_sorted(len, 10.0)),
         ("random_s30", |len| patterns::random_sorted(len, 30.0)),
@@ -330,6 +329,21 @@ fn criterion_benchmark(c: &mut Criterion) {
             values.iter().map(|val| F128::new(*val)).collect()
         });
 
+        // Floats ordered with total_cmp, cheap to copy but not Ord on their own.
+        #[cfg(feature = "bench_type_f32")]
+        {
+            bench_patterns(c, test_len, "f32", |values| {
+                values.iter().map(|val| F32::new(*val)).collect()
+            });
+        }
+
+        #[cfg(feature = "bench_type_f64")]
+        {
+            bench_patterns(c, test_len, "f64", |values| {
+                values.iter().map(|val| F64::new(*val)).collect()
+            });
+        }
+
         #[cfg(feature = "bench_type_rust_string")]
         {
             bench_patterns(c, test_len, "rust_string", |values| {
@@ -47,6 +47,7 @@ fn bench_impl<T: Ord + std::fmt::Debug, P: PartitionPoint>(
         pattern_name,
         p_pattern_provider,
         &bench_name,
+        true,
         p_test_fn,
     )
 }
@@ -1,4 +1,5 @@
 use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use criterion::{black_box, Criterion};
 
@@ -29,21 +30,23 @@ fn measure_comp_count<S: Sort, T: Ord + std::fmt::Debug>(
         10
     };
 
-    let mut comp_count = 0u64;
+    // Counted atomically, so that the comparison function is `Fn + Sync` and parallel sorts don't
+    // fall back to sorting sequentially.
+    let comp_count = AtomicU64::new(0);
 
     // Instrument via sort_by to ensure the type properties such as Copy of the type
     // that is being sorted doesn't change. And we get representative numbers.
     for _ in 0..run_count {
         let mut test_data = transform(pattern_provider(test_len));
         S::sort_by(black_box(test_data.as_mut_slice()), |a, b| {
-            comp_count += 1;
+            comp_count.fetch_add(1, Ordering::Relaxed);
             a.cmp(b)
         })
     }
 
     // If there is on average less than a single comparison this will be wrong.
     // But that's such a corner case I don't care about it.
-    let total = comp_count / (run_count as u64);
+    let total = comp_count.into_inner() / (run_count as u64);
     println!("{name}: mean comparisons: {total}");
 }
 
@@ -54,6 +57,7 @@ pub fn bench_fn<S: Sort, T: Ord + std::fmt::Debug>(
     transform: &fn(Vec<i32>) -> Vec<T>,
     pattern_name: &str,
     pattern_provider: impl Fn(usize) -> Vec<i32>,
+    pin_thread: bool,
 ) {
     let bench_name = S::name();
 
@@ -75,6 +79,7 @@ pub fn bench_fn<S: Sort, T: Ord + std::fmt::Debug>(
             pattern_name,
             pattern_provider,
             &bench_name,
+            pin_thread,
             S::sort,
         );
     }
@@ -99,6 +104,21 @@ pub fn bench<T: Ord + std::fmt::Debug>(
                 transform,
                 pattern_name,
                 pattern_provider,
+                true,
+            );
+        }};
+        ($sort_impl_path:path, $threads:expr) => {{
+            use $sort_impl_path::*;
+
+            // Not pinned, the threads of the parallel sort would inherit the affinity.
+            bench_fn::<SortImpl<$threads>, T>(
+                c,
+                test_len,
+                transform_name,
+                transform,
+                pattern_name,
+                pattern_provider,
+                false,
             );
         }};
     }
@@ -138,6 +158,24 @@ pub fn bench<T: Ord + std::fmt::Debug>(
 
     bench_inst!(unstable::rust_ipnsort);
 
+    bench_inst!(unstable::rust_ipnsort_scratch);
+
+    // Same as rust_ipnsort, but with a dual-pivot quicksort and without radix sort for integers.
+    bench_inst!(unstable::rust_ipnsort_dual_pivot);
+
+    // The thread count is part of the name, e.g. rust_ipnsort_par_t16_unstable.
+    bench_inst!(unstable::rust_ipnsort_par, 1);
+    bench_inst!(unstable::rust_ipnsort_par, 4);
+    bench_inst!(unstable::rust_ipnsort_par, 16);
+    bench_inst!(unstable::rust_ipnsort_par, 64);
+
+    // Same as rust_ipnsort for all types except f32 and f64.
+    #[cfg(any(feature = "bench_type_f32", feature = "bench_type_f64"))]
+    bench_inst!(unstable::rust_ipnsort_floats);
+
+    // Same as rust_ipnsort for all types except string and rust_string.
+    bench_inst!(unstable::rust_ipnsort_strings);
+
     bench_inst!(unstable::rust_std);
 
     #[cfg(feature = "rust_dmsort")]
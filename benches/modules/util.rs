@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::env;
 use std::str::FromStr;
 
@@ -7,12 +8,11 @@ use criterion::{black_box, BatchSize, Criterion};
 
 use once_cell::sync::OnceCell;
 
+thread_local! {static AFFINITY_ALREADY_SET: Cell<bool> = Cell::new(false); }
+
 pub fn pin_thread_to_core() {
-    use std::cell::Cell;
     let pin_core_id: usize = 2;
 
-    thread_local! {static AFFINITY_ALREADY_SET: Cell<bool> = Cell::new(false); }
-
     // Set affinity only once per thread.
     AFFINITY_ALREADY_SET.with(|affinity_already_set| {
         if !affinity_already_set.get() {
@@ -28,6 +28,28 @@ pub fn pin_thread_to_core() {
     });
 }
 
+/// Allows the current thread to run on all cores again, undoing `pin_thread_to_core`. Threads
+/// inherit the affinity of the thread that starts them, so parallel sorts would run all of their
+/// threads on the pinned core.
+pub fn unpin_thread() {
+    AFFINITY_ALREADY_SET.with(|affinity_already_set| {
+        if affinity_already_set.get() {
+            // core_affinity can only pin to a single core. The kernel ignores cores that don't
+            // exist or are not available to the process.
+            #[cfg(target_os = "linux")]
+            unsafe {
+                let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+                for cpu in 0..(libc::CPU_SETSIZE as usize) {
+                    libc::CPU_SET(cpu, &mut cpu_set);
+                }
+                libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set);
+            }
+
+            affinity_already_set.set(false);
+        }
+    });
+}
+
 #[allow(unused)]
 pub fn cpu_max_freq_hz() -> Option<f64> {
     static MAX_FREQUENCY: OnceCell<Option<f64>> = OnceCell::new();
@@ -68,12 +90,18 @@ pub fn bench_fn<T: Ord + std::fmt::Debug>(
     pattern_name: &str,
     pattern_provider: impl Fn(usize) -> Vec<i32>,
     mut bench_name: &str,
+    pin_thread: bool,
     test_fn: impl Fn(&mut [T]),
 ) {
     // Pin the benchmark to the same core to improve repeatability. Doing it this way allows
     // criterion to do other stuff with other threads, which greatly impacts overall benchmark
-    // throughput.
-    pin_thread_to_core();
+    // throughput. Parallel sorts pass `pin_thread: false`, their threads would inherit the
+    // affinity.
+    if pin_thread {
+        pin_thread_to_core();
+    } else {
+        unpin_thread();
+    }
 
     let batch_size = if test_len > 30 {
         BatchSize::LargeInput
@@ -0,0 +1,111 @@
+#pragma once
+
+// C and C++ declarations for the functions exported by the ipnsort-capi crate.
+//
+// Link against libipnsort_capi.a or libipnsort_capi.so. The types have the
+// same layout as the ones in src/cpp/shared.h, and both headers can be included
+// in the same translation unit.
+//
+// The _by variants return 0 if the sort completed and 1 if cmp_fn reported a
+// panic via is_panic or returned a cmp_result other than -1, 0 or 1. In the
+// latter case the slice contains the original elements in unspecified order.
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifndef __cplusplus
+#include <stdbool.h>
+#endif
+
+#ifdef __cplusplus
+#define IPNSORT_CONST_REF(T) const T&
+#else
+#define IPNSORT_CONST_REF(T) const T*
+#endif
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#ifndef SORT_SHARED_FFI_TYPES
+#define SORT_SHARED_FFI_TYPES
+
+typedef struct CompResult {
+  int8_t cmp_result;  // -1 == less, 0 == equal, 1 == more
+  bool is_panic;
+} CompResult;
+
+typedef struct FFIString {
+  char* data;
+  size_t len;
+  size_t capacity;
+} FFIString;
+
+typedef struct F128 {
+  double x;
+  double y;
+} F128;
+
+typedef struct FFIOneKiloByte {
+  int64_t values[128];
+} FFIOneKiloByte;
+
+#endif  // SORT_SHARED_FFI_TYPES
+
+// --- i32 ---
+
+void ipnsort_i32(int32_t* data, size_t len);
+uint32_t ipnsort_i32_by(int32_t* data,
+                        size_t len,
+                        CompResult (*cmp_fn)(IPNSORT_CONST_REF(int32_t),
+                                             IPNSORT_CONST_REF(int32_t),
+                                             uint8_t*),
+                        uint8_t* ctx);
+
+// --- u64 ---
+
+void ipnsort_u64(uint64_t* data, size_t len);
+uint32_t ipnsort_u64_by(uint64_t* data,
+                        size_t len,
+                        CompResult (*cmp_fn)(IPNSORT_CONST_REF(uint64_t),
+                                             IPNSORT_CONST_REF(uint64_t),
+                                             uint8_t*),
+                        uint8_t* ctx);
+
+// --- ffi_string ---
+
+// Compares the bytes of the strings lexicographically.
+void ipnsort_ffi_string(FFIString* data, size_t len);
+uint32_t ipnsort_ffi_string_by(FFIString* data,
+                               size_t len,
+                               CompResult (*cmp_fn)(IPNSORT_CONST_REF(FFIString),
+                                                    IPNSORT_CONST_REF(FFIString),
+                                                    uint8_t*),
+                               uint8_t* ctx);
+
+// --- f128 ---
+
+// Compares x / y in the IEEE 754 total order, unlike operator< of F128Cpp: -0.0 is sorted before
+// 0.0, NaNs with the sign bit set before all other values and the other NaNs after them.
+void ipnsort_f128(F128* data, size_t len);
+uint32_t ipnsort_f128_by(F128* data,
+                         size_t len,
+                         CompResult (*cmp_fn)(IPNSORT_CONST_REF(F128),
+                                              IPNSORT_CONST_REF(F128),
+                                              uint8_t*),
+                         uint8_t* ctx);
+
+// --- 1k ---
+
+// Compares values[11] + values[55] + values[77], same as FFIOneKiloByteCpp.
+void ipnsort_1k(FFIOneKiloByte* data, size_t len);
+uint32_t ipnsort_1k_by(FFIOneKiloByte* data,
+                       size_t len,
+                       CompResult (*cmp_fn)(IPNSORT_CONST_REF(FFIOneKiloByte),
+                                            IPNSORT_CONST_REF(FFIOneKiloByte),
+                                            uint8_t*),
+                       uint8_t* ctx);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif
@@ -0,0 +1,163 @@
+//! C ABI for ipnsort.
+//!
+//! The exported functions follow the same naming and signature scheme as the C++ sort
+//! implementations in `src/cpp`, and the types have the same layout as the ones in
+//! `src/cpp/shared.h`. See `include/ipnsort.h` for the C and C++ declarations.
+//!
+//! The `_by` variants return 0 if the sort completed and 1 if the comparison function reported a
+//! panic or returned an invalid `cmp_result`. In the latter case the slice contains the original
+//! elements in unspecified order.
+
+use std::cmp::Ordering;
+use std::ffi::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+#[repr(C)]
+pub struct CompResult {
+    pub cmp_result: i8, // -1 == less, 0 == equal, 1 == more
+    pub is_panic: bool,
+}
+
+#[repr(C)]
+pub struct FFIString {
+    pub data: *mut c_char,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+impl FFIString {
+    fn as_bytes(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+
+        // SAFETY: The caller of the exported function guarantees that `data` points to `len`
+        // initialized bytes.
+        unsafe { slice::from_raw_parts(self.data as *const u8, self.len) }
+    }
+}
+
+#[repr(C)]
+pub struct F128 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl F128 {
+    fn as_div_val(&self) -> f64 {
+        self.x / self.y
+    }
+}
+
+#[repr(C)]
+pub struct FFIOneKiloByte {
+    pub values: [i64; 128],
+}
+
+impl FFIOneKiloByte {
+    fn as_i64(&self) -> i64 {
+        self.values[11] + self.values[55] + self.values[77]
+    }
+}
+
+/// Payload used to unwind out of the sort if the foreign comparison function reports an error.
+struct ComparisonFailed;
+
+unsafe fn as_slice<'a, T>(data: *mut T, len: usize) -> &'a mut [T] {
+    if len == 0 {
+        // Allow callers to pass a null pointer for empty inputs.
+        return &mut [];
+    }
+
+    // SAFETY: The caller guarantees that `data` points to `len` initialized values of `T`.
+    unsafe { slice::from_raw_parts_mut(data, len) }
+}
+
+unsafe fn sort_by_impl<T>(
+    data: *mut T,
+    len: usize,
+    cmp_fn: unsafe extern "C" fn(&T, &T, *mut u8) -> CompResult,
+    cmp_fn_ctx: *mut u8,
+) -> u32 {
+    // SAFETY: See `as_slice`.
+    let v = unsafe { as_slice(data, len) };
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        ipnsort::sort_by(v, |a, b| {
+            // SAFETY: The caller guarantees that `cmp_fn` can be called with `cmp_fn_ctx`.
+            let comp_result = unsafe { cmp_fn(a, b, cmp_fn_ctx) };
+
+            let ordering = match comp_result.cmp_result {
+                _ if comp_result.is_panic => None,
+                -1 => Some(Ordering::Less),
+                0 => Some(Ordering::Equal),
+                1 => Some(Ordering::Greater),
+                _ => None,
+            };
+
+            // `resume_unwind` doesn't invoke the panic hook, so nothing gets printed. The sort
+            // guarantees that `v` still contains all original elements after unwinding.
+            ordering.unwrap_or_else(|| panic::resume_unwind(Box::new(ComparisonFailed)))
+        });
+    }));
+
+    match res {
+        Ok(()) => 0,
+        Err(_) => 1,
+    }
+}
+
+macro_rules! capi_sort_impl {
+    ($type:ty, $sort_name:ident, $sort_by_name:ident, $sort_fn:expr) => {
+        /// # Safety
+        ///
+        /// `data` must point to `len` initialized values, or `len` must be 0.
+        #[no_mangle]
+        pub unsafe extern "C" fn $sort_name(data: *mut $type, len: usize) {
+            // SAFETY: See `as_slice`.
+            $sort_fn(unsafe { as_slice(data, len) });
+        }
+
+        /// # Safety
+        ///
+        /// `data` must point to `len` initialized values, or `len` must be 0. `cmp_fn` must be
+        /// safe to call with any two of those values and `cmp_fn_ctx`.
+        #[no_mangle]
+        pub unsafe extern "C" fn $sort_by_name(
+            data: *mut $type,
+            len: usize,
+            cmp_fn: unsafe extern "C" fn(&$type, &$type, *mut u8) -> CompResult,
+            cmp_fn_ctx: *mut u8,
+        ) -> u32 {
+            // SAFETY: Forwarded to the caller.
+            unsafe { sort_by_impl(data, len, cmp_fn, cmp_fn_ctx) }
+        }
+    };
+}
+
+capi_sort_impl!(i32, ipnsort_i32, ipnsort_i32_by, ipnsort::sort::<i32>);
+
+capi_sort_impl!(u64, ipnsort_u64, ipnsort_u64_by, ipnsort::sort::<u64>);
+
+capi_sort_impl!(
+    FFIString,
+    ipnsort_ffi_string,
+    ipnsort_ffi_string_by,
+    |v: &mut [FFIString]| ipnsort::sort_by(v, |a, b| a.as_bytes().cmp(b.as_bytes()))
+);
+
+// Sorted by `x / y` with `f64::total_cmp`, which differs from `operator<` of `F128Cpp`: -0.0 is
+// placed before 0.0, NaNs with the sign bit set before all other values and the other NaNs after
+// them. Treating NaN as equal to everything isn't a total order, `ipnsort::sort_by` may panic on
+// it, and a panic can't unwind out of the exported function.
+capi_sort_impl!(F128, ipnsort_f128, ipnsort_f128_by, |v: &mut [F128]| {
+    ipnsort::sort_by(v, |a, b| a.as_div_val().total_cmp(&b.as_div_val()))
+});
+
+capi_sort_impl!(
+    FFIOneKiloByte,
+    ipnsort_1k,
+    ipnsort_1k_by,
+    |v: &mut [FFIOneKiloByte]| ipnsort::sort_by(v, |a, b| a.as_i64().cmp(&b.as_i64()))
+);
@@ -0,0 +1,223 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+use sort_test_tools::ffi_types::{CompResult, FFIOneKiloByte, FFIString, F128};
+use sort_test_tools::patterns;
+
+// Only referenced to make sure the exported symbols get linked into the test binary.
+use ipnsort_capi as _;
+
+// Declared the same way `ffi_util::ffi_sort_impl!` declares the C++ sort implementations, with the
+// FFI types of sort_test_tools, to check that the symbols can be used interchangeably.
+extern "C" {
+    fn ipnsort_i32(data: *mut i32, len: usize);
+    fn ipnsort_i32_by(
+        data: *mut i32,
+        len: usize,
+        cmp_fn: unsafe extern "C" fn(&i32, &i32, *mut u8) -> CompResult,
+        cmp_fn_ctx: *mut u8,
+    ) -> u32;
+    fn ipnsort_u64(data: *mut u64, len: usize);
+    fn ipnsort_u64_by(
+        data: *mut u64,
+        len: usize,
+        cmp_fn: unsafe extern "C" fn(&u64, &u64, *mut u8) -> CompResult,
+        cmp_fn_ctx: *mut u8,
+    ) -> u32;
+    fn ipnsort_ffi_string(data: *mut FFIString, len: usize);
+    fn ipnsort_ffi_string_by(
+        data: *mut FFIString,
+        len: usize,
+        cmp_fn: unsafe extern "C" fn(&FFIString, &FFIString, *mut u8) -> CompResult,
+        cmp_fn_ctx: *mut u8,
+    ) -> u32;
+    fn ipnsort_f128(data: *mut F128, len: usize);
+    fn ipnsort_f128_by(
+        data: *mut F128,
+        len: usize,
+        cmp_fn: unsafe extern "C" fn(&F128, &F128, *mut u8) -> CompResult,
+        cmp_fn_ctx: *mut u8,
+    ) -> u32;
+    fn ipnsort_1k(data: *mut FFIOneKiloByte, len: usize);
+    fn ipnsort_1k_by(
+        data: *mut FFIOneKiloByte,
+        len: usize,
+        cmp_fn: unsafe extern "C" fn(&FFIOneKiloByte, &FFIOneKiloByte, *mut u8) -> CompResult,
+        cmp_fn_ctx: *mut u8,
+    ) -> u32;
+}
+
+type SortFn<T> = unsafe extern "C" fn(*mut T, usize);
+type SortByFn<T> = unsafe extern "C" fn(
+    *mut T,
+    usize,
+    unsafe extern "C" fn(&T, &T, *mut u8) -> CompResult,
+    *mut u8,
+) -> u32;
+
+#[cfg(miri)]
+const TEST_SIZES: [usize; 8] = [0, 1, 2, 3, 10, 20, 33, 100];
+
+#[cfg(not(miri))]
+const TEST_SIZES: [usize; 14] = [0, 1, 2, 3, 10, 16, 17, 20, 33, 50, 100, 500, 2_048, 10_000];
+
+fn comp_result(ordering: Ordering) -> CompResult {
+    CompResult {
+        cmp_result: ordering as i8,
+        is_panic: false,
+    }
+}
+
+unsafe extern "C" fn cmp_trampoline<T, F: FnMut(&T, &T) -> CompResult>(
+    a: &T,
+    b: &T,
+    ctx: *mut u8,
+) -> CompResult {
+    let compare_fn = ctx as *mut F;
+    (*compare_fn)(a, b)
+}
+
+fn sort_by<T, F: FnMut(&T, &T) -> CompResult>(
+    sort_by_fn: SortByFn<T>,
+    v: &mut [T],
+    mut compare: F,
+) -> u32 {
+    unsafe {
+        sort_by_fn(
+            v.as_mut_ptr(),
+            v.len(),
+            cmp_trampoline::<T, F>,
+            &mut compare as *mut F as *mut u8,
+        )
+    }
+}
+
+fn round_trip<T: Ord + Clone + Debug>(
+    sort_fn: SortFn<T>,
+    sort_by_fn: SortByFn<T>,
+    pattern_fn: impl Fn(usize) -> Vec<T>,
+) {
+    for test_len in TEST_SIZES {
+        let test_data = pattern_fn(test_len);
+
+        let mut expected = test_data.clone();
+        expected.sort();
+
+        let mut sorted = test_data.clone();
+        unsafe {
+            sort_fn(sorted.as_mut_ptr(), sorted.len());
+        }
+        assert_eq!(sorted, expected);
+
+        let mut sorted_by = test_data.clone();
+        let ret_code = sort_by(sort_by_fn, &mut sorted_by, |a, b| comp_result(b.cmp(a)));
+        expected.reverse();
+        assert_eq!(ret_code, 0);
+        assert_eq!(sorted_by, expected);
+    }
+}
+
+#[test]
+fn i32() {
+    round_trip::<i32>(ipnsort_i32, ipnsort_i32_by, patterns::random);
+    round_trip::<i32>(ipnsort_i32, ipnsort_i32_by, |size| {
+        patterns::random_uniform(size, 0..4)
+    });
+    round_trip::<i32>(ipnsort_i32, ipnsort_i32_by, patterns::descending);
+}
+
+#[test]
+fn u64() {
+    round_trip::<u64>(ipnsort_u64, ipnsort_u64_by, |size| {
+        patterns::random(size)
+            .into_iter()
+            .map(|val| (val as u64).wrapping_mul(u32::MAX as u64))
+            .collect()
+    });
+}
+
+#[test]
+fn ffi_string() {
+    round_trip::<FFIString>(ipnsort_ffi_string, ipnsort_ffi_string_by, |size| {
+        patterns::random(size)
+            .into_iter()
+            .map(|val| FFIString::new(format!("{}", val)))
+            .collect()
+    });
+}
+
+#[test]
+fn f128() {
+    round_trip::<F128>(ipnsort_f128, ipnsort_f128_by, |size| {
+        patterns::random(size).into_iter().map(F128::new).collect()
+    });
+}
+
+#[test]
+fn one_kilo_byte() {
+    round_trip::<FFIOneKiloByte>(ipnsort_1k, ipnsort_1k_by, |size| {
+        patterns::random(size)
+            .into_iter()
+            .map(FFIOneKiloByte::new)
+            .collect()
+    });
+}
+
+#[test]
+fn null_ptr_empty() {
+    unsafe {
+        ipnsort_i32(std::ptr::null_mut(), 0);
+        ipnsort_ffi_string(std::ptr::null_mut(), 0);
+    }
+}
+
+#[test]
+fn comparison_error_retain_original_set() {
+    for test_len in TEST_SIZES {
+        let test_data = patterns::random(test_len)
+            .into_iter()
+            .map(|val| FFIString::new(format!("{}", val)))
+            .collect::<Vec<_>>();
+
+        let mut expected = test_data.clone();
+        expected.sort();
+
+        let mut required_comps = 0;
+        sort_by(ipnsort_ffi_string_by, &mut test_data.clone(), |a, b| {
+            required_comps += 1;
+            comp_result(a.cmp(b))
+        });
+
+        if required_comps == 0 {
+            continue;
+        }
+
+        let error_threshold = patterns::random_uniform(1, 0..required_comps)[0];
+
+        // Reported panic and invalid `cmp_result`.
+        let invalid_results = [(0, true), (2, false)];
+
+        for (cmp_result, is_panic) in invalid_results {
+            let mut sorted = test_data.clone();
+            let mut comp_counter = 0;
+
+            let ret_code = sort_by(ipnsort_ffi_string_by, &mut sorted, |a, b| {
+                if comp_counter == error_threshold {
+                    return CompResult {
+                        cmp_result,
+                        is_panic,
+                    };
+                }
+                comp_counter += 1;
+
+                comp_result(a.cmp(b))
+            });
+
+            assert_eq!(ret_code, 1);
+
+            // If the elements don't match, it means the set of elements hasn't remained the same.
+            sorted.sort();
+            assert_eq!(sorted, expected);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use core::cmp::Ordering;
+
+use crate::unstable_sort;
+
+/// Returns the permutation that sorts `v`, see [`crate::argsort_by`].
+pub(crate) fn argsort<T, F>(v: &[T], compare: &mut F) -> Vec<usize>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Using `u32` indices halves the memory footprint and increases the number of types that
+    // qualify for the pair sorting and the small-sort sorting-networks.
+    if v.len() <= u32::MAX as usize {
+        T::argsort::<u32, F>(v, compare)
+    } else {
+        T::argsort::<usize, F>(v, compare)
+    }
+}
+
+pub(crate) trait ArgsortIndex: Copy + Ord {
+    fn from_usize(val: usize) -> Self;
+
+    fn to_usize(self) -> usize;
+}
+
+impl ArgsortIndex for u32 {
+    #[inline(always)]
+    fn from_usize(val: usize) -> Self {
+        val as u32
+    }
+
+    #[inline(always)]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+impl ArgsortIndex for usize {
+    #[inline(always)]
+    fn from_usize(val: usize) -> Self {
+        val
+    }
+
+    #[inline(always)]
+    fn to_usize(self) -> usize {
+        self
+    }
+}
+
+pub(crate) trait ArgsortImpl: Sized {
+    /// Returns the permutation that sorts `v`, using `I` to store the indices while sorting.
+    ///
+    /// Equal elements are ordered by their index, which makes the result unique and identical to
+    /// the permutation a stable sort would produce.
+    fn argsort<I, F>(v: &[Self], compare: &mut F) -> Vec<usize>
+    where
+        I: ArgsortIndex,
+        F: FnMut(&Self, &Self) -> Ordering;
+}
+
+// See `crate::specialize` for the type specific implementations.
+#[cfg(feature = "stable")]
+impl<T> ArgsortImpl for T {
+    fn argsort<I, F>(v: &[T], compare: &mut F) -> Vec<usize>
+    where
+        I: ArgsortIndex,
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        argsort_indirect::<T, I, F>(v, compare)
+    }
+}
+
+/// Sorts the indices and looks up the elements they refer to for each comparison.
+pub(crate) fn argsort_indirect<T, I, F>(v: &[T], compare: &mut F) -> Vec<usize>
+where
+    I: ArgsortIndex,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut indices = (0..v.len()).map(I::from_usize).collect::<Vec<_>>();
+
+    // SAFETY: All indices are in-bounds by construction.
+    unstable_sort(&mut indices, |a, b| unsafe {
+        compare(v.get_unchecked(a.to_usize()), v.get_unchecked(b.to_usize())).then(a.cmp(b))
+            == Ordering::Less
+    });
+
+    indices.into_iter().map(I::to_usize).collect()
+}
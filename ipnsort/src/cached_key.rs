@@ -0,0 +1,52 @@
+use crate::argsort::ArgsortIndex;
+use crate::unstable_sort;
+
+/// Sorts `v` by the keys `f` returns, calling it once for each element, see
+/// [`crate::sort_by_cached_key`].
+pub(crate) fn sort_by_cached_key<T, K, F>(v: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    if v.len() < 2 {
+        return;
+    }
+
+    // See `crate::argsort::argsort`, `u32` indices make the pairs smaller.
+    if v.len() <= u32::MAX as usize {
+        sort_by_cached_key_impl::<T, K, F, u32>(v, f);
+    } else {
+        sort_by_cached_key_impl::<T, K, F, usize>(v, f);
+    }
+}
+
+fn sort_by_cached_key_impl<T, K, F, I>(v: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+    I: ArgsortIndex,
+{
+    let mut pairs = v
+        .iter()
+        .map(f)
+        .enumerate()
+        .map(|(i, key)| (key, I::from_usize(i)))
+        .collect::<Vec<_>>();
+
+    // The indices are unique, so sorting the pairs is equivalent to a stable sort of the keys. The
+    // pairs get the small-sort specialized for their type, e.g. sorting-networks for integer keys.
+    unstable_sort(&mut pairs, |a, b| a.lt(b));
+
+    // Apply the permutation in place. `pairs[i].1` is the original position of the element that
+    // belongs at `i`. If that position is less than `i`, the element was already swapped away, to
+    // the position the pair at that index points to, which is followed until it is not less than
+    // `i`. The result is stored back, to shorten the chains for later positions.
+    for i in 0..v.len() {
+        let mut index = pairs[i].1;
+        while index.to_usize() < i {
+            index = pairs[index.to_usize()].1;
+        }
+        pairs[i].1 = index;
+        v.swap(i, index.to_usize());
+    }
+}
@@ -0,0 +1,110 @@
+use core::cmp::Ordering;
+
+use crate::argsort::ArgsortIndex;
+use crate::unstable_sort;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Tuples of mutable slices that can be sorted with [`crate::sort_columns`], implemented for one
+/// to six columns whose elements implement [`Ord`].
+pub trait Columns: private::Sealed {
+    /// Returns the length of the columns, which must all be the same.
+    #[doc(hidden)]
+    fn rows(&self) -> usize;
+
+    /// Compares row `a` and `b` column by column.
+    #[doc(hidden)]
+    fn compare_rows(&self, a: usize, b: usize) -> Ordering;
+
+    /// Swaps row `a` and `b` in every column.
+    #[doc(hidden)]
+    fn swap_rows(&mut self, a: usize, b: usize);
+}
+
+macro_rules! columns_impl {
+    ($($col:ident $idx:tt),+) => {
+        impl<$($col: Ord),+> private::Sealed for ($(&mut [$col],)+) {}
+
+        impl<$($col: Ord),+> Columns for ($(&mut [$col],)+) {
+            fn rows(&self) -> usize {
+                let len = self.0.len();
+                $(
+                    let col_len = self.$idx.len();
+                    if col_len != len {
+                        panic!(
+                            "sort_columns column {} has length {col_len}, column 0 has length {len}",
+                            $idx
+                        );
+                    }
+                )+
+                len
+            }
+
+            #[inline]
+            fn compare_rows(&self, a: usize, b: usize) -> Ordering {
+                Ordering::Equal $(.then_with(|| self.$idx[a].cmp(&self.$idx[b])))+
+            }
+
+            #[inline]
+            fn swap_rows(&mut self, a: usize, b: usize) {
+                $(self.$idx.swap(a, b);)+
+            }
+        }
+    };
+}
+
+columns_impl!(A 0);
+columns_impl!(A 0, B 1);
+columns_impl!(A 0, B 1, C 2);
+columns_impl!(A 0, B 1, C 2, D 3);
+columns_impl!(A 0, B 1, C 2, D 3, E 4);
+columns_impl!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// Sorts the rows `0..len` of `columns` with `compare` and moves them into place with `swap`, see
+/// [`crate::sort_columns_by`].
+pub(crate) fn sort_rows<T, C, S>(columns: &mut T, len: usize, compare: &mut C, swap: &mut S)
+where
+    C: FnMut(&T, usize, usize) -> Ordering,
+    S: FnMut(&mut T, usize, usize),
+{
+    if len < 2 {
+        return;
+    }
+
+    // See `crate::argsort::argsort`.
+    if len <= u32::MAX as usize {
+        sort_rows_impl::<T, C, S, u32>(columns, len, compare, swap);
+    } else {
+        sort_rows_impl::<T, C, S, usize>(columns, len, compare, swap);
+    }
+}
+
+fn sort_rows_impl<T, C, S, I>(columns: &mut T, len: usize, compare: &mut C, swap: &mut S)
+where
+    C: FnMut(&T, usize, usize) -> Ordering,
+    S: FnMut(&mut T, usize, usize),
+    I: ArgsortIndex,
+{
+    // Rows that compare equal are ordered by their index, so the permutation is unique and the same
+    // one a stable sort would produce. The rows are not moved before the permutation is complete,
+    // so a panic in `compare` leaves them unchanged.
+    let mut indices = (0..len).map(I::from_usize).collect::<Vec<_>>();
+    unstable_sort(&mut indices, |a, b| {
+        compare(columns, a.to_usize(), b.to_usize()).then(a.cmp(b)) == Ordering::Less
+    });
+
+    // Apply the permutation with swaps, see `crate::cached_key`.
+    for i in 0..len {
+        let mut index = indices[i];
+        while index.to_usize() < i {
+            index = indices[index.to_usize()];
+        }
+        indices[i] = index;
+
+        if index.to_usize() != i {
+            swap(columns, i, index.to_usize());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use core::mem;
+use core::ops::Range;
+use core::slice;
+
+use crate::{find_streak, strict, trace};
+
+/// Sorts `v` and removes consecutive duplicates, see [`crate::sort_dedup_by`].
+pub(crate) fn sort_dedup<T, F>(v: &mut Vec<T>, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let runs = sort_equal_runs(v, is_less);
+    let mut known_equal = KnownEqual::new(&runs);
+
+    // `v[..kept]` are the unique elements found so far, `v[kept..i]` the duplicates of them.
+    let mut kept = 1;
+    for i in 1..v.len() {
+        // Elements in a run are equal to their predecessor without comparing them. Otherwise, the
+        // slice is sorted, so `v[i]` is a duplicate if it's not greater than the last unique one.
+        if known_equal.contains(i) || !is_less(&v[kept - 1], &v[i]) {
+            continue;
+        }
+
+        v.swap(kept, i);
+        kept += 1;
+    }
+
+    v.truncate(kept);
+}
+
+/// Sorts `v` and counts the elements in each group of equal elements, see
+/// [`crate::sort_count_by`].
+pub(crate) fn sort_count<T, F>(v: &mut [T], is_less: &mut F) -> Vec<(T, usize)>
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> bool,
+{
+    let runs = sort_equal_runs(v, is_less);
+    let mut known_equal = KnownEqual::new(&runs);
+
+    let mut counts = Vec::new();
+    let mut group_start = 0;
+    for i in 1..v.len() {
+        if known_equal.contains(i) || !is_less(&v[i - 1], &v[i]) {
+            continue;
+        }
+
+        counts.push((v[group_start].clone(), i - group_start));
+        group_start = i;
+    }
+
+    if let Some(first) = v.get(group_start) {
+        counts.push((first.clone(), v.len() - group_start));
+    }
+
+    counts
+}
+
+/// Sorts `v` like [`crate::sort_by`] and returns the runs of equal elements the quicksort found
+/// on the way, see the `on_equal_run` parameter of [`crate::quicksort::quicksort`]. Elements
+/// outside of the runs have to be compared to find out whether they are equal to their neighbors.
+fn sort_equal_runs<T, F>(v: &mut [T], is_less: &mut F) -> Vec<Range<usize>>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut runs = Vec::new();
+    let len = v.len();
+
+    // Sorting has no meaningful behavior on zero-sized types.
+    if mem::size_of::<T>() == 0 || len < 2 {
+        return runs;
+    }
+
+    let (streak_end, was_reversed) = find_streak(v, is_less);
+    trace::event!(streak(len, streak_end, was_reversed));
+    if streak_end == len {
+        if was_reversed {
+            v.reverse();
+        }
+
+        return runs;
+    }
+
+    // Limit the number of imbalanced partitions to `2 * floor(log2(len))`.
+    // The binary OR by one is used to eliminate the zero-check in the logarithm.
+    let limit = 2 * (len | 1).ilog2();
+
+    let origin = v.as_ptr();
+    crate::quicksort::quicksort(v, &mut [], is_less, None, limit, None, origin, &mut |run| {
+        runs.push(run)
+    });
+
+    if cfg!(feature = "strict") {
+        strict::check_sorted(v, origin, is_less);
+    }
+
+    runs
+}
+
+/// Answers whether a position lies inside one of the runs, behind its first element. The positions
+/// have to be asked for in ascending order.
+struct KnownEqual<'a> {
+    runs: slice::Iter<'a, Range<usize>>,
+    current: Range<usize>,
+}
+
+impl<'a> KnownEqual<'a> {
+    fn new(runs: &'a [Range<usize>]) -> Self {
+        Self {
+            runs: runs.iter(),
+            current: 0..0,
+        }
+    }
+
+    #[inline(always)]
+    fn contains(&mut self, pos: usize) -> bool {
+        while self.current.end <= pos {
+            match self.runs.next() {
+                Some(run) => self.current = run.clone(),
+                None => return false,
+            }
+        }
+
+        self.current.start < pos
+    }
+}
@@ -0,0 +1,60 @@
+use core::mem;
+use core::slice;
+
+use crate::radix;
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+/// Floating point types that can be sorted with [`crate::sort_floats`], implemented for [`f32`]
+/// and [`f64`].
+pub trait Float: private::Sealed + Sized {
+    #[doc(hidden)]
+    fn sort_total_order(v: &mut [Self]);
+}
+
+macro_rules! float_impl {
+    ($float:ty, $int:ty, $uint:ty) => {
+        impl Float for $float {
+            fn sort_total_order(v: &mut [Self]) {
+                const {
+                    assert!(mem::size_of::<$float>() == mem::size_of::<$int>());
+                    assert!(mem::align_of::<$float>() == mem::align_of::<$int>());
+                }
+
+                // Same as `total_cmp`, flipping all bits except the sign bit of negative values
+                // yields a two's complement integer with the same order as the IEEE 754
+                // totalOrder predicate. Doing so twice restores the original value.
+                #[inline(always)]
+                fn flip(val: $int) -> $int {
+                    val ^ ((((val >> (<$int>::BITS - 1)) as $uint) >> 1) as $int)
+                }
+
+                // SAFETY: Both types have the same size and alignment, and every bit pattern is a
+                // valid value for both of them.
+                let ints =
+                    unsafe { slice::from_raw_parts_mut(v.as_mut_ptr() as *mut $int, v.len()) };
+
+                for val in ints.iter_mut() {
+                    *val = flip(*val);
+                }
+
+                // Sorted like `sort` sorts integers, with radix sort for long slices and otherwise
+                // the sorting-network small-sort and branchless partition. Comparing integers
+                // can't panic, so `v` can't be left behind with transformed values.
+                <$int as radix::SortImpl>::sort(ints);
+
+                for val in ints.iter_mut() {
+                    *val = flip(*val);
+                }
+            }
+        }
+    };
+}
+
+float_impl!(f32, i32, u32);
+float_impl!(f64, i64, u64);
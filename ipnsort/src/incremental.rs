@@ -0,0 +1,255 @@
+use core::cell::Cell;
+use core::cmp::Ordering;
+use core::mem;
+
+use crate::smallsort::SmallSortImpl;
+use crate::trace;
+
+/// The result of [`IncrementalSort::step`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// The slice is sorted.
+    Done,
+    /// The budget was used up before the slice was sorted, call [`IncrementalSort::step`] again to
+    /// continue.
+    Pending,
+}
+
+/// Sorts a slice in steps of a given number of comparisons, for callers that can't block until a
+/// large slice is sorted, e.g. a game loop or an async task. The sort can be continued any time
+/// later, or dropped.
+///
+/// The result is the same as with [`crate::sort_by`], it is unstable (i.e., may reorder equal
+/// elements), and *O*(*n* \* log(*n*)) worst-case. The slice can't be accessed until the sort is
+/// dropped, as it is in an unspecified order in between.
+///
+/// # Current implementation
+///
+/// The same quicksort as [`crate::sort_by`], but with an explicit stack of the sub-slices that
+/// are still to be sorted instead of recursion, which allocates. Partitions are done in chunks
+/// that fit into the budget. Choosing a pivot, small-sorting and the heapsort fallback are done in
+/// one go, so a step can exceed its budget by about the square root of the length of the largest
+/// sub-slice, or rarely by a heapsort. Unlike [`crate::sort`], it neither detects presorted prefixes
+/// and suffixes, nor radix sorts integers.
+///
+/// If the comparison panics, the slice keeps all of its elements, in an unspecified order. Further
+/// steps leave it in an unspecified order as well.
+///
+/// # Examples
+///
+/// ```
+/// use ipnsort::{IncrementalSort, Step};
+///
+/// let mut v: Vec<u32> = (0..100_000).map(|i| i.wrapping_mul(2654435761)).collect();
+///
+/// let mut sort = IncrementalSort::new(&mut v);
+/// let mut steps = 1;
+/// while sort.step(10_000) == Step::Pending {
+///     steps += 1;
+/// }
+///
+/// assert!(steps > 100);
+/// assert!(v.windows(2).all(|w| w[0] <= w[1]));
+/// ```
+pub struct IncrementalSort<'a, T, F = fn(&T, &T) -> Ordering> {
+    v: &'a mut [T],
+    compare: F,
+    stack: Vec<Task>,
+}
+
+/// A pending part of the sort. Positions are relative to the start of the whole slice.
+enum Task {
+    /// `v[start..end]` has to be sorted. `ancestor_pivot` is the position of its predecessor, if
+    /// it was a pivot, see [`crate::quicksort::quicksort`].
+    Sort {
+        start: usize,
+        end: usize,
+        ancestor_pivot: Option<usize>,
+        limit: u32,
+    },
+    /// `v[start..end]` is being partitioned around the pivot in `v[start]`. Of `v[start + 1..next]`
+    /// the elements before `lt_end` are the ones that compare less than the pivot, or not greater
+    /// than it if `equal`, see [`crate::quicksort::quicksort`].
+    Partition {
+        start: usize,
+        end: usize,
+        next: usize,
+        lt_end: usize,
+        equal: bool,
+        ancestor_pivot: Option<usize>,
+        limit: u32,
+    },
+}
+
+impl<'a, T: Ord> IncrementalSort<'a, T> {
+    /// Prepares sorting `v` in the order of [`Ord`], without comparing any elements yet.
+    pub fn new(v: &'a mut [T]) -> Self {
+        Self::new_by(v, T::cmp)
+    }
+}
+
+impl<'a, T, F> IncrementalSort<'a, T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    /// Prepares sorting `v` with the comparator function `compare`, which must define a total
+    /// order, see [`crate::sort_by`]. No elements are compared yet.
+    pub fn new_by(v: &'a mut [T], compare: F) -> Self {
+        let len = v.len();
+        let mut stack = Vec::new();
+
+        // Sorting has no meaningful behavior on zero-sized types.
+        if len >= 2 && mem::size_of::<T>() != 0 {
+            // See `crate::quicksort`.
+            let limit = 2 * (len | 1).ilog2();
+            stack.reserve(limit as usize + 1);
+            stack.push(Task::Sort {
+                start: 0,
+                end: len,
+                ancestor_pivot: None,
+                limit,
+            });
+        }
+
+        Self { v, compare, stack }
+    }
+
+    /// Continues sorting until about `budget` comparisons are done, or the slice is sorted.
+    ///
+    /// Returns [`Step::Done`] once the slice is sorted, further calls do nothing. A budget of zero
+    /// does nothing either.
+    pub fn step(&mut self, budget: usize) -> Step {
+        let Self { v, compare, stack } = self;
+
+        let comparisons = Cell::new(0);
+        let is_less = &mut |a: &T, b: &T| {
+            comparisons.set(comparisons.get() + 1);
+            compare(a, b) == Ordering::Less
+        };
+
+        while comparisons.get() < budget {
+            let Some(task) = stack.pop() else {
+                break;
+            };
+
+            match task {
+                Task::Sort {
+                    start,
+                    end,
+                    ancestor_pivot,
+                    limit,
+                } => {
+                    let sub = &mut v[start..end];
+
+                    if sub.len() <= T::SMALL_SORT_THRESHOLD {
+                        trace::event!(small_sort(sub.len()));
+                        T::small_sort(sub, is_less);
+                        continue;
+                    }
+
+                    // See `crate::quicksort::quicksort`.
+                    if limit == 0 {
+                        trace::event!(heapsort(sub.len()));
+                        // SAFETY: We checked that `sub` is longer than the small-sort threshold,
+                        // which is at least 1.
+                        unsafe {
+                            crate::heapsort::heapsort(sub, is_less);
+                        }
+                        continue;
+                    }
+
+                    let pivot_pos = crate::pivot::choose_pivot(sub, is_less);
+                    trace::event!(pivot(sub.len(), pivot_pos));
+                    let pivot_pos = start + pivot_pos;
+
+                    let equal = ancestor_pivot.is_some_and(|p| !is_less(&v[p], &v[pivot_pos]));
+                    v.swap(start, pivot_pos);
+
+                    stack.push(Task::Partition {
+                        start,
+                        end,
+                        next: start + 1,
+                        lt_end: start + 1,
+                        equal,
+                        ancestor_pivot,
+                        limit: limit - 1,
+                    });
+                }
+                Task::Partition {
+                    start,
+                    end,
+                    mut next,
+                    mut lt_end,
+                    equal,
+                    ancestor_pivot,
+                    limit,
+                } => {
+                    // A branchless Lomuto partition, it can be paused after any element.
+                    let chunk_end = end.min(next.saturating_add(budget - comparisons.get()));
+                    while next < chunk_end {
+                        let pivot = &v[start];
+                        let elem = &v[next];
+                        let is_lt = if equal {
+                            !is_less(pivot, elem)
+                        } else {
+                            is_less(elem, pivot)
+                        };
+
+                        v.swap(lt_end, next);
+                        lt_end += is_lt as usize;
+                        next += 1;
+                    }
+
+                    if next < end {
+                        stack.push(Task::Partition {
+                            start,
+                            end,
+                            next,
+                            lt_end,
+                            equal,
+                            ancestor_pivot,
+                            limit,
+                        });
+                        continue;
+                    }
+
+                    // Place the pivot between the two partitions.
+                    let mid = lt_end - 1;
+                    v.swap(start, mid);
+
+                    if equal {
+                        trace::event!(equal_partition(end - start, mid - start));
+                        // The elements equal to the pivot are in their final position.
+                        stack.push(Task::Sort {
+                            start: mid + 1,
+                            end,
+                            ancestor_pivot: None,
+                            limit,
+                        });
+                    } else {
+                        trace::event!(partition(end - start, mid - start));
+                        // The left side is sorted first, like the recursion of `quicksort`.
+                        stack.push(Task::Sort {
+                            start: mid + 1,
+                            end,
+                            ancestor_pivot: Some(mid),
+                            limit,
+                        });
+                        stack.push(Task::Sort {
+                            start,
+                            end: mid,
+                            ancestor_pivot,
+                            limit,
+                        });
+                    }
+                }
+            }
+        }
+
+        if stack.is_empty() {
+            Step::Done
+        } else {
+            Step::Pending
+        }
+    }
+}
@@ -1,27 +1,54 @@
 //! Instruction-Parallel-Network Unstable Sort, ipnsort by Lukas Bergdoll
-#![allow(incomplete_features)]
-#![feature(
-    ptr_sub_ptr,
-    maybe_uninit_slice,
-    auto_traits,
-    negative_impls,
-    specialization,
-    const_trait_impl,
-    inline_const,
-    core_intrinsics,
-    sized_type_properties,
-    generic_const_exprs
+#![allow(incomplete_features, internal_features)]
+#![cfg_attr(
+    not(feature = "stable"),
+    feature(
+        auto_traits,
+        negative_impls,
+        specialization,
+        const_trait_impl,
+        core_intrinsics,
+        generic_const_exprs
+    )
 )]
 
 use core::cmp::Ordering;
+#[cfg(not(feature = "stable"))]
 use core::intrinsics;
-use core::mem::{self, ManuallyDrop, SizedTypeProperties};
+use core::mem::{self, ManuallyDrop, MaybeUninit};
 use core::ptr;
 
+use pivot::PivotRng;
+
+mod argsort;
+mod cached_key;
+mod columns;
+mod dedup;
+mod float;
 mod heapsort;
+mod incremental;
+mod merge;
+mod network;
+mod parallel;
 mod pivot;
 mod quicksort;
+mod radix;
+mod select;
 mod smallsort;
+mod strict;
+mod strings;
+mod trace;
+// The nightly-only parts live in their own module, because the stable parser rejects some of the
+// syntax even if the items are disabled.
+#[cfg(not(feature = "stable"))]
+mod specialize;
+
+pub use columns::Columns;
+pub use float::Float;
+pub use incremental::{IncrementalSort, Step};
+pub use parallel::ParConfig;
+#[cfg(feature = "trace")]
+pub use trace::{with_observer, JsonLines, SortObserver};
 
 /// Sorts the slice, but might not preserve the order of equal elements.
 ///
@@ -39,6 +66,16 @@ mod smallsort;
 /// It is typically faster than stable sorting, except in a few special cases, e.g., when the
 /// slice consists of several concatenated sorted sequences.
 ///
+/// Slices of at least 1024 primitive integers, [`u8`] to [`u64`] and [`i8`] to [`i64`], are
+/// sorted with in-place MSD radix sort instead, unless they start or end with a long sorted
+/// streak or the difference between the smallest and largest value doesn't fit into 32 bits.
+/// This requires the `stable` feature to be disabled.
+///
+/// # Panics
+///
+/// May panic if the implementation of [`Ord`] for `T` does not define a total order. With the
+/// `strict` feature enabled it panics whenever the sort detects this, see [`sort_by`].
+///
 /// # Examples
 ///
 /// ```
@@ -54,7 +91,7 @@ pub fn sort<T>(arr: &mut [T])
 where
     T: Ord,
 {
-    unstable_sort(arr, |a, b| a.lt(b));
+    <T as radix::SortImpl>::sort(arr);
 }
 
 /// Sorts the slice with a comparator function, but might not preserve the order of equal
@@ -88,7 +125,24 @@ where
 /// deterministic behavior.
 ///
 /// It is typically faster than stable sorting, except in a few special cases, e.g., when the
-/// slice consists of several concatenated sorted sequences.
+/// slice consists of several concatenated sorted sequences. If at least half of the slice is a
+/// sorted or reversed prefix or suffix, only the rest is quicksorted and then merged with it, using
+/// a fixed-size stack buffer and rotations.
+///
+/// # Panics
+///
+/// May panic if the ordering is not total. With the `strict` feature enabled, the sort checks
+/// the comparator for contradicting answers while partitioning, small-sorting and heapsorting, and
+/// checks the order of the result before returning. Instead of returning elements the comparator
+/// considers out of order, it panics with a message naming the indices of the offending elements.
+/// Violations the small-sort runs into while merging are reported without indices.
+/// These checks cost about `2 * arr.len()` additional comparisons. The same applies to
+/// [`sort_with_scratch`] and [`partial_sort_by`].
+///
+/// The indices name elements of the slice that is being sorted. [`argsort_by`],
+/// [`sort_columns_by`], [`sort_by_cached_key`] and [`sort_strings`] sort an internal buffer of
+/// indices, keys or cached prefixes instead of the caller's slice, the indices they report are
+/// positions in that buffer.
 ///
 /// # Examples
 ///
@@ -111,16 +165,917 @@ where
     unstable_sort(arr, |a, b| compare(a, b) == Ordering::Less);
 }
 
+/// Sorts the slice with a key extraction function, but might not preserve the order of equal
+/// elements.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate),
+/// and *O*(*m* \* *n* \* log(*n*)) worst-case, where the key function is *O*(*m*).
+///
+/// The key function is called twice for every comparison. For expensive key functions, e.g.
+/// functions that allocate, [`sort_by_cached_key`] is likely to be faster.
+///
+/// # Current implementation
+///
+/// Same as [`sort_by`], comparing the keys with [`Ord`].
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5i32, 4, 1, -3, 2];
+///
+/// ipnsort::sort_by_key(&mut v, |k| k.abs());
+/// assert!(v == [1, 2, -3, 4, -5]);
+/// ```
+#[inline(always)]
+pub fn sort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    unstable_sort(arr, |a, b| f(a).lt(&f(b)));
+}
+
+/// Sorts the slice with a key extraction function, calling it only once per element.
+///
+/// This sort is stable (i.e., does not reorder equal elements), and *O*(*m* \* *n* + *n* \*
+/// log(*n*)) worst-case, where the key function is *O*(*m*). It allocates a buffer of
+/// `arr.len()` (key, index) pairs.
+///
+/// For simple key functions, e.g. functions that are property accesses or basic operations,
+/// [`sort_by_key`] is likely to be faster.
+///
+/// # Current implementation
+///
+/// Same as [`slice::sort_by_cached_key`], the keys are computed into a buffer of (key, index)
+/// pairs, which are sorted like [`sort_by`] would, before the elements are permuted into place.
+/// The indices are stored as `u32` if `arr.len()` allows for it. The pairs are sorted with the
+/// small-sort specialized for their type, and because the indices are unique, the result is
+/// stable.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5i32, 4, 32, -3, 2];
+///
+/// ipnsort::sort_by_cached_key(&mut v, |k| k.to_string());
+/// assert!(v == [-3, -5, 2, 32, 4]);
+/// ```
+#[inline]
+pub fn sort_by_cached_key<T, K, F>(arr: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    cached_key::sort_by_cached_key(arr, f);
+}
+
+/// Sorts the slice with a comparison function, using `scratch` as temporary memory, but might not
+/// preserve the order of equal elements.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate),
+/// and *O*(*n* \* log(*n*)) worst-case.
+///
+/// `is_less(a, b)` returns `true` if `a` is ordered before `b`. It must define a strict total order
+/// for the elements in the slice, see [`sort_by`] for the requirements.
+///
+/// `scratch` may have any length, the content of it after the call is unspecified.
+///
+/// # Current implementation
+///
+/// Same as [`sort_by`], but sub-slices that are small enough to be small-sorted and fit into
+/// `scratch`, with 16 elements to spare, are sorted with merges using `scratch`. This increases the
+/// small-sort length from 20 to 32 for types that are larger than `u64` or not `Copy`, if `scratch`
+/// holds at least 48 elements. Smaller buffers are still used in place of the stack buffer of the
+/// small-sort, and an empty buffer makes this equivalent to [`sort_by`]. The small-sort never uses
+/// the buffer for types with interior mutability, or when the `stable` feature is enabled.
+/// Merging a presorted prefix or suffix uses `scratch` for all types, if it is larger than the
+/// stack buffer of the merge.
+///
+/// # Examples
+///
+/// ```
+/// use std::mem::MaybeUninit;
+///
+/// let mut v = [5, 4, 1, 3, 2];
+/// let mut scratch = [MaybeUninit::uninit(); 48];
+///
+/// ipnsort::sort_with_scratch(&mut v, &mut scratch, |a, b| a < b);
+/// assert!(v == [1, 2, 3, 4, 5]);
+/// ```
+#[inline(always)]
+pub fn sort_with_scratch<T, F>(arr: &mut [T], scratch: &mut [MaybeUninit<T>], is_less: F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    unstable_sort_with_scratch::<T, F, false>(arr, scratch, None, is_less);
+}
+
+/// Same as [`sort`], but partitions with two pivots instead of one.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate),
+/// and *O*(*n* \* log(*n*)) worst-case.
+///
+/// # Current implementation
+///
+/// Detects and merges sorted prefixes and suffixes like [`sort_by`], but sorts the rest with a
+/// dual-pivot quicksort. The pivots are the tertiles of a sample, and a branchless partition splits
+/// each slice into the elements less than the smaller pivot, the ones between the pivots, and the
+/// ones greater than the larger pivot. Compared to one pivot, this takes fewer passes over the
+/// data, but more comparisons and moves per element. It exists to compare the two designs and
+/// doesn't use the radix sort of [`sort`] for integers.
+///
+/// # Panics
+///
+/// May panic if the implementation of [`Ord`] for `T` does not define a total order, see
+/// [`sort_by`].
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5, 4, 1, -3, 2];
+///
+/// ipnsort::sort_dual_pivot(&mut v);
+/// assert!(v == [-5, -3, 1, 2, 4]);
+/// ```
+#[inline(always)]
+pub fn sort_dual_pivot<T>(arr: &mut [T])
+where
+    T: Ord,
+{
+    unstable_sort_with_scratch::<T, _, true>(arr, &mut [], None, T::lt);
+}
+
+/// Same as [`sort_by`], but partitions with two pivots instead of one, see [`sort_dual_pivot`].
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [5, 4, 1, 3, 2];
+///
+/// ipnsort::sort_by_dual_pivot(&mut v, |a, b| b.cmp(a));
+/// assert!(v == [5, 4, 3, 2, 1]);
+/// ```
+#[inline(always)]
+pub fn sort_by_dual_pivot<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    unstable_sort_with_scratch::<T, _, true>(arr, &mut [], None, |a, b| {
+        compare(a, b) == Ordering::Less
+    });
+}
+
+/// Same as [`sort`], but the pivots are sampled from random positions, drawn from `seed`.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate),
+/// and *O*(*n* \* log(*n*)) worst-case.
+///
+/// [`sort`] samples the pivots from fixed positions. Someone who controls the input can arrange
+/// the elements at those positions such that every pivot splits off only a few elements, which
+/// uses up the limit of imbalanced partitions and makes the sort fall back to heapsort. That
+/// keeps the worst case at *O*(*n* \* log(*n*)), but heapsort is several times slower. Sampling
+/// from positions that depend on `seed` makes such inputs as fast to sort as random ones, as long
+/// as the seed is unknown to whoever builds them.
+///
+/// If `seed` is `None`, it is derived from the addresses of `arr` and of the stack. That is cheap,
+/// but only unpredictable with address space layout randomization. Passing a seed from a proper
+/// entropy source is preferable for inputs from untrusted sources. A fixed seed gives the same
+/// result on every run.
+///
+/// # Current implementation
+///
+/// Same as [`sort_by`], only the positions of the pivot samples differ. Integers are sorted by
+/// comparisons as well, the radix sort of [`sort`] has no pivots an input could be arranged
+/// against.
+///
+/// # Panics
+///
+/// May panic if the implementation of [`Ord`] for `T` does not define a total order, see
+/// [`sort_by`].
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5, 4, 1, -3, 2];
+///
+/// ipnsort::sort_randomized(&mut v, Some(0x5eed));
+/// assert!(v == [-5, -3, 1, 2, 4]);
+/// ```
+#[inline(always)]
+pub fn sort_randomized<T>(arr: &mut [T], seed: Option<u64>)
+where
+    T: Ord,
+{
+    sort_by_randomized(arr, seed, T::cmp);
+}
+
+/// Same as [`sort_by`], but the pivots are sampled from random positions, drawn from `seed`, see
+/// [`sort_randomized`].
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [5, 4, 1, 3, 2];
+///
+/// ipnsort::sort_by_randomized(&mut v, None, |a, b| b.cmp(a));
+/// assert!(v == [5, 4, 3, 2, 1]);
+/// ```
+#[inline(always)]
+pub fn sort_by_randomized<T, F>(arr: &mut [T], seed: Option<u64>, mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut rng = match seed {
+        Some(seed) => PivotRng::new(seed),
+        None => PivotRng::from_address(arr),
+    };
+    unstable_sort_with_scratch::<T, _, false>(arr, &mut [], Some(&mut rng), |a, b| {
+        compare(a, b) == Ordering::Less
+    });
+}
+
+/// Partially sorts the slice, such that `arr[..k]` contains the `k` smallest elements in sorted
+/// order. The order of the remaining elements `arr[k..]` is unspecified.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate),
+/// and *O*(*n* + *k* \* log(*k*)) worst-case.
+///
+/// # Current implementation
+///
+/// The current algorithm is the same quicksort that is used by [`sort`], but it skips all
+/// partitions that lie entirely beyond `k`. Calling it with `k == arr.len()` is equivalent to
+/// calling [`sort`].
+///
+/// # Panics
+///
+/// Panics when `k > arr.len()`.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5, 4, 1, -3, 2];
+///
+/// ipnsort::partial_sort(&mut v, 3);
+/// assert!(v[..3] == [-5, -3, 1]);
+/// ```
+#[inline(always)]
+pub fn partial_sort<T>(arr: &mut [T], k: usize)
+where
+    T: Ord,
+{
+    unstable_partial_sort(arr, k, |a, b| a.lt(b));
+}
+
+/// Partially sorts the slice with a comparator function, such that `arr[..k]` contains the `k`
+/// smallest elements in sorted order. The order of the remaining elements `arr[k..]` is
+/// unspecified.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate),
+/// and *O*(*n* + *k* \* log(*k*)) worst-case.
+///
+/// The comparator function must define a total ordering for the elements in the slice. If the
+/// ordering is not total, the order of the elements is unspecified, but all original elements
+/// remain in the slice. The same is true if the comparator function panics.
+///
+/// # Panics
+///
+/// Panics when `k > arr.len()`.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [5, 4, 1, 3, 2];
+///
+/// // The two largest elements in descending order.
+/// ipnsort::partial_sort_by(&mut v, 2, |a, b| b.cmp(a));
+/// assert!(v[..2] == [5, 4]);
+/// ```
+#[inline(always)]
+pub fn partial_sort_by<T, F>(arr: &mut [T], k: usize, mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    unstable_partial_sort(arr, k, |a, b| compare(a, b) == Ordering::Less);
+}
+
+/// Reorders the slice such that the element at `index` after the reordering is at its final
+/// sorted position.
+///
+/// This reordering has the additional property that any value at position `i < index` will be
+/// less than or equal to any value at a position `j > index`. Additionally, this reordering is
+/// unstable (i.e. any number of equal elements may end up at position `index`), in-place (i.e.
+/// does not allocate), and runs in *O*(*n*) time. This function is also known as "kth element"
+/// in other libraries.
+///
+/// It returns a triplet of the following from the reordered slice: the subslice prior to
+/// `index`, the element at `index`, and the subslice after `index`; accordingly, the values in
+/// those two subslices will respectively all be less-than-or-equal-to and
+/// greater-than-or-equal-to the value of the element at `index`.
+///
+/// # Current implementation
+///
+/// The current algorithm is an introselect implementation based on ipnsort, which is also the
+/// basis for [`sort`]. It shares the pivot selection, partition and small-sort code with it. If
+/// too many bad pivot choices are made, the algorithm falls back to a median of medians
+/// selection, which guarantees *O*(*n*) worst-case.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5i32, 4, 2, -3, 1];
+///
+/// // Find the items less than or equal to the median, the median, and greater than or equal to
+/// // the median.
+/// let (lesser, median, greater) = ipnsort::select_nth_unstable(&mut v, 2);
+///
+/// assert!(lesser == [-3, -5] || lesser == [-5, -3]);
+/// assert_eq!(median, &mut 1);
+/// assert!(greater == [4, 2] || greater == [2, 4]);
+/// ```
+#[inline]
+pub fn select_nth_unstable<T>(arr: &mut [T], index: usize) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Ord,
+{
+    select::partition_at_index(arr, index, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` after the
+/// reordering is at its final sorted position.
+///
+/// This reordering has the additional property that any value at position `i < index` will be
+/// less than or equal to any value at a position `j > index` using the comparator function.
+/// Additionally, this reordering is unstable (i.e. any number of equal elements may end up at
+/// position `index`), in-place (i.e. does not allocate), and runs in *O*(*n*) time.
+///
+/// The comparator function must define a total ordering for the elements in the slice. If the
+/// ordering is not total, the order of the elements is unspecified, but all original elements
+/// remain in the slice. The same is true if the comparator function panics.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5i32, 4, 2, -3, 1];
+///
+/// // Find the items less than or equal to the median, the median, and greater than or equal to
+/// // the median as if the slice were sorted in descending order.
+/// let (lesser, median, greater) = ipnsort::select_nth_unstable_by(&mut v, 2, |a, b| b.cmp(a));
+///
+/// assert!(lesser == [4, 2] || lesser == [2, 4]);
+/// assert_eq!(median, &mut 1);
+/// assert!(greater == [-3, -5] || greater == [-5, -3]);
+/// ```
+#[inline]
+pub fn select_nth_unstable_by<T, F>(
+    arr: &mut [T],
+    index: usize,
+    mut compare: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select::partition_at_index(arr, index, |a: &T, b: &T| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a key extraction function such that the element at `index` after the
+/// reordering is at its final sorted position.
+///
+/// This reordering has the additional property that any value at position `i < index` will be
+/// less than or equal to any value at a position `j > index` using the key extraction function.
+/// Additionally, this reordering is unstable (i.e. any number of equal elements may end up at
+/// position `index`), in-place (i.e. does not allocate), and runs in *O*(*n*) time.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5i32, 4, 1, -3, 2];
+///
+/// // Find the items less than or equal to the median, the median, and greater than or equal to
+/// // the median as if the slice were sorted according to absolute value.
+/// let (lesser, median, greater) = ipnsort::select_nth_unstable_by_key(&mut v, 2, |a| a.abs());
+///
+/// assert!(lesser == [1, 2] || lesser == [2, 1]);
+/// assert_eq!(median, &mut -3);
+/// assert!(greater == [4, -5] || greater == [-5, 4]);
+/// ```
+#[inline]
+pub fn select_nth_unstable_by_key<T, K, F>(
+    arr: &mut [T],
+    index: usize,
+    mut f: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    select::partition_at_index(arr, index, |a: &T, b: &T| f(a).lt(&f(b)))
+}
+
+/// Returns the permutation that sorts the slice, without moving any elements.
+///
+/// The returned vector `indices` contains every index of `arr` exactly once, such that
+/// `indices.iter().map(|&i| &arr[i])` yields the elements of `arr` in sorted order. This is
+/// useful to apply the same reordering to several slices, e.g. the columns of a table.
+///
+/// Equal elements are ordered by their index, so the permutation is the same one a stable sort
+/// would produce, e.g. `sort_by_key` on the indices. This function allocates the returned vector
+/// and scratch memory proportional to `arr.len()`, and is *O*(*n* \* log(*n*)) worst-case.
+///
+/// # Current implementation
+///
+/// The indices are stored as `u32` while sorting if `arr.len()` allows for it. For types that
+/// are cheap to copy and have no interior mutability, compact (element, index) pairs are sorted
+/// instead of sorting the indices and looking up the elements for each comparison.
+///
+/// # Examples
+///
+/// ```
+/// let v = [-5, 4, 1, -3, 2];
+///
+/// let indices = ipnsort::argsort(&v);
+/// assert!(indices == [0, 3, 2, 4, 1]);
+/// ```
+#[inline]
+pub fn argsort<T>(arr: &[T]) -> Vec<usize>
+where
+    T: Ord,
+{
+    argsort::argsort(arr, &mut T::cmp)
+}
+
+/// Returns the permutation that sorts the slice with a comparator function, without moving any
+/// elements.
+///
+/// See [`argsort`]. The comparator function must define a total ordering for the elements in the
+/// slice. If the ordering is not total, the returned permutation is unspecified, but it will
+/// still contain every index exactly once.
+///
+/// # Examples
+///
+/// ```
+/// let v = [-5, 4, 1, -3, 2];
+///
+/// // Reverse sorting.
+/// let indices = ipnsort::argsort_by(&v, |a, b| b.cmp(a));
+/// assert!(indices == [1, 4, 2, 3, 0]);
+/// ```
+#[inline]
+pub fn argsort_by<T, F>(arr: &[T], mut compare: F) -> Vec<usize>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    argsort::argsort(arr, &mut compare)
+}
+
+/// Sorts the vector and removes all but the first of each group of equal elements, the same as
+/// [`sort`] followed by [`Vec::dedup`].
+///
+/// The sort is unstable (i.e., may reorder equal elements), so it is unspecified which of the
+/// equal elements is kept. It is *O*(*n* \* log(*n*)) worst-case and allocates a small buffer
+/// proportional to the number of distinct elements at most.
+///
+/// # Current implementation
+///
+/// The quicksort of [`sort_by`] partitions elements equal to a previous pivot into runs of their
+/// own, which is what makes it fast on slices with many duplicates. These runs are remembered and
+/// their elements are removed without comparing them again. Unlike [`sort`], it doesn't merge
+/// presorted prefixes or suffixes and doesn't radix sort integers.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = vec![3, 1, 3, 2, 1, 3];
+///
+/// ipnsort::sort_dedup(&mut v);
+/// assert!(v == [1, 2, 3]);
+/// ```
+#[inline]
+pub fn sort_dedup<T>(arr: &mut Vec<T>)
+where
+    T: Ord,
+{
+    dedup::sort_dedup(arr, &mut |a: &T, b: &T| a.lt(b));
+}
+
+/// Sorts the vector with a comparator function and removes all but the first of each group of
+/// elements the comparator considers equal.
+///
+/// See [`sort_dedup`]. The comparator function must define a total ordering for the elements in
+/// the vector. If the ordering is not total, the order of the elements and which of them are
+/// removed is unspecified. If the comparator function panics, the vector keeps all of its
+/// elements, in an unspecified order.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = vec![-3, 1, 3, -2, -1, 2];
+///
+/// // Keeps one element of each absolute value, in descending order.
+/// ipnsort::sort_dedup_by(&mut v, |a: &i32, b| b.abs().cmp(&a.abs()));
+/// assert!(v.iter().map(|x| x.abs()).eq([3, 2, 1]));
+/// ```
+#[inline]
+pub fn sort_dedup_by<T, F>(arr: &mut Vec<T>, mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    dedup::sort_dedup(arr, &mut |a, b| compare(a, b) == Ordering::Less);
+}
+
+/// Sorts the slice and returns each distinct element, in ascending order, together with the number
+/// of times it occurs.
+///
+/// The sort is unstable (i.e., may reorder equal elements), and it is unspecified which of the
+/// equal elements is cloned into the result. It is *O*(*n* \* log(*n*)) worst-case. See
+/// [`sort_dedup`] for how the duplicates are found.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = ["b", "a", "b", "c", "b"];
+///
+/// let counts = ipnsort::sort_count(&mut v);
+/// assert!(counts == [("a", 1), ("b", 3), ("c", 1)]);
+/// assert!(v == ["a", "b", "b", "b", "c"]);
+/// ```
+#[inline]
+pub fn sort_count<T>(arr: &mut [T]) -> Vec<(T, usize)>
+where
+    T: Ord + Clone,
+{
+    dedup::sort_count(arr, &mut |a: &T, b: &T| a.lt(b))
+}
+
+/// Sorts the slice with a comparator function and returns each group of elements the comparator
+/// considers equal as one of its elements, together with the size of the group.
+///
+/// See [`sort_count`]. The comparator function must define a total ordering for the elements in
+/// the slice. If the ordering is not total, the order of the elements and the counts are
+/// unspecified, but all original elements remain in the slice. The same is true if the comparator
+/// function panics.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [10, 21, 12, 33, 22];
+///
+/// // Counts the elements by their last digit.
+/// let counts = ipnsort::sort_count_by(&mut v, |a, b| (a % 10).cmp(&(b % 10)));
+/// assert!(counts.iter().map(|&(x, n)| (x % 10, n)).eq([(0, 1), (1, 1), (2, 2), (3, 1)]));
+/// ```
+#[inline]
+pub fn sort_count_by<T, F>(arr: &mut [T], mut compare: F) -> Vec<(T, usize)>
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    dedup::sort_count(arr, &mut |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Sorts the rows of several columns of equal length, given as a tuple of mutable slices, by the
+/// first column, then the second and so on, moving the elements of all columns together.
+///
+/// The result is the same as sorting the rows as tuples with [`slice::sort`], without storing the
+/// rows as tuples. This sort is stable (i.e., does not reorder equal rows), and *O*(*n* \*
+/// log(*n*)) worst-case. It allocates a buffer of `n` indices.
+///
+/// # Current implementation
+///
+/// The indices of the rows are sorted like [`argsort`] does, comparing the rows column by column.
+/// Then the permutation is applied to all columns at once, swapping each row into its final
+/// position. The indices are stored as `u32` if the length allows for it.
+///
+/// If the comparison of an element panics, the columns are left unchanged.
+///
+/// # Panics
+///
+/// Panics if the columns don't have the same length.
+///
+/// # Examples
+///
+/// ```
+/// let mut names = ["c", "a", "b", "a"];
+/// let mut ages = [30, 40, 20, 10];
+///
+/// ipnsort::sort_columns((&mut names[..], &mut ages[..]));
+/// assert!(names == ["a", "a", "b", "c"]);
+/// assert!(ages == [10, 40, 20, 30]);
+/// ```
+#[inline]
+pub fn sort_columns<C>(mut columns: C)
+where
+    C: Columns,
+{
+    let len = columns.rows();
+    columns::sort_rows(
+        &mut columns,
+        len,
+        &mut |columns: &C, a, b| columns.compare_rows(a, b),
+        &mut |columns: &mut C, a, b| columns.swap_rows(a, b),
+    );
+}
+
+/// Sorts `len` rows stored in any layout, with a function that compares two rows by their index,
+/// and a function that swaps two rows.
+///
+/// See [`sort_columns`]. Both functions are passed `columns`, to access the rows. All comparisons
+/// are done before the first swap, `swap_rows` is called with the indices of two distinct rows
+/// at most `len - 1` times. The comparison function must define a total ordering of the rows. If
+/// the ordering is not total, the resulting order of the rows is unspecified.
+///
+/// # Examples
+///
+/// ```
+/// let mut columns = (vec![3, 1, 2], vec![0.5f64, 0.9, 0.1]);
+///
+/// // Highest score first.
+/// ipnsort::sort_columns_by(
+///     &mut columns,
+///     3,
+///     |(_, scores), a, b| scores[b].total_cmp(&scores[a]),
+///     |(ids, scores), a, b| {
+///         ids.swap(a, b);
+///         scores.swap(a, b);
+///     },
+/// );
+/// assert!(columns.0 == [1, 3, 2]);
+/// ```
+#[inline]
+pub fn sort_columns_by<T, C, S>(columns: &mut T, len: usize, mut compare_rows: C, mut swap_rows: S)
+where
+    C: FnMut(&T, usize, usize) -> Ordering,
+    S: FnMut(&mut T, usize, usize),
+{
+    columns::sort_rows(columns, len, &mut compare_rows, &mut swap_rows);
+}
+
+/// Sorts the slice of floats according to the IEEE 754 totalOrder predicate, but might not
+/// preserve the order of equal elements.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate),
+/// and *O*(*n* \* log(*n*)) worst-case.
+///
+/// The order is the same as the one of [`f64::total_cmp`] and [`f32::total_cmp`]:
+///
+/// `-NaN < -inf < negative numbers < -0.0 < +0.0 < positive numbers < +inf < +NaN`
+///
+/// NaNs are ordered by their sign bit, those with the sign bit set are placed at the start of
+/// the slice and all others at the end. Among themselves NaNs are ordered by their payload. Note
+/// that [`f64::NAN`] is a positive NaN, but the sign of a NaN produced by an arithmetic operation
+/// is not specified and e.g. `0.0 / 0.0` yields a negative NaN on x86.
+///
+/// # Current implementation
+///
+/// The bits of each float are transformed into an integer with the same order, the integers are
+/// sorted with [`sort`] and transformed back. This avoids the cost of [`f64::total_cmp`] in every
+/// comparison and lets the sort use the same code paths it picks for integers, including radix
+/// sort for long slices.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [5.0, f64::NAN, -0.0, f64::NEG_INFINITY, 0.0, -f64::NAN, -1.5];
+///
+/// ipnsort::sort_floats(&mut v);
+/// assert!(v[0].is_nan() && v[0].is_sign_negative());
+/// assert_eq!(v[1..6], [f64::NEG_INFINITY, -1.5, -0.0, 0.0, 5.0]);
+/// assert!(v[3].is_sign_negative() && v[4].is_sign_positive());
+/// assert!(v[6].is_nan() && v[6].is_sign_positive());
+/// ```
+#[inline]
+pub fn sort_floats<T>(arr: &mut [T])
+where
+    T: Float,
+{
+    T::sort_total_order(arr);
+}
+
+/// Sorts the slice of strings by their bytes, but might not preserve the order of equal elements.
+///
+/// This sort is unstable (i.e., may reorder equal elements), allocates auxiliary memory of one key
+/// and element per string, and is *O*(*n* \* log(*n*)) worst-case in the number of key
+/// comparisons per key of the strings.
+///
+/// The order is the lexicographic order of the bytes returned by `as_ref`, which is the order of
+/// [`Ord`] for [`str`], [`String`], `[u8]` and [`Vec<u8>`].
+///
+/// # Current implementation
+///
+/// The current algorithm is multikey quicksort by Bentley and Sedgewick, on keys of 7 bytes
+/// instead of single bytes. The keys are cached next to the elements. Each partition splits the
+/// strings into three parts, the ones with a smaller key, the ones with the same key and the ones
+/// with a larger key. Only the middle part moves on to the next key, so shared prefixes are read
+/// once per partition instead of once per comparison. Partitions of up to 32 strings, and
+/// partitions after too many imbalanced ones, are sorted by key like [`sort`] would.
+///
+/// It is faster than [`sort`] for strings with long common prefixes, e.g. paths or qualified
+/// symbol names, and for random strings. It can be slower for inputs made of long sorted runs,
+/// which [`sort`] detects and merges.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = ["std::vec::Vec", "std::vec", "core::mem", "std::str", "std::vec::IntoIter"];
+///
+/// ipnsort::sort_strings(&mut v);
+/// assert_eq!(
+///     v,
+///     ["core::mem", "std::str", "std::vec", "std::vec::IntoIter", "std::vec::Vec"]
+/// );
+/// ```
+#[inline]
+pub fn sort_strings<T>(arr: &mut [T])
+where
+    T: AsRef<[u8]>,
+{
+    strings::sort_strings(arr);
+}
+
+/// Sorts the array, but might not preserve the order of equal elements.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate),
+/// and *O*(*N* \* log(*N*)) worst-case.
+///
+/// # Current implementation
+///
+/// Arrays with up to 32 elements are sorted with a sorting network, a fixed sequence of
+/// compare-and-swap operations that is selected at compile time based on `N`. The networks for up
+/// to 16 elements use the fewest comparisons known, e.g. 19 for `N == 8` and 60 for `N == 16`,
+/// the larger ones stay close to that, with 185 for `N == 32`. The operations are branchless,
+/// which makes them fast for types that are cheap to compare and copy. Larger arrays are sorted
+/// like [`sort`] does.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5, 4, 1, -3, 2];
+///
+/// ipnsort::sort_array(&mut v);
+/// assert!(v == [-5, -3, 1, 2, 4]);
+/// ```
+#[inline]
+pub fn sort_array<T, const N: usize>(arr: &mut [T; N])
+where
+    T: Ord,
+{
+    network::sort_array(arr, &mut |a, b| a.lt(b));
+}
+
+/// Sorts the array with a comparator function, but might not preserve the order of equal
+/// elements.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate),
+/// and *O*(*N* \* log(*N*)) worst-case. See [`sort_array`] for the implementation.
+///
+/// The comparator function must define a total ordering for the elements in the array. If the
+/// ordering is not total, the order of the elements is unspecified, but all original elements
+/// remain in the array. The same is true if the comparator function panics.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [5.0f32, 4.0, 1.0, 3.0, 2.0, 8.0, 7.0, 6.0];
+///
+/// ipnsort::sort_array_by(&mut v, |a, b| a.total_cmp(b));
+/// assert!(v == [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+/// ```
+#[inline]
+pub fn sort_array_by<T, F, const N: usize>(arr: &mut [T; N], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    network::sort_array(arr, &mut |a, b| compare(a, b) == Ordering::Less);
+}
+
+/// Sorts the slice in parallel, but might not preserve the order of equal elements.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate,
+/// apart from the threads it starts), and *O*(*n* \* log(*n*)) worst-case.
+///
+/// # Current implementation
+///
+/// Same as [`sort`], but once a partition step split a large slice in two, the left side is sorted
+/// by a new thread, while the calling thread continues with the right side. The threads are
+/// created with [`std::thread::scope`] for each call, there is no thread pool. Uses
+/// [`ParConfig::default`], which starts as many threads as [`std::thread::available_parallelism`]
+/// reports and sorts slices of up to 16384 elements on a single thread. See
+/// [`par_sort_with_config`] to change that.
+///
+/// The partition steps themselves are performed by a single thread, which limits the speedup to
+/// roughly `log2(len) / 2`.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [-5, 4, 1, -3, 2];
+///
+/// ipnsort::par_sort(&mut v);
+/// assert!(v == [-5, -3, 1, 2, 4]);
+/// ```
+#[inline]
+pub fn par_sort<T>(arr: &mut [T])
+where
+    T: Ord + Send,
+{
+    parallel::par_sort(arr, &|a: &T, b: &T| a.lt(b), &ParConfig::default());
+}
+
+/// Sorts the slice in parallel with a comparator function, but might not preserve the order of
+/// equal elements.
+///
+/// This sort is unstable (i.e., may reorder equal elements), in-place (i.e., does not allocate,
+/// apart from the threads it starts), and *O*(*n* \* log(*n*)) worst-case. See [`par_sort`] for
+/// the implementation.
+///
+/// The comparator function must define a total ordering for the elements in the slice, see
+/// [`sort_by`]. It is called from multiple threads at the same time.
+///
+/// # Panics
+///
+/// If the comparator function panics on one of the threads, the panic is propagated once all
+/// threads are done, see [`std::thread::scope`]. All original elements remain in the slice.
+///
+/// # Examples
+///
+/// ```
+/// let mut v = [5, 4, 1, 3, 2];
+///
+/// // Reverse sorting.
+/// ipnsort::par_sort_by(&mut v, |a, b| b.cmp(a));
+/// assert!(v == [5, 4, 3, 2, 1]);
+/// ```
+#[inline]
+pub fn par_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    par_sort_with_config(arr, &ParConfig::default(), compare);
+}
+
+/// Sorts the slice in parallel with a comparator function and the given number of threads and
+/// sequential cutoff, but might not preserve the order of equal elements.
+///
+/// See [`par_sort_by`] and [`ParConfig`].
+///
+/// # Examples
+///
+/// ```
+/// let mut v: Vec<u64> = (0..100_000).rev().collect();
+///
+/// let config = ipnsort::ParConfig {
+///     threads: 4,
+///     sequential_cutoff: 1000,
+/// };
+/// ipnsort::par_sort_with_config(&mut v, &config, |a, b| a.cmp(b));
+/// assert!(v.windows(2).all(|w| w[0] <= w[1]));
+/// ```
+#[inline]
+pub fn par_sort_with_config<T, F>(arr: &mut [T], config: &ParConfig, compare: F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    parallel::par_sort(arr, &|a: &T, b: &T| compare(a, b) == Ordering::Less, config);
+}
+
 // --- IMPL ---
 
 /// Sorts `v` using pattern-defeating quicksort, which is *O*(*n* \* log(*n*)) worst-case.
 #[inline(always)]
-fn unstable_sort<T, F>(v: &mut [T], mut is_less: F)
+fn unstable_sort<T, F>(v: &mut [T], is_less: F)
 where
     F: FnMut(&T, &T) -> bool,
+{
+    unstable_sort_with_scratch::<T, F, false>(v, &mut [], None, is_less);
+}
+
+/// Same as [`unstable_sort`], but lends `scratch` to the small-sort. `DUAL_PIVOT` selects
+/// [`quicksort::quicksort_dual_pivot`] as the quicksort core, `rng` randomizes the pivots of
+/// [`quicksort::quicksort`].
+#[inline(always)]
+fn unstable_sort_with_scratch<T, F, const DUAL_PIVOT: bool>(
+    v: &mut [T],
+    scratch: &mut [MaybeUninit<T>],
+    rng: Option<&mut PivotRng>,
+    mut is_less: F,
+) where
+    F: FnMut(&T, &T) -> bool,
 {
     // Sorting has no meaningful behavior on zero-sized types.
-    if T::IS_ZST {
+    if mem::size_of::<T>() == 0 {
         return;
     }
 
@@ -147,35 +1102,126 @@ where
         // More specialized and faster options, extending the range of allocation free sorting
         // are possible but come at a great cost of additional code, which is problematic for
         // compile-times.
+        trace::event!(small_sort(len));
         crate::smallsort::insertion_sort_shift_left(v, 1, &mut is_less);
     } else {
-        quicksort(v, is_less);
+        let origin = v.as_ptr();
+        quicksort::<T, F, DUAL_PIVOT>(v, scratch, &mut is_less, rng, origin);
+    }
+
+    if cfg!(feature = "strict") {
+        strict::check_sorted(v, v.as_ptr(), &mut is_less);
     }
 }
 
+/// Sorts `v` with [`quicksort::quicksort`], or [`quicksort::quicksort_dual_pivot`] if
+/// `DUAL_PIVOT`, unless it is already sorted or reversed. If a long prefix or suffix is, only the
+/// rest is quicksorted and merged with it.
+///
+/// `origin` is the start of the slice the sort was called with, the `strict` checks use it to name
+/// indices.
 #[inline(never)]
-fn quicksort<T, F>(v: &mut [T], mut is_less: F)
+fn quicksort<T, F, const DUAL_PIVOT: bool>(
+    v: &mut [T],
+    scratch: &mut [MaybeUninit<T>],
+    is_less: &mut F,
+    rng: Option<&mut PivotRng>,
+    origin: *const T,
+) where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+
+    let (streak_end, was_reversed) = find_streak(v, is_less);
+    trace::event!(streak(len, streak_end, was_reversed));
+    if streak_end == len {
+        if was_reversed {
+            v.reverse();
+        }
+
+        return;
+    }
+
+    // A long sorted or reversed prefix or suffix, e.g. new elements appended to a sorted slice, is
+    // kept as is. Only the rest is quicksorted, and then merged with it.
+    let (suffix_start, suffix_reversed) = find_suffix_streak(v, is_less);
+    let (presorted, reversed) = if streak_end >= len - suffix_start {
+        (0..streak_end, was_reversed)
+    } else {
+        (suffix_start..len, suffix_reversed)
+    };
+
+    // Measured on the `random_s*` patterns with `i32`, merging breaks even once the streak covers
+    // about a third of the slice and pays off from half on.
+    if presorted.len() < len / 2 {
+        // Limit the number of imbalanced partitions to `2 * floor(log2(len))`.
+        // The binary OR by one is used to eliminate the zero-check in the logarithm.
+        let limit = 2 * (len | 1).ilog2();
+
+        if DUAL_PIVOT {
+            crate::quicksort::quicksort_dual_pivot(v, is_less, None, limit, origin);
+        } else {
+            crate::quicksort::quicksort(v, scratch, is_less, None, limit, rng, origin, &mut |_| {});
+        }
+        return;
+    }
+
+    trace::event!(presorted(len, presorted.start, presorted.end));
+
+    if reversed {
+        v[presorted.clone()].reverse();
+    }
+
+    let (rest, mid) = if presorted.start == 0 {
+        (&mut v[presorted.end..], presorted.end)
+    } else {
+        (&mut v[..presorted.start], presorted.start)
+    };
+
+    // The rest is at most about half as long, and may itself start or end with a presorted streak.
+    quicksort::<T, F, DUAL_PIVOT>(rest, scratch, is_less, rng, origin);
+    crate::merge::merge(v, mid, scratch, is_less);
+}
+
+/// Sorts the `k` smallest elements of `v` into `v[..k]`, see [`partial_sort`].
+#[inline(always)]
+fn unstable_partial_sort<T, F>(v: &mut [T], k: usize, mut is_less: F)
 where
     F: FnMut(&T, &T) -> bool,
 {
     let len = v.len();
 
+    if k > len {
+        panic!("partial_sort k {k} greater than length of slice {len}");
+    }
+
+    if k == len {
+        unstable_sort(v, is_less);
+        return;
+    }
+
+    // Sorting has no meaningful behavior on zero-sized types.
+    if mem::size_of::<T>() == 0 || k == 0 {
+        return;
+    }
+
     let (streak_end, was_reversed) = find_streak(v, &mut is_less);
+    trace::event!(streak(len, streak_end, was_reversed));
     if streak_end == len {
         if was_reversed {
             v.reverse();
         }
 
-        // It would be possible to a do in-place merging here for a long existing streak. But that makes the
-        // implementation a lot bigger, users can use `slice::sort` for that use-case.
         return;
     }
 
-    // Limit the number of imbalanced partitions to `2 * floor(log2(len))`.
-    // The binary OR by one is used to eliminate the zero-check in the logarithm.
-    let limit = 2 * (len | 1).ilog2();
+    let origin = v.as_ptr();
+    crate::quicksort::partial_quicksort(v, k, &mut is_less, None, origin);
 
-    crate::quicksort::quicksort(v, &mut is_less, None, limit);
+    if cfg!(feature = "strict") {
+        strict::check_sorted(&v[..k], origin, &mut is_less);
+        strict::check_not_less(&v[k - 1], &v[k..], origin, &mut is_less);
+    }
 }
 
 /// Finds a streak of presorted elements starting at the beginning of the slice. Returns the first
@@ -215,39 +1261,66 @@ where
     }
 }
 
-// // #[rustc_unsafe_specialization_marker]
-// trait Freeze {}
+/// Same as [`find_streak`], but for the streak at the end of `v`. Returns the start of the streak
+/// and whether it is strictly descending.
+fn find_suffix_streak<T, F>(v: &[T], is_less: &mut F) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
 
-// Can the type have interior mutability, this is checked by testing if T is Freeze. If the type can
-// have interior mutability it may alter itself during comparison in a way that must be observed
-// after the sort operation concludes. Otherwise a type like Mutex<Option<Box<str>>> could lead to
-// double free.
-unsafe auto trait Freeze {}
+    if len < 2 {
+        return (0, false);
+    }
 
-impl<T: ?Sized> !Freeze for core::cell::UnsafeCell<T> {}
-unsafe impl<T: ?Sized> Freeze for core::marker::PhantomData<T> {}
-unsafe impl<T: ?Sized> Freeze for *const T {}
-unsafe impl<T: ?Sized> Freeze for *mut T {}
-unsafe impl<T: ?Sized> Freeze for &T {}
-unsafe impl<T: ?Sized> Freeze for &mut T {}
+    let mut start = len - 2;
 
-#[must_use]
-const fn has_efficient_in_place_swap<T>() -> bool {
-    const MEM_SIZE_U64: usize = mem::size_of::<u64>();
+    if is_less(&v[len - 1], &v[len - 2]) {
+        while start > 0 && is_less(&v[start], &v[start - 1]) {
+            start -= 1;
+        }
 
-    mem::size_of::<T>() <= MEM_SIZE_U64
-}
+        (start, true)
+    } else {
+        while start > 0 && !is_less(&v[start], &v[start - 1]) {
+            start -= 1;
+        }
 
-#[test]
-fn type_info() {
-    assert!(has_efficient_in_place_swap::<i32>());
-    assert!(has_efficient_in_place_swap::<u64>());
-    assert!(!has_efficient_in_place_swap::<u128>());
-    assert!(!has_efficient_in_place_swap::<String>());
+        (start, false)
+    }
 }
 
-trait IsTrue<const B: bool> {}
-impl IsTrue<true> for () {}
+#[cfg(not(feature = "stable"))]
+use specialize::Freeze;
+
+/// Without auto traits there is no way to tell if a type has interior mutability. No type
+/// implements this version of `Freeze`, and the code paths that require it are never instantiated.
+///
+/// # Safety
+///
+/// Implementing types must not have interior mutability.
+#[cfg(feature = "stable")]
+unsafe trait Freeze {}
+
+/// Stable replacements for the compiler intrinsics used by the implementation.
+#[cfg(feature = "stable")]
+mod intrinsics {
+    #[inline(always)]
+    pub(crate) fn likely(b: bool) -> bool {
+        b
+    }
+
+    #[inline(always)]
+    pub(crate) unsafe fn assume(b: bool) {
+        // SAFETY: The caller must guarantee that `b` is true.
+        unsafe { core::hint::assert_unchecked(b) }
+    }
+
+    #[cold]
+    pub(crate) fn abort() -> ! {
+        std::process::abort()
+    }
+}
 
 struct GapGuard<T> {
     pos: *mut T,
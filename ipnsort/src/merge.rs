@@ -0,0 +1,199 @@
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+
+/// Size in bytes of the stack buffer used for merging, if the caller provides less scratch space.
+/// Merges of runs that don't fit are split by rotations until they do.
+const STACK_BUF_BYTES: usize = 4096;
+
+/// Merges the sorted runs `v[..mid]` and `v[mid..]` in place.
+///
+/// Doesn't allocate. The shorter run is buffered in `scratch` or a stack buffer, whichever is
+/// larger. If it doesn't fit, the merge is split into smaller merges with rotations, which costs
+/// *O*(*n* \* log(*m* / *b*)) moves, with *m* the length of the shorter run and *b* the length of
+/// the buffer.
+#[inline(never)]
+pub(crate) fn merge<T, F>(v: &mut [T], mid: usize, scratch: &mut [MaybeUninit<T>], is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut stack_buf = AlignedStorage::<T, STACK_BUF_BYTES>::new();
+    let stack_scratch = stack_buf.as_uninit_slice_mut();
+
+    if scratch.len() >= stack_scratch.len() {
+        merge_rotating(v, mid, scratch, is_less);
+    } else {
+        merge_rotating(v, mid, stack_scratch, is_less);
+    }
+}
+
+fn merge_rotating<T, F>(v: &mut [T], mid: usize, buf: &mut [MaybeUninit<T>], is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+
+    if mid == 0 || mid == len {
+        return;
+    }
+
+    if mid <= buf.len() && mid <= len - mid {
+        // SAFETY: Both runs are non-empty and the left one fits into `buf`.
+        unsafe { merge_up(v, mid, buf, is_less) };
+        return;
+    }
+
+    if len - mid <= buf.len() {
+        // SAFETY: Both runs are non-empty and the right one fits into `buf`.
+        unsafe { merge_down(v, mid, buf, is_less) };
+        return;
+    }
+
+    if len == 2 {
+        // Only reachable with an empty `buf`, for types larger than the stack buffer. Splitting
+        // wouldn't make progress here.
+        if is_less(&v[1], &v[0]) {
+            v.swap(0, 1);
+        }
+        return;
+    }
+
+    // Split the longer run in the middle and the shorter one where that middle element belongs.
+    // Rotating the inner two parts leaves two independent merges, each of the original length.
+    let (left, right) = v.split_at(mid);
+    let (left_cut, right_cut) = if left.len() >= right.len() {
+        let left_cut = left.len() / 2;
+        let right_cut = mid + right.partition_point(|elem| is_less(elem, &left[left_cut]));
+        (left_cut, right_cut)
+    } else {
+        let right_cut = right.len() / 2;
+        let left_cut = left.partition_point(|elem| !is_less(&right[right_cut], elem));
+        (left_cut, mid + right_cut)
+    };
+
+    v[left_cut..right_cut].rotate_left(mid - left_cut);
+    let new_mid = left_cut + (right_cut - mid);
+
+    let (new_left, new_right) = v.split_at_mut(new_mid);
+    merge_rotating(new_left, left_cut, buf, is_less);
+    merge_rotating(new_right, right_cut - new_mid, buf, is_less);
+}
+
+/// Merges front to back, after moving the left run into `buf`.
+///
+/// SAFETY: The caller has to guarantee that `0 < mid < v.len()` and `mid <= buf.len()`.
+unsafe fn merge_up<T, F>(v: &mut [T], mid: usize, buf: &mut [MaybeUninit<T>], is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+    let v_base = v.as_mut_ptr();
+    let buf_base = buf.as_mut_ptr() as *mut T;
+
+    // SAFETY: The caller guarantees that `buf` can hold `v[..mid]`. From then on the gap in `v`
+    // is as long as the part of `buf` that hasn't been merged yet, and `hole` fills it with that
+    // part when it is dropped, even if `is_less` panics. `dst` never overtakes `right`, because
+    // the gap stays between them.
+    unsafe {
+        ptr::copy_nonoverlapping(v_base, buf_base, mid);
+
+        let mut hole = MergeHole {
+            start: buf_base,
+            end: buf_base.add(mid),
+            dst: v_base,
+        };
+
+        let mut right = v_base.add(mid);
+        let v_end = v_base.add(len);
+
+        while hole.start < hole.end && right < v_end {
+            let take_right = is_less(&*right, &*hole.start);
+            let src = if take_right { right } else { hole.start };
+            ptr::copy_nonoverlapping(src, hole.dst, 1);
+
+            right = right.add(take_right as usize);
+            hole.start = hole.start.add(!take_right as usize);
+            hole.dst = hole.dst.add(1);
+        }
+    }
+}
+
+/// Merges back to front, after moving the right run into `buf`.
+///
+/// SAFETY: The caller has to guarantee that `0 < mid < v.len()` and `v.len() - mid <= buf.len()`.
+unsafe fn merge_down<T, F>(v: &mut [T], mid: usize, buf: &mut [MaybeUninit<T>], is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+    let v_base = v.as_mut_ptr();
+    let buf_base = buf.as_mut_ptr() as *mut T;
+
+    // SAFETY: See `merge_up`, mirrored. The gap is between the unmerged part of the left run,
+    // ending at `hole.dst`, and `out`.
+    unsafe {
+        ptr::copy_nonoverlapping(v_base.add(mid), buf_base, len - mid);
+
+        let mut hole = MergeHole {
+            start: buf_base,
+            end: buf_base.add(len - mid),
+            dst: v_base.add(mid),
+        };
+
+        let mut out = v_base.add(len);
+
+        while v_base < hole.dst && hole.start < hole.end {
+            let left = hole.dst.sub(1);
+            let right = hole.end.sub(1);
+
+            let take_left = is_less(&*right, &*left);
+            let src = if take_left { left } else { right };
+            out = out.sub(1);
+            ptr::copy_nonoverlapping(src, out, 1);
+
+            hole.dst = hole.dst.sub(take_left as usize);
+            hole.end = hole.end.sub(!take_left as usize);
+        }
+    }
+}
+
+/// When dropped, moves the elements `start..end` to `dst`.
+struct MergeHole<T> {
+    start: *mut T,
+    end: *mut T,
+    dst: *mut T,
+}
+
+impl<T> Drop for MergeHole<T> {
+    fn drop(&mut self) {
+        // SAFETY: `start..end` are the buffered elements that haven't been merged yet, and `dst`
+        // is the start of the equally long gap in the slice.
+        unsafe {
+            let len = self.end.offset_from(self.start) as usize;
+            ptr::copy_nonoverlapping(self.start, self.dst, len);
+        }
+    }
+}
+
+/// Stack memory with the alignment of `T`, without requiring `T: Copy` or a const generic length
+/// in elements.
+#[repr(C)]
+struct AlignedStorage<T, const N: usize> {
+    _align: [T; 0],
+    storage: [MaybeUninit<u8>; N],
+}
+
+impl<T, const N: usize> AlignedStorage<T, N> {
+    fn new() -> Self {
+        Self {
+            _align: [],
+            storage: [MaybeUninit::uninit(); N],
+        }
+    }
+
+    fn as_uninit_slice_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        let len = N / mem::size_of::<T>();
+
+        // SAFETY: `_align` ensures that the storage is aligned for `T`, and `len` elements fit.
+        unsafe { core::slice::from_raw_parts_mut(self.storage.as_mut_ptr().cast(), len) }
+    }
+}
@@ -0,0 +1,314 @@
+//! Sorting networks for arrays with a length that is known at compile time, see
+//! [`crate::sort_array`].
+
+use crate::smallsort::swap_if_less;
+use crate::unstable_sort;
+
+macro_rules! sorting_networks {
+    ($($len:literal => [$(($a:literal, $b:literal)),* $(,)?],)*) => {
+        // The comparators of each network must be in-bounds for the unchecked access below.
+        const _: () = {
+            $($(assert!($a < $b && $b < $len);)*)*
+        };
+
+        /// Sorts `v` with a fixed sorting network if there is one for `N`, otherwise with the
+        /// general implementation. `N` is known at compile time, only the selected network is
+        /// part of the generated code.
+        #[inline]
+        pub(crate) fn sort_array<T, F, const N: usize>(v: &mut [T; N], is_less: &mut F)
+        where
+            F: FnMut(&T, &T) -> bool,
+        {
+            let v_base = v.as_mut_ptr();
+
+            match N {
+                // Already sorted.
+                0 | 1 => {}
+                $(
+                    // SAFETY: All comparator indices are less than `N`, see the assertions above.
+                    // `swap_if_less` leaves `v` in a valid state if `is_less` panics.
+                    $len => unsafe {
+                        $(swap_if_less(v_base, $a, $b, is_less);)*
+                    },
+                )*
+                _ => unstable_sort(v, is_less),
+            }
+        }
+    };
+}
+
+// Sorting networks with the fewest comparators known for each length up to 16, see
+// https://bertdobbelaere.github.io/sorting_networks.html. The larger ones are built from two of
+// those and a merging network, based on Batcher's odd-even merge and shortened with a randomized
+// search where possible. Each network is ordered by layers, the comparators of a layer are
+// independent of each other.
+sorting_networks! {
+    2 => [(0, 1)],
+    3 => [(0, 1), (0, 2), (1, 2)],
+    4 => [(0, 1), (2, 3), (0, 2), (1, 3), (1, 2)],
+    5 => [(0, 4), (1, 3), (0, 2), (1, 4), (0, 1), (2, 3), (1, 2), (2, 4), (3, 4)],
+    6 => [(0, 1), (2, 3), (4, 5), (0, 2), (1, 3), (0, 4), (1, 2), (1, 5), (2, 4), (1, 2), (3, 5),
+        (3, 4)],
+    7 => [(0, 1), (2, 3), (4, 5), (0, 6), (2, 4), (3, 5), (0, 2), (1, 6), (3, 4), (1, 3), (4, 6),
+        (2, 4), (3, 5), (1, 2), (3, 4), (5, 6)],
+    8 => [(0, 1), (2, 3), (4, 5), (6, 7), (0, 2), (1, 3), (4, 6), (5, 7), (0, 4), (1, 2), (3, 7),
+        (5, 6), (1, 5), (2, 6), (1, 4), (3, 5), (2, 4), (5, 6), (3, 4)],
+    9 => [(0, 3), (1, 7), (2, 5), (4, 8), (0, 7), (2, 4), (3, 8), (5, 6), (0, 2), (1, 3), (4, 5),
+        (7, 8), (1, 4), (3, 6), (5, 7), (0, 1), (2, 4), (3, 5), (6, 8), (2, 3), (4, 5), (6, 7),
+        (1, 2), (3, 4), (5, 6)],
+    10 => [(0, 8), (1, 9), (2, 7), (3, 5), (4, 6), (0, 2), (1, 4), (5, 8), (7, 9), (0, 3), (2, 4),
+        (5, 7), (6, 9), (0, 1), (3, 6), (8, 9), (1, 5), (2, 3), (4, 8), (6, 7), (1, 2), (3, 5),
+        (4, 6), (7, 8), (2, 3), (4, 5), (6, 7), (3, 4), (5, 6)],
+    11 => [(0, 9), (1, 6), (2, 4), (3, 7), (5, 8), (0, 1), (3, 5), (4, 10), (6, 9), (7, 8), (1, 3),
+        (2, 5), (4, 7), (8, 10), (0, 4), (1, 2), (3, 7), (5, 9), (6, 8), (0, 1), (2, 6), (4, 5),
+        (7, 8), (9, 10), (2, 4), (3, 6), (5, 7), (8, 9), (1, 2), (3, 4), (5, 6), (7, 8), (2, 3),
+        (4, 5), (6, 7)],
+    12 => [(0, 8), (1, 7), (2, 6), (3, 11), (4, 10), (5, 9), (0, 1), (2, 5), (3, 4), (6, 9), (7, 8),
+        (10, 11), (0, 2), (1, 6), (5, 10), (9, 11), (0, 3), (1, 2), (4, 6), (5, 7), (8, 11),
+        (9, 10), (1, 4), (3, 5), (6, 8), (7, 10), (1, 3), (2, 5), (6, 9), (8, 10), (2, 3), (4, 5),
+        (6, 7), (8, 9), (4, 6), (5, 7), (3, 4), (5, 6), (7, 8)],
+    13 => [(0, 12), (1, 10), (2, 9), (3, 7), (5, 11), (6, 8), (1, 6), (2, 3), (4, 11), (7, 9),
+        (8, 10), (0, 4), (1, 2), (3, 6), (7, 8), (9, 10), (11, 12), (4, 6), (5, 9), (8, 11),
+        (10, 12), (0, 5), (3, 8), (4, 7), (6, 11), (9, 10), (0, 1), (2, 5), (6, 9), (7, 8),
+        (10, 11), (1, 3), (2, 4), (5, 6), (9, 10), (1, 2), (3, 4), (5, 7), (6, 8), (2, 3), (4, 5),
+        (6, 7), (8, 9), (3, 4), (5, 6)],
+    14 => [(0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11), (12, 13), (0, 2), (1, 3), (4, 8),
+        (5, 9), (10, 12), (11, 13), (0, 4), (1, 2), (3, 7), (5, 8), (6, 10), (9, 13), (11, 12),
+        (0, 6), (1, 5), (3, 9), (4, 10), (7, 13), (8, 12), (2, 10), (3, 11), (4, 6), (7, 9), (1, 3),
+        (2, 8), (5, 11), (6, 7), (10, 12), (1, 4), (2, 6), (3, 5), (7, 11), (8, 10), (9, 12),
+        (2, 4), (3, 6), (5, 8), (7, 10), (9, 11), (3, 4), (5, 6), (7, 8), (9, 10), (6, 7)],
+    15 => [(0, 13), (1, 12), (3, 14), (4, 8), (5, 6), (7, 11), (9, 10), (0, 5), (1, 7), (2, 9),
+        (3, 4), (6, 13), (8, 14), (11, 12), (0, 1), (2, 3), (4, 5), (6, 8), (7, 9), (10, 11),
+        (12, 13), (0, 2), (1, 3), (4, 10), (5, 11), (6, 7), (8, 9), (12, 14), (1, 2), (3, 12),
+        (4, 6), (5, 7), (8, 10), (9, 11), (13, 14), (1, 4), (2, 6), (5, 8), (7, 10), (9, 13),
+        (11, 14), (2, 4), (3, 6), (9, 12), (11, 13), (3, 5), (6, 8), (7, 9), (10, 12), (3, 4),
+        (5, 6), (7, 8), (9, 10), (11, 12), (6, 7), (8, 9)],
+    16 => [(0, 13), (1, 12), (2, 15), (3, 14), (4, 8), (5, 6), (7, 11), (9, 10), (0, 5), (1, 7),
+        (2, 9), (3, 4), (6, 13), (8, 14), (10, 15), (11, 12), (0, 1), (2, 3), (4, 5), (6, 8),
+        (7, 9), (10, 11), (12, 13), (14, 15), (0, 2), (1, 3), (4, 10), (5, 11), (6, 7), (8, 9),
+        (12, 14), (13, 15), (1, 2), (3, 12), (4, 6), (5, 7), (8, 10), (9, 11), (13, 14), (1, 4),
+        (2, 6), (5, 8), (7, 10), (9, 13), (11, 14), (2, 4), (3, 6), (9, 12), (11, 13), (3, 5),
+        (6, 8), (7, 9), (10, 12), (3, 4), (5, 6), (7, 8), (9, 10), (11, 12), (6, 7), (8, 9)],
+    17 => [(0, 1), (2, 3), (4, 5), (6, 7), (8, 11), (9, 15), (10, 13), (12, 16), (0, 2), (1, 3),
+        (4, 6), (5, 7), (8, 15), (10, 12), (11, 16), (13, 14), (0, 4), (1, 2), (3, 7), (5, 6),
+        (8, 10), (9, 11), (12, 13), (15, 16), (1, 5), (2, 6), (9, 12), (11, 14), (13, 15), (1, 4),
+        (3, 5), (8, 9), (10, 12), (11, 13), (14, 16), (0, 8), (2, 4), (5, 6), (10, 11), (12, 13),
+        (14, 15), (3, 4), (7, 15), (9, 10), (11, 12), (13, 14), (1, 9), (2, 10), (3, 11), (4, 12),
+        (5, 13), (6, 14), (4, 8), (5, 9), (6, 10), (7, 11), (12, 16), (1, 4), (3, 5), (6, 8),
+        (7, 9), (10, 12), (11, 13), (14, 16), (2, 4), (5, 6), (7, 8), (11, 12), (13, 14), (15, 16),
+        (3, 4), (8, 10), (9, 10)],
+    18 => [(0, 1), (2, 3), (4, 5), (6, 7), (8, 16), (9, 17), (10, 15), (11, 13), (12, 14), (0, 2),
+        (1, 3), (4, 6), (5, 7), (8, 10), (9, 12), (13, 16), (15, 17), (0, 4), (1, 2), (3, 7),
+        (5, 6), (8, 11), (10, 12), (13, 15), (14, 17), (1, 5), (2, 6), (8, 9), (11, 14), (16, 17),
+        (0, 8), (1, 4), (3, 5), (9, 13), (10, 11), (12, 16), (14, 15), (2, 4), (5, 6), (9, 10),
+        (11, 13), (12, 14), (15, 16), (1, 9), (3, 4), (10, 11), (12, 13), (14, 15), (2, 10),
+        (7, 15), (11, 12), (13, 14), (3, 11), (4, 12), (5, 13), (6, 14), (4, 8), (5, 9), (6, 10),
+        (7, 11), (12, 16), (13, 17), (2, 4), (3, 5), (6, 8), (7, 9), (10, 12), (11, 13), (14, 16),
+        (15, 17), (1, 2), (3, 4), (5, 6), (7, 8), (9, 11), (13, 14), (15, 16), (8, 10), (11, 12),
+        (9, 10)],
+    19 => [(0, 3), (1, 7), (2, 5), (4, 8), (9, 17), (10, 18), (11, 16), (12, 14), (13, 15), (0, 7),
+        (2, 4), (3, 8), (5, 6), (9, 11), (10, 13), (14, 17), (16, 18), (0, 2), (1, 3), (4, 5),
+        (7, 8), (9, 12), (11, 13), (14, 16), (15, 18), (1, 4), (3, 6), (5, 7), (9, 10), (12, 15),
+        (17, 18), (0, 1), (2, 4), (3, 5), (6, 8), (10, 14), (11, 12), (13, 17), (15, 16), (2, 3),
+        (4, 5), (6, 7), (8, 18), (10, 11), (12, 14), (13, 15), (16, 17), (0, 10), (1, 2), (3, 4),
+        (5, 6), (7, 17), (11, 12), (13, 14), (15, 16), (1, 11), (6, 16), (12, 13), (14, 15),
+        (2, 12), (3, 13), (4, 14), (5, 15), (3, 9), (4, 10), (5, 11), (6, 12), (7, 13), (8, 14),
+        (0, 3), (2, 4), (5, 9), (6, 10), (7, 11), (8, 12), (13, 15), (14, 16), (1, 3), (4, 5),
+        (6, 9), (8, 10), (12, 13), (14, 15), (16, 17), (2, 3), (7, 9), (10, 11), (8, 9)],
+    20 => [(0, 8), (1, 9), (2, 7), (3, 5), (4, 6), (10, 18), (11, 19), (12, 17), (13, 15), (14, 16),
+        (0, 2), (1, 4), (5, 8), (7, 9), (10, 12), (11, 14), (15, 18), (17, 19), (0, 3), (2, 4),
+        (5, 7), (6, 9), (10, 13), (12, 14), (15, 17), (16, 19), (0, 1), (3, 6), (8, 9), (10, 11),
+        (13, 16), (18, 19), (0, 10), (1, 5), (2, 3), (4, 8), (6, 7), (9, 19), (11, 15), (12, 13),
+        (14, 18), (16, 17), (1, 2), (3, 5), (4, 6), (7, 8), (11, 12), (13, 15), (14, 16), (17, 18),
+        (1, 11), (2, 3), (4, 5), (6, 7), (8, 18), (12, 13), (14, 15), (16, 17), (2, 12), (3, 4),
+        (5, 6), (7, 17), (13, 14), (15, 16), (3, 13), (4, 14), (5, 15), (6, 16), (4, 10), (5, 11),
+        (6, 12), (7, 13), (9, 15), (1, 4), (3, 5), (8, 10), (9, 11), (15, 17), (2, 4), (6, 8),
+        (7, 9), (10, 14), (11, 13), (17, 18), (3, 4), (5, 6), (7, 8), (10, 12), (14, 16), (9, 10),
+        (11, 12), (13, 14), (15, 16)],
+    21 => [(0, 8), (1, 9), (2, 7), (3, 5), (4, 6), (10, 19), (11, 16), (12, 14), (13, 17), (15, 18),
+        (0, 2), (1, 4), (5, 8), (7, 9), (10, 11), (13, 15), (14, 20), (16, 19), (17, 18), (0, 3),
+        (2, 4), (5, 7), (6, 9), (11, 13), (12, 15), (14, 17), (18, 20), (0, 1), (3, 6), (8, 9),
+        (10, 14), (11, 12), (13, 17), (15, 19), (16, 18), (1, 5), (2, 3), (4, 8), (6, 7), (10, 11),
+        (12, 16), (14, 15), (17, 18), (19, 20), (0, 10), (1, 2), (3, 5), (4, 6), (7, 8), (12, 14),
+        (13, 16), (15, 17), (18, 19), (2, 3), (4, 5), (6, 7), (9, 19), (11, 12), (13, 14), (15, 16),
+        (17, 18), (1, 11), (3, 4), (5, 6), (8, 18), (12, 13), (14, 15), (16, 17), (2, 12), (3, 13),
+        (4, 14), (5, 15), (7, 17), (4, 10), (5, 11), (7, 13), (8, 14), (9, 15), (12, 16), (1, 4),
+        (3, 5), (6, 12), (8, 10), (9, 11), (15, 17), (2, 4), (6, 8), (7, 9), (11, 13), (12, 20),
+        (3, 4), (5, 6), (7, 8), (12, 16), (18, 20), (10, 12), (14, 16), (17, 18), (19, 20), (9, 10),
+        (11, 12), (13, 14), (15, 16)],
+    22 => [(0, 8), (1, 9), (2, 7), (3, 5), (4, 6), (10, 18), (11, 17), (12, 16), (13, 21), (14, 20),
+        (15, 19), (0, 2), (1, 4), (5, 8), (7, 9), (10, 11), (12, 15), (13, 14), (16, 19), (17, 18),
+        (20, 21), (0, 3), (2, 4), (5, 7), (6, 9), (10, 12), (11, 16), (15, 20), (19, 21), (0, 1),
+        (3, 6), (8, 9), (10, 13), (11, 12), (14, 16), (15, 17), (18, 21), (19, 20), (1, 5), (2, 3),
+        (4, 8), (6, 7), (9, 21), (11, 14), (13, 15), (16, 18), (17, 20), (1, 2), (3, 5), (4, 6),
+        (7, 8), (11, 13), (12, 15), (16, 19), (18, 20), (2, 3), (4, 5), (6, 7), (8, 20), (12, 13),
+        (14, 15), (16, 17), (18, 19), (0, 12), (2, 10), (3, 4), (5, 6), (14, 16), (15, 17), (0, 2),
+        (3, 11), (13, 14), (15, 16), (17, 18), (1, 13), (4, 16), (5, 17), (6, 14), (7, 15), (1, 3),
+        (4, 12), (5, 13), (6, 10), (7, 11), (14, 18), (15, 19), (1, 2), (4, 6), (5, 7), (8, 12),
+        (9, 13), (10, 14), (11, 15), (3, 4), (5, 6), (8, 10), (9, 11), (12, 16), (13, 17), (7, 8),
+        (9, 10), (12, 14), (13, 15), (16, 18), (17, 19), (11, 12), (13, 14), (15, 16), (17, 18),
+        (19, 20)],
+    23 => [(0, 9), (1, 6), (2, 4), (3, 7), (5, 8), (11, 19), (12, 18), (13, 17), (14, 22), (15, 21),
+        (16, 20), (0, 1), (3, 5), (4, 10), (6, 9), (7, 8), (11, 12), (13, 16), (14, 15), (17, 20),
+        (18, 19), (21, 22), (1, 3), (2, 5), (4, 7), (8, 10), (11, 13), (12, 17), (16, 21), (20, 22),
+        (0, 4), (1, 2), (3, 7), (5, 9), (6, 8), (11, 14), (12, 13), (15, 17), (16, 18), (19, 22),
+        (20, 21), (0, 1), (2, 6), (4, 5), (7, 8), (9, 10), (12, 15), (14, 16), (17, 19), (18, 21),
+        (2, 4), (3, 6), (5, 7), (8, 9), (10, 22), (12, 14), (13, 16), (17, 20), (19, 21), (0, 12),
+        (1, 2), (3, 4), (5, 6), (7, 8), (9, 21), (13, 14), (15, 16), (17, 18), (19, 20), (1, 13),
+        (2, 3), (4, 5), (6, 7), (8, 20), (15, 17), (16, 18), (3, 11), (14, 15), (16, 17), (18, 19),
+        (0, 3), (2, 14), (4, 16), (5, 17), (6, 18), (7, 15), (1, 3), (4, 12), (5, 13), (6, 14),
+        (7, 11), (8, 16), (9, 17), (10, 18), (15, 19), (2, 4), (5, 7), (8, 12), (9, 13), (10, 14),
+        (11, 15), (17, 19), (18, 20), (2, 3), (4, 5), (6, 8), (9, 11), (10, 12), (13, 15), (14, 16),
+        (18, 19), (20, 21), (6, 7), (8, 9), (10, 11), (12, 13), (14, 15), (16, 17)],
+    24 => [(0, 8), (1, 7), (2, 6), (3, 11), (4, 10), (5, 9), (12, 20), (13, 19), (14, 18), (15, 23),
+        (16, 22), (17, 21), (0, 1), (2, 5), (3, 4), (6, 9), (7, 8), (10, 11), (12, 13), (14, 17),
+        (15, 16), (18, 21), (19, 20), (22, 23), (0, 2), (1, 6), (5, 10), (9, 11), (12, 14),
+        (13, 18), (17, 22), (21, 23), (0, 3), (1, 2), (4, 6), (5, 7), (8, 11), (9, 10), (12, 15),
+        (13, 14), (16, 18), (17, 19), (20, 23), (21, 22), (0, 12), (1, 4), (3, 5), (6, 8), (7, 10),
+        (11, 23), (13, 16), (15, 17), (18, 20), (19, 22), (1, 3), (2, 5), (6, 9), (8, 10), (13, 15),
+        (14, 17), (18, 21), (20, 22), (1, 13), (2, 3), (4, 5), (6, 7), (8, 9), (10, 22), (14, 15),
+        (16, 17), (18, 19), (20, 21), (2, 14), (4, 6), (5, 7), (9, 21), (16, 18), (17, 19), (3, 4),
+        (5, 17), (6, 18), (7, 8), (15, 16), (19, 20), (3, 15), (4, 16), (5, 6), (7, 19), (8, 20),
+        (17, 18), (4, 12), (5, 13), (6, 14), (8, 16), (9, 17), (10, 18), (11, 15), (1, 4), (3, 5),
+        (7, 11), (8, 12), (9, 13), (10, 14), (15, 19), (18, 20), (2, 4), (6, 8), (7, 9), (10, 12),
+        (11, 13), (14, 16), (15, 17), (19, 21), (3, 4), (5, 6), (7, 8), (9, 10), (11, 12), (13, 14),
+        (15, 16), (17, 18), (19, 20), (21, 22)],
+    25 => [(0, 8), (1, 7), (2, 6), (3, 11), (4, 10), (5, 9), (12, 24), (13, 22), (14, 21), (15, 19),
+        (17, 23), (18, 20), (0, 1), (2, 5), (3, 4), (6, 9), (7, 8), (10, 11), (13, 18), (14, 15),
+        (16, 23), (19, 21), (20, 22), (0, 2), (1, 6), (5, 10), (9, 11), (12, 16), (13, 14),
+        (15, 18), (19, 20), (21, 22), (23, 24), (0, 3), (1, 2), (4, 6), (5, 7), (8, 11), (9, 10),
+        (16, 18), (17, 21), (20, 23), (22, 24), (1, 4), (3, 5), (6, 8), (7, 10), (11, 24), (12, 17),
+        (15, 20), (16, 19), (18, 23), (21, 22), (1, 3), (2, 5), (6, 9), (8, 10), (12, 13), (14, 17),
+        (18, 21), (19, 20), (22, 23), (2, 3), (4, 5), (6, 7), (8, 9), (10, 23), (13, 15), (14, 16),
+        (17, 18), (21, 22), (4, 6), (5, 7), (9, 22), (13, 14), (15, 16), (17, 19), (18, 20),
+        (0, 13), (3, 4), (5, 6), (7, 8), (14, 15), (16, 17), (18, 19), (20, 21), (1, 14), (6, 19),
+        (7, 20), (8, 21), (15, 16), (17, 18), (2, 15), (3, 16), (4, 17), (5, 18), (6, 12), (7, 13),
+        (8, 14), (3, 6), (4, 7), (5, 8), (9, 15), (10, 16), (11, 17), (0, 3), (2, 4), (5, 6),
+        (9, 12), (10, 13), (11, 14), (15, 18), (16, 19), (17, 20), (1, 3), (4, 5), (7, 9), (8, 10),
+        (11, 12), (13, 15), (14, 16), (17, 18), (19, 21), (20, 22), (2, 3), (6, 7), (8, 9),
+        (10, 11), (12, 13), (14, 15), (16, 17), (18, 19), (20, 21), (22, 23)],
+    26 => [(0, 8), (1, 9), (2, 7), (3, 5), (4, 6), (10, 23), (11, 22), (12, 25), (13, 24), (14, 18),
+        (15, 16), (17, 21), (19, 20), (0, 2), (1, 4), (5, 8), (7, 9), (10, 15), (11, 17), (12, 19),
+        (13, 14), (16, 23), (18, 24), (20, 25), (21, 22), (0, 3), (2, 4), (5, 7), (6, 9), (10, 11),
+        (12, 13), (14, 15), (16, 18), (17, 19), (20, 21), (22, 23), (24, 25), (0, 1), (3, 6),
+        (8, 9), (10, 12), (11, 13), (14, 20), (15, 21), (16, 17), (18, 19), (22, 24), (23, 25),
+        (1, 5), (2, 3), (4, 8), (6, 7), (9, 25), (11, 12), (13, 22), (14, 16), (15, 17), (18, 20),
+        (19, 21), (23, 24), (1, 2), (3, 5), (4, 6), (7, 8), (11, 14), (12, 16), (15, 18), (17, 20),
+        (19, 23), (21, 24), (2, 3), (4, 5), (6, 7), (8, 24), (12, 14), (13, 16), (19, 22), (21, 23),
+        (3, 4), (5, 6), (7, 23), (13, 15), (16, 18), (17, 19), (20, 22), (13, 14), (15, 16),
+        (17, 18), (19, 20), (21, 22), (4, 20), (5, 21), (6, 22), (7, 15), (16, 17), (18, 19),
+        (0, 16), (1, 17), (2, 18), (3, 19), (4, 12), (5, 13), (6, 14), (0, 4), (1, 5), (2, 10),
+        (3, 11), (8, 16), (9, 17), (14, 18), (15, 19), (0, 2), (1, 3), (6, 10), (7, 11), (8, 12),
+        (9, 13), (16, 20), (17, 21), (1, 2), (4, 6), (5, 7), (8, 10), (9, 11), (12, 14), (13, 15),
+        (16, 18), (17, 19), (20, 22), (21, 23), (3, 4), (5, 6), (7, 8), (9, 10), (11, 12), (13, 14),
+        (15, 16), (17, 18), (19, 20), (21, 22), (23, 24)],
+    27 => [(0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11), (12, 13), (14, 26), (15, 24), (16, 23),
+        (17, 21), (19, 25), (20, 22), (0, 2), (1, 3), (4, 8), (5, 9), (10, 12), (11, 13), (15, 20),
+        (16, 17), (18, 25), (21, 23), (22, 24), (0, 4), (1, 2), (3, 7), (5, 8), (6, 10), (9, 13),
+        (11, 12), (14, 18), (15, 16), (17, 20), (21, 22), (23, 24), (25, 26), (0, 6), (1, 5),
+        (3, 9), (4, 10), (7, 13), (8, 12), (18, 20), (19, 23), (22, 25), (24, 26), (2, 10), (3, 11),
+        (4, 6), (7, 9), (14, 19), (17, 22), (18, 21), (20, 25), (23, 24), (1, 3), (2, 8), (5, 11),
+        (6, 7), (10, 12), (14, 15), (16, 19), (20, 23), (21, 22), (24, 25), (0, 14), (1, 4), (2, 6),
+        (3, 5), (7, 11), (8, 10), (9, 12), (15, 17), (16, 18), (19, 20), (23, 24), (2, 4), (3, 6),
+        (5, 8), (7, 10), (9, 11), (12, 26), (15, 16), (17, 18), (19, 21), (20, 22), (1, 15), (3, 4),
+        (5, 6), (7, 8), (9, 10), (11, 25), (16, 17), (18, 19), (20, 21), (22, 23), (2, 16), (6, 7),
+        (8, 22), (9, 23), (10, 24), (17, 18), (19, 20), (3, 17), (4, 18), (6, 20), (7, 21), (8, 14),
+        (9, 15), (10, 16), (13, 19), (4, 8), (5, 13), (6, 10), (11, 17), (12, 18), (16, 20),
+        (19, 23), (2, 4), (5, 9), (6, 8), (7, 11), (12, 14), (13, 15), (17, 21), (18, 22), (23, 25),
+        (1, 2), (3, 5), (7, 9), (10, 12), (11, 13), (14, 16), (15, 17), (18, 20), (19, 21),
+        (22, 24), (25, 26), (3, 4), (5, 6), (7, 8), (9, 10), (11, 12), (13, 14), (15, 16), (17, 18),
+        (19, 20), (21, 22), (23, 24)],
+    28 => [(0, 8), (1, 7), (2, 6), (3, 11), (4, 10), (5, 9), (12, 25), (13, 24), (14, 27), (15, 26),
+        (16, 20), (17, 18), (19, 23), (21, 22), (0, 1), (2, 5), (3, 4), (6, 9), (7, 8), (10, 11),
+        (12, 17), (13, 19), (14, 21), (15, 16), (18, 25), (20, 26), (22, 27), (23, 24), (0, 2),
+        (1, 6), (5, 10), (9, 11), (12, 13), (14, 15), (16, 17), (18, 20), (19, 21), (22, 23),
+        (24, 25), (26, 27), (0, 3), (1, 2), (4, 6), (5, 7), (8, 11), (9, 10), (12, 14), (13, 15),
+        (16, 22), (17, 23), (18, 19), (20, 21), (24, 26), (25, 27), (1, 4), (3, 5), (6, 8), (7, 10),
+        (11, 27), (13, 14), (15, 24), (16, 18), (17, 19), (20, 22), (21, 23), (25, 26), (1, 3),
+        (2, 5), (6, 9), (8, 10), (13, 16), (14, 18), (17, 20), (19, 22), (21, 25), (23, 26), (2, 3),
+        (4, 5), (6, 7), (8, 9), (10, 26), (14, 16), (15, 18), (21, 24), (23, 25), (4, 6), (5, 7),
+        (9, 25), (15, 17), (18, 20), (19, 21), (22, 24), (3, 4), (5, 6), (7, 8), (15, 16), (17, 18),
+        (19, 20), (21, 22), (23, 24), (0, 16), (1, 17), (6, 22), (7, 23), (8, 24), (18, 19),
+        (20, 21), (2, 18), (3, 19), (4, 20), (5, 21), (6, 14), (7, 15), (8, 16), (9, 17), (2, 6),
+        (3, 7), (4, 12), (5, 13), (10, 18), (11, 19), (16, 20), (17, 21), (0, 4), (1, 5), (8, 12),
+        (9, 13), (10, 14), (11, 15), (18, 22), (19, 23), (2, 4), (3, 5), (6, 8), (7, 9), (10, 12),
+        (11, 13), (14, 16), (15, 17), (18, 20), (19, 21), (22, 24), (23, 25), (1, 2), (3, 4),
+        (5, 6), (7, 8), (9, 10), (11, 12), (13, 14), (15, 16), (17, 18), (19, 20), (21, 22),
+        (23, 24), (25, 26)],
+    29 => [(0, 12), (1, 10), (2, 9), (3, 7), (5, 11), (6, 8), (13, 26), (14, 25), (15, 28),
+        (16, 27), (17, 21), (18, 19), (20, 24), (22, 23), (1, 6), (2, 3), (4, 11), (7, 9), (8, 10),
+        (13, 18), (14, 20), (15, 22), (16, 17), (19, 26), (21, 27), (23, 28), (24, 25), (0, 4),
+        (1, 2), (3, 6), (7, 8), (9, 10), (11, 12), (13, 14), (15, 16), (17, 18), (19, 21), (20, 22),
+        (23, 24), (25, 26), (27, 28), (4, 6), (5, 9), (8, 11), (10, 12), (13, 15), (14, 16),
+        (17, 23), (18, 24), (19, 20), (21, 22), (25, 27), (26, 28), (0, 5), (3, 8), (4, 7), (6, 11),
+        (9, 10), (12, 28), (14, 15), (16, 25), (17, 19), (18, 20), (21, 23), (22, 24), (26, 27),
+        (0, 1), (2, 5), (6, 9), (7, 8), (10, 11), (14, 17), (15, 19), (18, 21), (20, 23), (22, 26),
+        (24, 27), (1, 3), (2, 4), (5, 6), (9, 10), (11, 27), (15, 17), (16, 19), (22, 25), (24, 26),
+        (1, 2), (3, 4), (5, 7), (6, 8), (10, 26), (16, 18), (19, 21), (20, 22), (23, 25), (2, 3),
+        (4, 5), (6, 7), (8, 9), (16, 17), (18, 19), (20, 21), (22, 23), (24, 25), (0, 16), (1, 17),
+        (2, 18), (3, 4), (5, 6), (7, 23), (8, 24), (9, 25), (19, 20), (21, 22), (3, 19), (4, 20),
+        (5, 21), (6, 22), (7, 15), (8, 16), (9, 17), (10, 18), (3, 7), (4, 8), (5, 13), (6, 14),
+        (11, 19), (12, 20), (17, 21), (18, 22), (1, 5), (2, 6), (9, 13), (10, 14), (11, 15),
+        (12, 16), (19, 23), (20, 24), (0, 2), (3, 5), (4, 6), (7, 9), (8, 10), (11, 13), (12, 14),
+        (15, 17), (16, 18), (19, 21), (20, 22), (23, 25), (24, 26), (0, 1), (2, 3), (4, 5), (6, 7),
+        (8, 9), (10, 11), (12, 13), (14, 15), (16, 17), (18, 19), (20, 21), (22, 23), (24, 25),
+        (26, 27)],
+    30 => [(0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11), (12, 13), (14, 27), (15, 26), (16, 29),
+        (17, 28), (18, 22), (19, 20), (21, 25), (23, 24), (0, 2), (1, 3), (4, 8), (5, 9), (10, 12),
+        (11, 13), (14, 19), (15, 21), (16, 23), (17, 18), (20, 27), (22, 28), (24, 29), (25, 26),
+        (0, 4), (1, 2), (3, 7), (5, 8), (6, 10), (9, 13), (11, 12), (14, 15), (16, 17), (18, 19),
+        (20, 22), (21, 23), (24, 25), (26, 27), (28, 29), (0, 6), (1, 5), (3, 9), (4, 10), (7, 13),
+        (8, 12), (14, 16), (15, 17), (18, 24), (19, 25), (20, 21), (22, 23), (26, 28), (27, 29),
+        (2, 10), (3, 11), (4, 6), (7, 9), (13, 29), (15, 16), (17, 26), (18, 20), (19, 21),
+        (22, 24), (23, 25), (27, 28), (1, 3), (2, 8), (5, 11), (6, 7), (10, 12), (15, 18), (16, 20),
+        (19, 22), (21, 24), (23, 27), (25, 28), (1, 4), (2, 6), (3, 5), (7, 11), (8, 10), (9, 12),
+        (16, 18), (17, 20), (23, 26), (25, 27), (0, 16), (2, 4), (3, 6), (5, 8), (7, 10), (9, 11),
+        (12, 28), (17, 19), (20, 22), (21, 23), (24, 26), (3, 4), (5, 6), (7, 8), (9, 10), (11, 27),
+        (17, 18), (19, 20), (21, 22), (23, 24), (25, 26), (1, 17), (2, 18), (3, 19), (6, 7),
+        (8, 24), (9, 25), (10, 26), (20, 21), (22, 23), (4, 20), (5, 21), (6, 22), (7, 23), (8, 16),
+        (9, 17), (10, 18), (11, 19), (4, 8), (5, 9), (6, 14), (7, 15), (12, 20), (13, 21), (18, 22),
+        (19, 23), (2, 6), (3, 7), (10, 14), (11, 15), (12, 16), (13, 17), (20, 24), (21, 25),
+        (0, 2), (1, 3), (4, 6), (5, 7), (8, 10), (9, 11), (12, 14), (13, 15), (16, 18), (17, 19),
+        (20, 22), (21, 23), (24, 26), (25, 27), (1, 2), (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (13, 14), (15, 16), (17, 18), (19, 20), (21, 22), (23, 24), (25, 26), (27, 28)],
+    31 => [(0, 13), (1, 12), (3, 14), (4, 8), (5, 6), (7, 11), (9, 10), (15, 28), (16, 27),
+        (17, 30), (18, 29), (19, 23), (20, 21), (22, 26), (24, 25), (0, 5), (1, 7), (2, 9), (3, 4),
+        (6, 13), (8, 14), (11, 12), (15, 20), (16, 22), (17, 24), (18, 19), (21, 28), (23, 29),
+        (25, 30), (26, 27), (0, 1), (2, 3), (4, 5), (6, 8), (7, 9), (10, 11), (12, 13), (15, 16),
+        (17, 18), (19, 20), (21, 23), (22, 24), (25, 26), (27, 28), (29, 30), (0, 2), (1, 3),
+        (4, 10), (5, 11), (6, 7), (8, 9), (12, 14), (15, 17), (16, 18), (19, 25), (20, 26),
+        (21, 22), (23, 24), (27, 29), (28, 30), (1, 2), (3, 12), (4, 6), (5, 7), (8, 10), (9, 11),
+        (13, 14), (16, 17), (18, 27), (19, 21), (20, 22), (23, 25), (24, 26), (28, 29), (1, 4),
+        (2, 6), (5, 8), (7, 10), (9, 13), (11, 14), (16, 19), (17, 21), (20, 23), (22, 25),
+        (24, 28), (26, 29), (0, 16), (2, 4), (3, 6), (9, 12), (11, 13), (14, 30), (17, 19),
+        (18, 21), (24, 27), (26, 28), (1, 17), (3, 5), (6, 8), (7, 9), (10, 12), (13, 29), (18, 20),
+        (21, 23), (22, 24), (25, 27), (3, 4), (5, 6), (7, 8), (9, 10), (11, 12), (18, 19), (20, 21),
+        (22, 23), (24, 25), (26, 27), (2, 18), (3, 19), (4, 20), (6, 7), (8, 9), (10, 26), (11, 27),
+        (12, 28), (21, 22), (23, 24), (5, 21), (6, 22), (7, 23), (8, 24), (9, 25), (10, 18),
+        (11, 19), (12, 20), (6, 10), (7, 15), (8, 16), (9, 17), (13, 21), (14, 22), (19, 23),
+        (20, 24), (3, 7), (4, 8), (5, 9), (11, 15), (12, 16), (13, 17), (14, 18), (21, 25),
+        (22, 26), (1, 3), (2, 4), (5, 7), (6, 8), (9, 11), (10, 12), (13, 15), (14, 16), (17, 19),
+        (18, 20), (21, 23), (22, 24), (25, 27), (26, 28), (0, 1), (2, 3), (4, 5), (6, 7), (8, 9),
+        (10, 11), (12, 13), (14, 15), (16, 17), (18, 19), (20, 21), (22, 23), (24, 25), (26, 27),
+        (28, 29)],
+    32 => [(0, 13), (1, 12), (2, 15), (3, 14), (4, 8), (5, 6), (7, 11), (9, 10), (16, 29), (17, 28),
+        (18, 31), (19, 30), (20, 24), (21, 22), (23, 27), (25, 26), (0, 5), (1, 7), (2, 9), (3, 4),
+        (6, 13), (8, 14), (10, 15), (11, 12), (16, 21), (17, 23), (18, 25), (19, 20), (22, 29),
+        (24, 30), (26, 31), (27, 28), (0, 1), (2, 3), (4, 5), (6, 8), (7, 9), (10, 11), (12, 13),
+        (14, 15), (16, 17), (18, 19), (20, 21), (22, 24), (23, 25), (26, 27), (28, 29), (30, 31),
+        (0, 2), (1, 3), (4, 10), (5, 11), (6, 7), (8, 9), (12, 14), (13, 15), (16, 18), (17, 19),
+        (20, 26), (21, 27), (22, 23), (24, 25), (28, 30), (29, 31), (0, 16), (1, 2), (3, 12),
+        (4, 6), (5, 7), (8, 10), (9, 11), (13, 14), (15, 31), (17, 18), (19, 28), (20, 22),
+        (21, 23), (24, 26), (25, 27), (29, 30), (1, 4), (2, 6), (5, 8), (7, 10), (9, 13), (11, 14),
+        (17, 20), (18, 22), (21, 24), (23, 26), (25, 29), (27, 30), (1, 17), (2, 4), (3, 6),
+        (9, 12), (11, 13), (14, 30), (18, 20), (19, 22), (25, 28), (27, 29), (2, 18), (3, 5),
+        (6, 8), (7, 9), (10, 12), (13, 29), (19, 21), (22, 24), (23, 25), (26, 28), (3, 4), (5, 6),
+        (7, 8), (9, 10), (11, 12), (19, 20), (21, 22), (23, 24), (25, 26), (27, 28), (3, 19),
+        (4, 20), (5, 21), (6, 7), (8, 9), (10, 26), (11, 27), (12, 28), (22, 23), (24, 25), (6, 22),
+        (7, 23), (8, 24), (9, 25), (10, 18), (11, 19), (12, 20), (13, 21), (6, 10), (7, 11),
+        (8, 16), (9, 17), (14, 22), (15, 23), (20, 24), (21, 25), (4, 8), (5, 9), (12, 16),
+        (13, 17), (14, 18), (15, 19), (22, 26), (23, 27), (2, 4), (3, 5), (6, 8), (7, 9), (10, 12),
+        (11, 13), (14, 16), (15, 17), (18, 20), (19, 21), (22, 24), (23, 25), (26, 28), (27, 29),
+        (1, 2), (3, 4), (5, 6), (7, 8), (9, 10), (11, 12), (13, 14), (15, 16), (17, 18), (19, 20),
+        (21, 22), (23, 24), (25, 26), (27, 28), (29, 30)],
+}
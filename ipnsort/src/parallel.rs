@@ -0,0 +1,195 @@
+use std::thread::{self, Scope};
+
+use crate::quicksort::{partition, quicksort};
+use crate::{strict, trace};
+
+/// Configuration of the parallel sorts, see [`crate::par_sort_with_config`].
+#[derive(Clone, Debug)]
+pub struct ParConfig {
+    /// Maximum number of threads that sort at the same time, including the calling thread. With
+    /// one or zero threads the slice is sorted like [`crate::sort`] does.
+    ///
+    /// Defaults to [`std::thread::available_parallelism`].
+    pub threads: usize,
+
+    /// Sub-slices with at most this many elements are sorted by a single thread, starting another
+    /// thread for them would cost more than it saves.
+    ///
+    /// Defaults to 16384.
+    pub sequential_cutoff: usize,
+}
+
+impl Default for ParConfig {
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            sequential_cutoff: 16384,
+        }
+    }
+}
+
+/// State shared by all threads of one parallel sort.
+struct Shared<'a, F> {
+    is_less: &'a F,
+    sequential_cutoff: usize,
+    // Raw pointers are not `Send`, the `strict` checks only need the address.
+    origin: usize,
+}
+
+/// Sorts `v` like [`crate::unstable_sort`], but sorts the sides of large partitions with separate
+/// threads, using up to `config.threads` threads in total.
+pub(crate) fn par_sort<T, F>(v: &mut [T], is_less: &F, config: &ParConfig)
+where
+    T: Send,
+    F: Fn(&T, &T) -> bool + Sync,
+{
+    let len = v.len();
+
+    if config.threads <= 1 || len <= config.sequential_cutoff {
+        crate::unstable_sort(v, is_less);
+        return;
+    }
+
+    // Sorting has no meaningful behavior on zero-sized types.
+    if core::mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    let (streak_end, was_reversed) = crate::find_streak(v, &mut |a, b| is_less(a, b));
+    trace::event!(streak(len, streak_end, was_reversed));
+    if streak_end == len {
+        if was_reversed {
+            v.reverse();
+        }
+
+        return;
+    }
+
+    // Limit the number of imbalanced partitions to `2 * floor(log2(len))`.
+    // The binary OR by one is used to eliminate the zero-check in the logarithm.
+    let limit = 2 * (len | 1).ilog2();
+
+    let shared = Shared {
+        is_less,
+        sequential_cutoff: config.sequential_cutoff,
+        origin: v.as_ptr() as usize,
+    };
+
+    // The scope joins all threads before it returns. If any of them panicked, it panics too.
+    thread::scope(|scope| par_quicksort(scope, &mut *v, &shared, None, limit, config.threads));
+
+    if cfg!(feature = "strict") {
+        strict::check_sorted(v, v.as_ptr(), &mut |a, b| is_less(a, b));
+    }
+}
+
+/// Same as [`quicksort`], but hands the left side of each partition to a new thread, as long as
+/// both sides are larger than the sequential cutoff. `threads` is the number of threads this call
+/// may use, including the current one, it is split between both sides according to their length.
+fn par_quicksort<'scope, 'env, T, F>(
+    scope: &'scope Scope<'scope, 'env>,
+    mut v: &'env mut [T],
+    shared: &'env Shared<'env, F>,
+    mut ancestor_pivot: Option<&'env T>,
+    mut limit: u32,
+    mut threads: usize,
+) where
+    T: Send,
+    F: Fn(&T, &T) -> bool + Sync,
+{
+    let is_less = &mut |a: &T, b: &T| (shared.is_less)(a, b);
+    let origin = shared.origin as *const T;
+
+    loop {
+        let len = v.len();
+
+        // Once all imbalanced partitions are used up, the sequential quicksort switches to
+        // heapsort right away.
+        if threads <= 1 || len <= shared.sequential_cutoff || limit == 0 {
+            quicksort(
+                v,
+                &mut [],
+                is_less,
+                ancestor_pivot,
+                limit,
+                None,
+                origin,
+                &mut |_| {},
+            );
+            return;
+        }
+
+        limit -= 1;
+
+        let pivot_pos = crate::pivot::choose_pivot(v, is_less);
+        trace::event!(pivot(len, pivot_pos));
+
+        // See `quicksort`, elements equal to the ancestor pivot are already in their final
+        // position once they are moved to the front.
+        if let Some(p) = ancestor_pivot {
+            // SAFETY: We assume choose_pivot yields an in-bounds position.
+            if !is_less(p, unsafe { v.get_unchecked(pivot_pos) }) {
+                let mid = partition(v, pivot_pos, &mut |a, b| !is_less(b, a));
+                trace::event!(equal_partition(len, mid));
+
+                if cfg!(feature = "strict") {
+                    strict::check_pair(p, &v[mid], origin, is_less);
+                }
+
+                v = &mut v[(mid + 1)..];
+                ancestor_pivot = None;
+                continue;
+            }
+        }
+
+        let mid = partition(v, pivot_pos, is_less);
+        trace::event!(partition(len, mid));
+
+        if cfg!(feature = "strict") && mid > 0 {
+            strict::check_pair(&v[mid - 1], &v[mid], origin, is_less);
+        }
+
+        let (left, right) = v.split_at_mut(mid);
+        let (pivot, right) = right.split_at_mut(1);
+        let pivot = &pivot[0];
+
+        if left.len() <= shared.sequential_cutoff {
+            quicksort(
+                left,
+                &mut [],
+                is_less,
+                ancestor_pivot,
+                limit,
+                None,
+                origin,
+                &mut |_| {},
+            );
+        } else if right.len() <= shared.sequential_cutoff {
+            quicksort(
+                right,
+                &mut [],
+                is_less,
+                Some(pivot),
+                limit,
+                None,
+                origin,
+                &mut |_| {},
+            );
+
+            // Continue with the left side, with all threads.
+            v = left;
+            continue;
+        } else {
+            // The ancestor pivot can't be shared with the new thread, that would require `T: Sync`.
+            // It only speeds up sorting many duplicates, the new thread will find its own.
+            let left_threads = (threads * left.len() / len).clamp(1, threads - 1);
+            threads -= left_threads;
+
+            scope.spawn(move || par_quicksort(scope, left, shared, None, limit, left_threads));
+        }
+
+        // Continue with the right side.
+        v = right;
+        ancestor_pivot = Some(pivot);
+    }
+}
@@ -1,4 +1,4 @@
-use core::intrinsics;
+use crate::intrinsics;
 
 const PSEUDO_MEDIAN_REC_THRESHOLD: usize = 64;
 
@@ -38,10 +38,131 @@ where
             median3_rec(a, b, c, len_div_8, is_less)
         };
 
-        median_guess.sub_ptr(v_base)
+        median_guess.offset_from_unsigned(v_base)
     }
 }
 
+/// Same as [`choose_pivot`], but the sampled positions are drawn from `rng`, so inputs prepared
+/// against the fixed positions of `choose_pivot` don't yield bad pivots, see
+/// [`crate::sort_randomized`].
+///
+/// Each of the three samples lies in its own third of `v`. Beyond that, the recursive sampling
+/// uses the same relative positions as `choose_pivot`.
+pub(crate) fn choose_pivot_randomized<T, F>(v: &[T], is_less: &mut F, rng: &mut PivotRng) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+
+    if len < T::SMALL_SORT_THRESHOLD {
+        // It's a logic bug if this get's called on slice that would be small-sorted.
+        intrinsics::abort();
+    }
+
+    let len_div_3 = len / 3;
+    let v_base = v.as_ptr();
+
+    // SAFETY: The sampled elements, or sections of `len_div_8` elements, lie within the first,
+    // second and last third of `v` respectively, so all pointer calculations below yield valid
+    // in-bounds pointers.
+    unsafe {
+        let median_guess = if len < PSEUDO_MEDIAN_REC_THRESHOLD {
+            let a = v_base.add(rng.below(len_div_3));
+            let b = v_base.add(len_div_3 + rng.below(len_div_3));
+            let c = v_base.add(len_div_3 * 2 + rng.below(len - len_div_3 * 2));
+            median3(a, b, c, is_less)
+        } else {
+            let len_div_8 = len / 8;
+            let slack = len_div_3 - len_div_8;
+            let a = v_base.add(rng.below(slack));
+            let b = v_base.add(len_div_3 + rng.below(slack));
+            let c = v_base.add(len_div_3 * 2 + rng.below(len - len_div_3 * 2 - len_div_8));
+
+            median3_rec(a, b, c, len_div_8, is_less)
+        };
+
+        median_guess.offset_from_unsigned(v_base)
+    }
+}
+
+/// The random number generator of [`choose_pivot_randomized`], SplitMix64 by Sebastiano Vigna.
+/// Cheap and good enough to keep the sampled positions unpredictable without knowing the seed.
+pub(crate) struct PivotRng {
+    state: u64,
+}
+
+impl PivotRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Derives a seed from the addresses of `v` and of the stack, which differ between processes
+    /// with address space layout randomization.
+    pub(crate) fn from_address<T>(v: &[T]) -> Self {
+        let stack_marker = 0u8;
+        let addr = v.as_ptr() as u64;
+        let stack_addr = &stack_marker as *const u8 as u64;
+        let mut rng = Self::new(addr ^ stack_addr.rotate_left(32) ^ v.len() as u64);
+        // Mixes the address bits, which are mostly equal between calls, into the state.
+        rng.state = rng.next();
+        rng
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`, or 0 if `n` is zero.
+    fn below(&mut self, n: usize) -> usize {
+        ((self.next() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// Selects two pivots for [`crate::quicksort::quicksort_dual_pivot`], the tertiles of an evenly
+/// spaced sample of `v`. Returns their positions `(p1, p2)` with `p1 != p2` and
+/// `!is_less(&v[p2], &v[p1])`.
+///
+/// The sample grows with the length of `v`, but much slower than the square root used by
+/// [`choose_pivot`], as each pivot only has to split off a third.
+pub(crate) fn choose_pivots<T, F>(v: &[T], is_less: &mut F) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+
+    if len < T::SMALL_SORT_THRESHOLD {
+        // It's a logic bug if this get's called on slice that would be small-sorted.
+        intrinsics::abort();
+    }
+
+    let sample_len = if len < 512 {
+        5
+    } else if len < 8192 {
+        11
+    } else {
+        23
+    };
+
+    // The sample is sorted by insertion sort of the positions, leaving `v` untouched.
+    let mut sample = [0usize; 23];
+    let step = len / sample_len;
+    for i in 0..sample_len {
+        let pos = i * step + step / 2;
+        let mut j = i;
+        while j > 0 && is_less(&v[pos], &v[sample[j - 1]]) {
+            sample[j] = sample[j - 1];
+            j -= 1;
+        }
+        sample[j] = pos;
+    }
+
+    (sample[sample_len / 3], sample[sample_len * 2 / 3])
+}
+
 /// Calculates an approximate median of 3 elements from sections a, b, c, or recursively from an
 /// approximation of each, if they're large enough. By dividing the size of each section by 8 when
 /// recursing we have logarithmic recursion depth and overall sample from
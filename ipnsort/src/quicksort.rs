@@ -1,9 +1,10 @@
-use core::intrinsics;
-use core::mem::{self, ManuallyDrop};
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ops::Range;
 use core::ptr;
 
+use crate::pivot::PivotRng;
 use crate::smallsort::SmallSortImpl;
-use crate::{GapGuard, IsTrue};
+use crate::{intrinsics, strict, trace, GapGuard};
 
 /// Sorts `v` recursively.
 ///
@@ -11,36 +12,68 @@ use crate::{GapGuard, IsTrue};
 ///
 /// `limit` is the number of allowed imbalanced partitions before switching to `heapsort`. If zero,
 /// this function will immediately switch to heapsort.
-pub(crate) fn quicksort<'a, T, F>(
+///
+/// `scratch` is lent to the small-sort, it may be empty.
+///
+/// `rng` draws the positions the pivots are sampled from, see [`crate::sort_randomized`]. If
+/// `None`, they are fixed.
+///
+/// `origin` is the start of the slice the sort was called with, the `strict` checks use it to name
+/// indices.
+///
+/// `on_equal_run` is called with the run of equal elements each equal partition isolates, as a
+/// range of positions in the slice starting at `origin`, in ascending order. A run starts with the
+/// ancestor pivot, which always directly precedes `v`, and holds all elements the equal partition
+/// placed behind it. See [`crate::sort_dedup`], the other callers pass a closure that does
+/// nothing.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn quicksort<'a, T, F, R>(
     mut v: &'a mut [T],
+    scratch: &mut [MaybeUninit<T>],
     is_less: &mut F,
     mut ancestor_pivot: Option<&'a T>,
     mut limit: u32,
+    mut rng: Option<&mut PivotRng>,
+    origin: *const T,
+    on_equal_run: &mut R,
 ) where
     F: FnMut(&T, &T) -> bool,
+    R: FnMut(Range<usize>),
 {
-    loop {
-        // println!("len: {}", v.len());
+    let small_sort_threshold = T::small_sort_threshold_with_scratch(scratch.len());
 
-        if v.len() <= T::SMALL_SORT_THRESHOLD {
-            T::small_sort(v, is_less);
+    loop {
+        if v.len() <= small_sort_threshold {
+            trace::event!(small_sort(v.len()));
+            T::small_sort_with_scratch(v, scratch, is_less);
+            if cfg!(feature = "strict") {
+                strict::check_sorted(v, origin, is_less);
+            }
             return;
         }
 
         // If too many bad pivot choices were made, simply fall back to heapsort in order to
         // guarantee `O(n * log(n))` worst-case.
         if limit == 0 {
+            trace::event!(heapsort(v.len()));
             // SAFETY: We assume the `small_sort` threshold is at least 1.
             unsafe {
                 crate::heapsort::heapsort(v, is_less);
             }
+            if cfg!(feature = "strict") {
+                strict::check_sorted(v, origin, is_less);
+            }
             return;
         }
 
         limit -= 1;
 
         // Choose a pivot and try guessing whether the slice is already sorted.
-        let pivot_pos = crate::pivot::choose_pivot(v, is_less);
+        let pivot_pos = match rng.as_deref_mut() {
+            Some(rng) => crate::pivot::choose_pivot_randomized(v, is_less, rng),
+            None => crate::pivot::choose_pivot(v, is_less),
+        };
+        trace::event!(pivot(v.len(), pivot_pos));
 
         // If the chosen pivot is equal to the predecessor, then it's the smallest element in the
         // slice. Partition the slice into elements equal to and elements greater than the pivot.
@@ -49,6 +82,15 @@ pub(crate) fn quicksort<'a, T, F>(
             // SAFETY: We assume choose_pivot yields an in-bounds position.
             if !is_less(p, unsafe { v.get_unchecked(pivot_pos) }) {
                 let mid = partition(v, pivot_pos, &mut |a, b| !is_less(b, a));
+                trace::event!(equal_partition(v.len(), mid));
+
+                // The elements equal to the pivot must not be less than the preceding one.
+                if cfg!(feature = "strict") {
+                    strict::check_pair(p, &v[mid], origin, is_less);
+                }
+
+                let start = (v.as_ptr() as usize - origin as usize) / mem::size_of::<T>();
+                on_equal_run((start - 1)..(start + mid + 1));
 
                 // Continue sorting elements greater than the pivot. We know that mid contains the
                 // pivot. So we can continue after mid.
@@ -60,9 +102,15 @@ pub(crate) fn quicksort<'a, T, F>(
 
         // Partition the slice.
         let mid = partition(v, pivot_pos, is_less);
+        trace::event!(partition(v.len(), mid));
         // SAFETY: partition ensures that `mid` will be in-bounds.
         unsafe { intrinsics::assume(mid < v.len()) };
 
+        // The pivot must not be less than any of the elements that were placed left of it.
+        if cfg!(feature = "strict") && mid > 0 {
+            strict::check_pair(&v[mid - 1], &v[mid], origin, is_less);
+        }
+
         // Split the slice into `left`, `pivot`, and `right`.
         let (left, right) = v.split_at_mut(mid);
         let (pivot, right) = right.split_at_mut(1);
@@ -70,10 +118,242 @@ pub(crate) fn quicksort<'a, T, F>(
 
         // Recurse into the left side. We have a fixed recursion limit, testing shows no real
         // benefit for recursing into the shorter side.
-        quicksort(left, is_less, ancestor_pivot, limit);
+        quicksort(
+            left,
+            scratch,
+            is_less,
+            ancestor_pivot,
+            limit,
+            rng.as_deref_mut(),
+            origin,
+            on_equal_run,
+        );
+
+        // Continue with the right side.
+        v = right;
+        ancestor_pivot = Some(pivot);
+    }
+}
+
+/// Same as [`quicksort`], but partitions `v` around two pivots into three parts, see
+/// [`crate::sort_dual_pivot`].
+///
+/// Each partitioning pass moves every element once and splits off two thirds of the slice, so
+/// there are fewer passes over the data than with one pivot, in exchange for a third move per
+/// element and two comparisons.
+pub(crate) fn quicksort_dual_pivot<'a, T, F>(
+    mut v: &'a mut [T],
+    is_less: &mut F,
+    mut ancestor_pivot: Option<&'a T>,
+    mut limit: u32,
+    origin: *const T,
+) where
+    F: FnMut(&T, &T) -> bool,
+{
+    loop {
+        if v.len() <= T::SMALL_SORT_THRESHOLD {
+            trace::event!(small_sort(v.len()));
+            T::small_sort(v, is_less);
+            if cfg!(feature = "strict") {
+                strict::check_sorted(v, origin, is_less);
+            }
+            return;
+        }
+
+        // See `quicksort`.
+        if limit == 0 {
+            trace::event!(heapsort(v.len()));
+            // SAFETY: We assume the `small_sort` threshold is at least 1.
+            unsafe {
+                crate::heapsort::heapsort(v, is_less);
+            }
+            if cfg!(feature = "strict") {
+                strict::check_sorted(v, origin, is_less);
+            }
+            return;
+        }
+
+        limit -= 1;
+
+        let (pivot_pos1, pivot_pos2) = crate::pivot::choose_pivots(v, is_less);
+
+        // See `quicksort`, if the smaller pivot is equal to the predecessor, then it's the smallest
+        // element in the slice. The equal elements are split off with a single pivot.
+        if let Some(p) = ancestor_pivot {
+            // SAFETY: We assume choose_pivots yields in-bounds positions.
+            if !is_less(p, unsafe { v.get_unchecked(pivot_pos1) }) {
+                trace::event!(pivot(v.len(), pivot_pos1));
+                let mid = partition(v, pivot_pos1, &mut |a, b| !is_less(b, a));
+                trace::event!(equal_partition(v.len(), mid));
+
+                if cfg!(feature = "strict") {
+                    strict::check_pair(p, &v[mid], origin, is_less);
+                }
+
+                v = &mut v[(mid + 1)..];
+                ancestor_pivot = None;
+                continue;
+            }
+        }
+
+        let (mid1, mid2) = partition_dual_pivot(v, pivot_pos1, pivot_pos2, is_less);
+        trace::event!(dual_partition(v.len(), mid1, mid2));
+        // SAFETY: partition_dual_pivot ensures that `mid1 < mid2 < v.len()`.
+        unsafe { intrinsics::assume(mid1 < mid2 && mid2 < v.len()) };
+
+        // Each pivot must not be less than any of the elements that were placed left of it.
+        if cfg!(feature = "strict") {
+            if mid1 > 0 {
+                strict::check_pair(&v[mid1 - 1], &v[mid1], origin, is_less);
+            }
+            strict::check_pair(&v[mid2 - 1], &v[mid2], origin, is_less);
+        }
+
+        // Split the slice into `left`, `pivot1`, `middle`, `pivot2` and `right`.
+        let (left, rest) = v.split_at_mut(mid1);
+        let (pivot1, rest) = rest.split_at_mut(1);
+        let (middle, rest) = rest.split_at_mut(mid2 - mid1 - 1);
+        let (pivot2, right) = rest.split_at_mut(1);
+        let (pivot1, pivot2) = (&pivot1[0], &pivot2[0]);
+
+        quicksort_dual_pivot(left, is_less, ancestor_pivot, limit, origin);
+
+        // The middle holds the elements between the pivots. If they are equal, all of them are
+        // equal to the pivots and already in their final position. Otherwise elements equal to the
+        // smaller pivot are split off by the ancestor check.
+        if is_less(pivot1, pivot2) {
+            quicksort_dual_pivot(middle, is_less, Some(pivot1), limit, origin);
+        }
+
+        // The right side holds only elements greater than the larger pivot, so the ancestor check
+        // can't find any equal to it.
+        v = right;
+        ancestor_pivot = None;
+    }
+}
+
+/// Sorts the `k` smallest elements of `v` into `v[..k]`, the order of `v[k..]` is unspecified.
+///
+/// Same structure as [`quicksort`], but skips partitions that lie entirely beyond `k`.
+pub(crate) fn partial_quicksort<'a, T, F>(
+    mut v: &'a mut [T],
+    mut k: usize,
+    is_less: &mut F,
+    mut ancestor_pivot: Option<&'a T>,
+    origin: *const T,
+) where
+    F: FnMut(&T, &T) -> bool,
+{
+    // Every partition of this loop touches all of `v`, not only the part within `k`. Like in
+    // `select::partition_at_index_loop` the limit needs to be constant, a limit of `ilog2(len)`
+    // would allow *O*(*n* \* log(*n*)) comparisons before the fallback is reached.
+    let mut limit = 16;
+
+    loop {
+        if k == 0 {
+            return;
+        }
+
+        if v.len() <= T::SMALL_SORT_THRESHOLD {
+            trace::event!(small_sort(v.len()));
+            T::small_sort(v, is_less);
+            if cfg!(feature = "strict") {
+                strict::check_sorted(v, origin, is_less);
+            }
+            return;
+        }
+
+        // If too many bad pivot choices were made, select the `k` smallest elements in linear time
+        // and heapsort only those, guaranteeing `O(n + k * log(k))` for the remaining work.
+        if limit == 0 {
+            if k < v.len() {
+                crate::select::median_of_medians(v, is_less, k - 1);
+            }
+
+            if k >= 2 {
+                trace::event!(heapsort(k));
+                // SAFETY: We checked that `k` is at least 2.
+                unsafe {
+                    crate::heapsort::heapsort(&mut v[..k], is_less);
+                }
+                if cfg!(feature = "strict") {
+                    strict::check_sorted(&v[..k], origin, is_less);
+                }
+            }
+            return;
+        }
+
+        limit -= 1;
+
+        // Choose a pivot and try guessing whether the slice is already sorted.
+        let pivot_pos = crate::pivot::choose_pivot(v, is_less);
+        trace::event!(pivot(v.len(), pivot_pos));
+
+        // See `quicksort`, elements equal to the ancestor pivot are already in their final
+        // position once they are moved to the front.
+        if let Some(p) = ancestor_pivot {
+            // SAFETY: We assume choose_pivot yields an in-bounds position.
+            if !is_less(p, unsafe { v.get_unchecked(pivot_pos) }) {
+                let mid = partition(v, pivot_pos, &mut |a, b| !is_less(b, a));
+                trace::event!(equal_partition(v.len(), mid));
+
+                if cfg!(feature = "strict") {
+                    strict::check_pair(p, &v[mid], origin, is_less);
+                }
+
+                if mid + 1 >= k {
+                    return;
+                }
+
+                v = &mut v[(mid + 1)..];
+                k -= mid + 1;
+                ancestor_pivot = None;
+                continue;
+            }
+        }
+
+        // Partition the slice.
+        let mid = partition(v, pivot_pos, is_less);
+        trace::event!(partition(v.len(), mid));
+        // SAFETY: partition ensures that `mid` will be in-bounds.
+        unsafe { intrinsics::assume(mid < v.len()) };
+
+        if cfg!(feature = "strict") && mid > 0 {
+            strict::check_pair(&v[mid - 1], &v[mid], origin, is_less);
+        }
+
+        // Split the slice into `left`, `pivot`, and `right`.
+        let (left, right) = v.split_at_mut(mid);
+        let (pivot, right) = right.split_at_mut(1);
+        let pivot = &pivot[0];
+
+        if mid >= k {
+            // The pivot and everything right of it lie beyond `k`, skip them.
+            v = left;
+            continue;
+        }
+
+        // The left side lies entirely within `k` and has to be fully sorted. It gets its own limit,
+        // all sorted sides together hold fewer than `k` elements.
+        let left_limit = 2 * (left.len() | 1).ilog2();
+        quicksort(
+            left,
+            &mut [],
+            is_less,
+            ancestor_pivot,
+            left_limit,
+            None,
+            origin,
+            &mut |_| {},
+        );
+
+        if mid + 1 >= k {
+            return;
+        }
 
         // Continue with the right side.
         v = right;
+        k -= mid + 1;
         ancestor_pivot = Some(pivot);
     }
 }
@@ -113,7 +393,7 @@ pub(crate) fn quicksort<'a, T, F>(
 /// unspecified. All original elements will remain in `v` and any possible modifications via
 /// interior mutability will be observable. Same is true if `is_less` panics or `v.len()`
 /// exceeds `scratch.len()`.
-fn partition<T, F>(v: &mut [T], pivot: usize, is_less: &mut F) -> usize
+pub(crate) fn partition<T, F>(v: &mut [T], pivot: usize, is_less: &mut F) -> usize
 where
     F: FnMut(&T, &T) -> bool,
 {
@@ -148,40 +428,33 @@ where
     lt_count
 }
 
-trait PartitionImpl: Sized {
+pub(crate) trait PartitionImpl: Sized {
     /// See [`partition`].
     fn partition<F>(v: &mut [Self], pivot: &Self, is_less: &mut F) -> usize
     where
         F: FnMut(&Self, &Self) -> bool;
 }
 
-impl<T> PartitionImpl for T {
-    default fn partition<F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
-    where
-        F: FnMut(&T, &T) -> bool,
-    {
-        partition_hoare_branchy_cyclic(v, pivot, is_less)
-    }
-}
-
-const MAX_BRANCHLESS_PARTITION_SIZE: usize = 96;
+pub(crate) const MAX_BRANCHLESS_PARTITION_SIZE: usize = 96;
 
-/// Specialize for types that are relatively cheap to copy, where branchless optimizations have
-/// large leverage e.g. `u64` and `String`.
-impl<T> PartitionImpl for T
-where
-    (): IsTrue<{ mem::size_of::<T>() <= MAX_BRANCHLESS_PARTITION_SIZE }>,
-{
+// Without specialization pick the partition implementation with a branch that is resolved at
+// compile-time, see `crate::specialize`.
+#[cfg(feature = "stable")]
+impl<T> PartitionImpl for T {
     fn partition<F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
     where
         F: FnMut(&T, &T) -> bool,
     {
-        partition_lomuto_branchless_cyclic(v, pivot, is_less)
+        if const { mem::size_of::<T>() <= MAX_BRANCHLESS_PARTITION_SIZE } {
+            partition_lomuto_branchless_cyclic(v, pivot, is_less)
+        } else {
+            partition_hoare_branchy_cyclic(v, pivot, is_less)
+        }
     }
 }
 
 /// See [`partition`].
-fn partition_hoare_branchy_cyclic<T, F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
+pub(crate) fn partition_hoare_branchy_cyclic<T, F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
 where
     F: FnMut(&T, &T) -> bool,
 {
@@ -255,14 +528,18 @@ where
             left = left.add(1);
         }
 
-        left.sub_ptr(v_base)
+        left.offset_from_unsigned(v_base)
 
         // `gap_opt` goes out of scope and overwrites the last wrong-side element on the right side
         // with the first wrong-side element of the left side that was initially overwritten by the first  wrong-side element on the right side element.
     }
 }
 
-fn partition_lomuto_branchless_cyclic<T, F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
+pub(crate) fn partition_lomuto_branchless_cyclic<T, F>(
+    v: &mut [T],
+    pivot: &T,
+    is_less: &mut F,
+) -> usize
 where
     F: FnMut(&T, &T) -> bool,
 {
@@ -345,3 +622,150 @@ where
         lt_count
     }
 }
+
+/// Takes the input slice `v` and re-arranges elements around the two pivots `p1 = v[pivot_pos1]`
+/// and `p2 = v[pivot_pos2]`, which must satisfy `!is_less(p2, p1)`. When the call returns
+/// normally, `v` holds the elements that compare true for `is_less(elem, p1)`, followed by `p1`,
+/// the elements that compare false for both `is_less(elem, p1)` and `is_less(p2, elem)`, `p2`, and
+/// the elements that compare true for `is_less(p2, elem)`.
+///
+/// Returns the new positions of `p1` and `p2`.
+///
+/// If `is_less` does not implement a total order the resulting order and return value are
+/// unspecified. All original elements will remain in `v` and any possible modifications via
+/// interior mutability will be observable. Same is true if `is_less` panics.
+pub(crate) fn partition_dual_pivot<T, F>(
+    v: &mut [T],
+    pivot_pos1: usize,
+    pivot_pos2: usize,
+    is_less: &mut F,
+) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+
+    // Allows for panic-free code-gen by proving these properties to the compiler.
+    if len < 2 || pivot_pos1 >= len || pivot_pos2 >= len || pivot_pos1 == pivot_pos2 {
+        intrinsics::abort();
+    }
+
+    // Place the pivots at the beginning and end of the slice.
+    v.swap(0, pivot_pos1);
+    let pivot_pos2 = if pivot_pos2 == 0 {
+        pivot_pos1
+    } else {
+        pivot_pos2
+    };
+    v.swap(len - 1, pivot_pos2);
+
+    // See `partition` for why the pivots are passed as references into `v`.
+    let (pivot1, rest) = v.split_at_mut(1);
+    let (v_without_pivots, pivot2) = rest.split_at_mut(len - 2);
+    let (lt_count, le_count) = partition_dual_pivot_lomuto_branchless_cyclic(
+        v_without_pivots,
+        &pivot1[0],
+        &pivot2[0],
+        is_less,
+    );
+
+    // Place the pivots between the three partitions.
+    v.swap(0, lt_count);
+    v.swap(len - 1, le_count + 1);
+
+    (lt_count, le_count + 1)
+}
+
+/// See [`partition_dual_pivot`]. Returns the number of elements less than `p1`, and the number of
+/// elements not greater than `p2`.
+///
+/// Extends [`partition_lomuto_branchless_cyclic`] to three regions. The front holds the elements
+/// less than `p1`, followed by the ones between the pivots and the ones greater than `p2`, and the
+/// gap at the end of the processed elements. Each element moves the first element of the greater
+/// region into the gap. An element that belongs into the middle or greater region takes its place,
+/// an element less than `p1` takes the place of the first middle element instead, which moves to
+/// the end of the middle region. All three copies are done unconditionally, with the sources and
+/// destinations selected without branches.
+fn partition_dual_pivot_lomuto_branchless_cyclic<T, F>(
+    v: &mut [T],
+    pivot1: &T,
+    pivot2: &T,
+    is_less: &mut F,
+) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+    let v_base = v.as_mut_ptr();
+
+    if len == 0 {
+        return (0, 0);
+    }
+
+    // See `partition_lomuto_branchless_cyclic`.
+    let unroll_len = if const { mem::size_of::<T>() <= 16 } {
+        2
+    } else {
+        1
+    };
+
+    // SAFETY: Same as `partition_lomuto_branchless_cyclic`, with `lt_count <= le_count` both
+    // bounded by the number of processed elements, so `v_base.add(lt_count)` and
+    // `v_base.add(le_count)` are at most the gap position and in-bounds. An element that compares
+    // true against both pivots, which is only possible if `is_less` does not implement a total
+    // order, is treated like one less than `p1`, keeping `lt_count <= le_count`. `right` is always
+    // past the gap, or the saved gap value, so it never aliases any of the destinations.
+    unsafe {
+        let mut lt_count = 0;
+        let mut le_count = 0;
+        let mut right = v_base.add(1);
+
+        let mut gap = GapGuard {
+            pos: v_base,
+            value: ManuallyDrop::new(ptr::read(v_base)),
+        };
+
+        macro_rules! loop_body {
+            () => {{
+                let right_is_lt = is_less(&*right, pivot1);
+                let right_is_gt = is_less(pivot2, &*right);
+                let lt = v_base.add(lt_count);
+                let le = v_base.add(le_count);
+
+                let middle_src = if right_is_lt { lt } else { right };
+                let right_dst = if right_is_lt { lt } else { le };
+                ptr::copy(le, gap.pos, 1);
+                ptr::copy(middle_src, le, 1);
+                ptr::copy_nonoverlapping(right, right_dst, 1);
+
+                gap.pos = right;
+                lt_count += right_is_lt as usize;
+                le_count += (right_is_lt | !right_is_gt) as usize;
+
+                right = right.add(1);
+            }};
+        }
+
+        let unroll_end = v_base.add(len - (unroll_len - 1));
+        while right < unroll_end {
+            for _ in 0..unroll_len {
+                loop_body!();
+            }
+        }
+
+        let end = v_base.add(len);
+        loop {
+            let is_done = right == end;
+            right = if is_done { &mut *gap.value } else { right };
+
+            loop_body!();
+
+            if is_done {
+                mem::forget(gap);
+                break;
+            }
+        }
+
+        (lt_count, le_count)
+    }
+}
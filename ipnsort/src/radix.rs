@@ -0,0 +1,237 @@
+//! Only the nightly specialization picks radix sort, see `crate::specialize`.
+#![cfg_attr(feature = "stable", allow(dead_code))]
+
+use core::ptr;
+
+use crate::trace;
+use crate::unstable_sort;
+
+/// Slices and buckets shorter than this are sorted by comparisons, where the counting passes don't
+/// pay off.
+const RADIX_SORT_THRESHOLD: usize = 1024;
+
+/// Buckets shorter than this are only radix sorted further if one pass finishes them. Otherwise
+/// the next pass would leave buckets that are too short for the comparison sort to make up for
+/// it, measured on `random` with `i32`.
+const RADIX_SORT_BUCKET_THRESHOLD: usize = 16 * 1024;
+
+/// Keys spanning a range with more bits than this are sorted by comparisons. Radix sorting keys
+/// with up to 64 bits measured no faster on `random` with `u64`.
+const MAX_RADIX_SORT_BITS: u32 = 32;
+
+pub(crate) trait SortImpl: Ord + Sized {
+    /// Sorts `v` in the order of [`Ord`], see [`crate::sort`].
+    fn sort(v: &mut [Self]);
+}
+
+// Without specialization every type is sorted by comparisons, see `crate::specialize`.
+#[cfg(feature = "stable")]
+impl<T: Ord> SortImpl for T {
+    fn sort(v: &mut [T]) {
+        unstable_sort(v, T::lt);
+    }
+}
+
+/// Primitive integers, which can be sorted by the bytes of a key with the same order.
+pub(crate) trait RadixKey: Copy + Ord {
+    /// Returns the key in the low bits, zero-extended, ordered like `self` when compared as
+    /// unsigned integers.
+    fn radix_key(self) -> u64;
+}
+
+macro_rules! radix_key_impl {
+    ($($int:ty),*) => {$(
+        impl RadixKey for $int {
+            #[inline(always)]
+            fn radix_key(self) -> u64 {
+                // Flipping the sign bit maps the negative values of signed integers below the
+                // positive ones. `MIN` is zero for unsigned integers.
+                (self ^ <$int>::MIN) as u64 & (u64::MAX >> (64 - <$int>::BITS))
+            }
+        }
+    )*};
+}
+
+radix_key_impl!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// Sorts `v` with in-place MSD radix sort, also known as American flag sort, unless it is short,
+/// starts or ends with a long sorted streak or its keys span a wide range. Those are sorted like
+/// any other type, which e.g. detects and merges the streaks.
+pub(crate) fn radix_sort<T: RadixKey>(v: &mut [T]) {
+    let len = v.len();
+
+    if len < RADIX_SORT_THRESHOLD {
+        unstable_sort(v, T::lt);
+        return;
+    }
+
+    let (streak_end, was_reversed) = crate::find_streak(v, &mut T::lt);
+    if streak_end == len {
+        trace::event!(streak(len, streak_end, was_reversed));
+        if was_reversed {
+            v.reverse();
+        }
+
+        return;
+    }
+
+    // The comparison sort merges long presorted prefixes and suffixes, which is faster. It
+    // reports the streak itself.
+    let (suffix_start, _) = crate::find_suffix_streak(v, &mut T::lt);
+    if streak_end.max(len - suffix_start) >= len / 2 {
+        unstable_sort(v, T::lt);
+        return;
+    }
+
+    let (min_key, bits) = key_range(v);
+    if bits > MAX_RADIX_SORT_BITS {
+        unstable_sort(v, T::lt);
+        return;
+    }
+
+    trace::event!(streak(len, streak_end, was_reversed));
+    trace::event!(radix_sort(len));
+    american_flag_sort(v, min_key, bits);
+}
+
+/// Returns the smallest key of `v` and the number of bits of the difference to the largest one.
+fn key_range<T: RadixKey>(v: &[T]) -> (u64, u32) {
+    let (min_key, max_key) = v.iter().fold((u64::MAX, 0), |(min_key, max_key), elem| {
+        let key = elem.radix_key();
+        (min_key.min(key), max_key.max(key))
+    });
+
+    (min_key, u64::BITS - (max_key - min_key).leading_zeros())
+}
+
+/// Sorts `v` by the distance of the keys to `min_key`, which spans `bits` bits.
+fn american_flag_sort<T: RadixKey>(v: &mut [T], min_key: u64, bits: u32) {
+    // The digits are taken relative to the smallest key and aligned to the highest bit of the
+    // range, so the highest digit can't put all elements into the same bucket. Common leading
+    // bits, e.g. of small values in a large type or of values close to zero with both signs, are
+    // skipped without counting them.
+    let shift = bits.saturating_sub(8);
+    let digit = |elem: &T| (((elem.radix_key() - min_key) >> shift) & 0xFF) as usize;
+
+    let mut counts = [0usize; 256];
+    for elem in v.iter() {
+        counts[digit(elem)] += 1;
+    }
+
+    if shift == 0 {
+        // Elements in the same bucket of the lowest byte are equal, so each bucket can be filled
+        // with copies of one of its elements.
+        let mut values = [v[0]; 256];
+        for elem in v.iter() {
+            values[digit(elem)] = *elem;
+        }
+
+        let mut start = 0;
+        for (count, value) in counts.into_iter().zip(values) {
+            v[start..start + count].fill(value);
+            start += count;
+        }
+
+        return;
+    }
+
+    // `heads[b]` is the next position in bucket `b` that doesn't hold an element of it yet,
+    // `tails[b]` the end of the bucket.
+    let mut heads = counts;
+    let mut tails = [0usize; 256];
+    let mut sum = 0;
+    for (head, tail) in heads.iter_mut().zip(tails.iter_mut()) {
+        let count = *head;
+        *head = sum;
+        sum += count;
+        *tail = sum;
+    }
+
+    // Instead of following the cycle of each element, which makes every swap wait for the
+    // previous one, the unfilled part of each bucket is swept and each element in it is swapped
+    // into the bucket it belongs to. The element swapped in its place may belong somewhere else,
+    // so the sweeps repeat until every bucket is filled. Unrolling the sweep lets the swaps of
+    // four elements overlap.
+    let mut remaining = [0u8; 256];
+    let mut remaining_len = 0;
+    for bucket in 0..256 {
+        if heads[bucket] < tails[bucket] {
+            remaining[remaining_len] = bucket as u8;
+            remaining_len += 1;
+        }
+    }
+
+    let v_base = v.as_mut_ptr();
+    while remaining_len > 0 {
+        for &bucket in &remaining[..remaining_len] {
+            let bucket = bucket as usize;
+            let end = tails[bucket];
+            let mut i = heads[bucket];
+
+            // SAFETY: `i` only visits the unfilled part of the current bucket, and `heads[b]`
+            // only advances while bucket `b` has elements in the wrong place, so all positions
+            // are in bounds. The four positions read at once are all swapped with positions in
+            // other buckets or before them, so each digit still belongs to the element at `i + k`
+            // when it is swapped.
+            unsafe {
+                while i + 4 <= end {
+                    let d0 = digit(&*v_base.add(i));
+                    let d1 = digit(&*v_base.add(i + 1));
+                    let d2 = digit(&*v_base.add(i + 2));
+                    let d3 = digit(&*v_base.add(i + 3));
+
+                    let t0 = heads[d0];
+                    heads[d0] += 1;
+                    let t1 = heads[d1];
+                    heads[d1] += 1;
+                    let t2 = heads[d2];
+                    heads[d2] += 1;
+                    let t3 = heads[d3];
+                    heads[d3] += 1;
+
+                    ptr::swap(v_base.add(i), v_base.add(t0));
+                    ptr::swap(v_base.add(i + 1), v_base.add(t1));
+                    ptr::swap(v_base.add(i + 2), v_base.add(t2));
+                    ptr::swap(v_base.add(i + 3), v_base.add(t3));
+                    i += 4;
+                }
+
+                while i < end {
+                    let d = digit(&*v_base.add(i));
+                    let t = heads[d];
+                    heads[d] += 1;
+                    ptr::swap(v_base.add(i), v_base.add(t));
+                    i += 1;
+                }
+            }
+        }
+
+        let mut kept = 0;
+        for r in 0..remaining_len {
+            let bucket = remaining[r];
+            if heads[bucket as usize] < tails[bucket as usize] {
+                remaining[kept] = bucket;
+                kept += 1;
+            }
+        }
+        remaining_len = kept;
+    }
+
+    let mut start = 0;
+    for end in tails {
+        let bucket = &mut v[start..end];
+        if bucket.len() < RADIX_SORT_THRESHOLD {
+            unstable_sort(bucket, T::lt);
+        } else {
+            let (min_key, bits) = key_range(bucket);
+            if bits <= 8 || bucket.len() >= RADIX_SORT_BUCKET_THRESHOLD {
+                if bits > 0 {
+                    american_flag_sort(bucket, min_key, bits);
+                }
+            } else {
+                unstable_sort(bucket, T::lt);
+            }
+        }
+        start = end;
+    }
+}
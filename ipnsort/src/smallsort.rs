@@ -1,8 +1,11 @@
-use core::intrinsics;
+// The sorting-network and merge based small-sorts rely on specialization, with the `stable`
+// feature only the insertion sort based small-sort is used.
+#![cfg_attr(feature = "stable", allow(dead_code))]
+
 use core::mem::{self, ManuallyDrop, MaybeUninit};
 use core::ptr;
 
-use crate::{has_efficient_in_place_swap, Freeze, GapGuard, IsTrue};
+use crate::{intrinsics, Freeze, GapGuard};
 
 // Use a trait to focus code-gen on only the parts actually relevant for the type. Avoid generating
 // LLVM-IR for the sorting-network and median-networks for types that don't qualify.
@@ -13,12 +16,28 @@ pub(crate) trait SmallSortImpl: Sized {
     fn small_sort<F>(v: &mut [Self], is_less: &mut F)
     where
         F: FnMut(&Self, &Self) -> bool;
+
+    /// Returns the length up to which `small_sort_with_scratch` is used, given `scratch_len`
+    /// elements of scratch space. Never less than `SMALL_SORT_THRESHOLD`.
+    fn small_sort_threshold_with_scratch(scratch_len: usize) -> usize;
+
+    /// Same as `small_sort`, but may use `scratch` to sort slices up to
+    /// `small_sort_threshold_with_scratch(scratch.len())` elements.
+    fn small_sort_with_scratch<F>(
+        v: &mut [Self],
+        scratch: &mut [MaybeUninit<Self>],
+        is_less: &mut F,
+    ) where
+        F: FnMut(&Self, &Self) -> bool;
 }
 
+// See `crate::specialize` for the type specific implementations. Without specialization every
+// type uses the small-sort that is valid for all types.
+#[cfg(feature = "stable")]
 impl<T> SmallSortImpl for T {
-    default const SMALL_SORT_THRESHOLD: usize = 20;
+    const SMALL_SORT_THRESHOLD: usize = 20;
 
-    default fn small_sort<F>(v: &mut [T], is_less: &mut F)
+    fn small_sort<F>(v: &mut [T], is_less: &mut F)
     where
         F: FnMut(&T, &T) -> bool,
     {
@@ -26,35 +45,16 @@ impl<T> SmallSortImpl for T {
             insertion_sort_shift_left(v, 1, is_less);
         }
     }
-}
-
-impl<T: Freeze> SmallSortImpl for T {
-    default const SMALL_SORT_THRESHOLD: usize = 20;
 
-    default fn small_sort<F>(v: &mut [T], is_less: &mut F)
-    where
-        F: FnMut(&T, &T) -> bool,
-    {
-        small_sort_general(v, is_less);
+    fn small_sort_threshold_with_scratch(_scratch_len: usize) -> usize {
+        T::SMALL_SORT_THRESHOLD
     }
-}
-
-impl<T> SmallSortImpl for T
-where
-    T: Freeze + Copy,
-    (): IsTrue<{ has_efficient_in_place_swap::<T>() }>,
-{
-    const SMALL_SORT_THRESHOLD: usize = 32;
 
-    fn small_sort<F>(v: &mut [T], is_less: &mut F)
+    fn small_sort_with_scratch<F>(v: &mut [T], _scratch: &mut [MaybeUninit<T>], is_less: &mut F)
     where
         F: FnMut(&T, &T) -> bool,
     {
-        // I suspect that generalized efficient indirect branchless sorting constructs like
-        // sort4_indirect for larger sizes exist. But finding them is an open research problem.
-        // And even then it's not clear that they would be better than in-place sorting-networks
-        // as used in small_sort_network.
-        small_sort_network(v, is_less);
+        T::small_sort(v, is_less);
     }
 }
 
@@ -374,7 +374,7 @@ where
     let len = v.len();
     const MAX_BRANCHLESS_SMALL_SORT: usize = i32::SMALL_SORT_THRESHOLD;
 
-    if len < 18 || len > MAX_BRANCHLESS_SMALL_SORT {
+    if !(18..=MAX_BRANCHLESS_SMALL_SORT).contains(&len) {
         intrinsics::abort();
     }
 
@@ -404,14 +404,14 @@ where
     // Should is_less panic v was not modified in parity_merge and retains it's original input.
     // scratch and v must not alias and scratch has v.len() space.
     unsafe {
-        bi_directional_merge_even(&mut v[..even_len], scratch_base, is_less);
+        bi_directional_merge_even(&v[..even_len], scratch_base, is_less);
         ptr::copy_nonoverlapping(scratch_base, v.as_mut_ptr(), even_len);
     }
 
     even_len
 }
 
-fn small_sort_network<T, F>(v: &mut [T], is_less: &mut F)
+pub(crate) fn small_sort_network<T, F>(v: &mut [T], is_less: &mut F)
 where
     T: Freeze,
     F: FnMut(&T, &T) -> bool,
@@ -440,20 +440,45 @@ where
     }
 }
 
-fn small_sort_general<T, F>(v: &mut [T], is_less: &mut F)
+pub(crate) fn small_sort_general<T, F>(v: &mut [T], is_less: &mut F)
 where
     T: Freeze,
     F: FnMut(&T, &T) -> bool,
+{
+    const SCRATCH_LEN: usize = String::SMALL_SORT_THRESHOLD + SMALL_SORT_GENERAL_SCRATCH_EXTRA;
+    let mut scratch = [const { MaybeUninit::<T>::uninit() }; SCRATCH_LEN];
+
+    if SCRATCH_LEN < (T::SMALL_SORT_THRESHOLD + SMALL_SORT_GENERAL_SCRATCH_EXTRA) {
+        intrinsics::abort();
+    }
+
+    small_sort_general_with_scratch(v, &mut scratch, is_less);
+}
+
+/// Scratch space needed by `small_sort_general_with_scratch` in addition to the length of the
+/// slice.
+pub(crate) const SMALL_SORT_GENERAL_SCRATCH_EXTRA: usize = 16;
+
+/// Length up to which `small_sort_general_with_scratch` is used, if the caller provides enough
+/// scratch space. Longer slices spend too much time in the insertion sort phase.
+pub(crate) const SMALL_SORT_GENERAL_SCRATCH_THRESHOLD: usize = 32;
+
+/// Same as `small_sort_general`, but uses the caller provided `scratch` which must be at least
+/// `v.len() + SMALL_SORT_GENERAL_SCRATCH_EXTRA` long.
+pub(crate) fn small_sort_general_with_scratch<T, F>(
+    v: &mut [T],
+    scratch: &mut [MaybeUninit<T>],
+    is_less: &mut F,
+) where
+    T: Freeze,
+    F: FnMut(&T, &T) -> bool,
 {
     // This implementation is tuned to be efficient for various types that are larger than u64.
 
     let len = v.len();
 
     if len >= 2 {
-        const SCRATCH_LEN: usize = String::SMALL_SORT_THRESHOLD + 16;
-        let mut scratch = MaybeUninit::<[T; SCRATCH_LEN]>::uninit();
-
-        if SCRATCH_LEN < (T::SMALL_SORT_THRESHOLD + 16) {
+        if scratch.len() < len + SMALL_SORT_GENERAL_SCRATCH_EXTRA {
             intrinsics::abort();
         }
 
@@ -467,17 +492,14 @@ where
                 let scratch_base = scratch.as_mut_ptr() as *mut T;
 
                 let presorted_len = if len >= 16 {
-                    // SAFETY: scratch_base is valid and has enough space.
-                    sort8_stable(
-                        v_base,
-                        scratch_base.add(T::SMALL_SORT_THRESHOLD),
-                        scratch_base,
-                        is_less,
-                    );
+                    // SAFETY: scratch_base is valid and has enough space. The first `len`
+                    // elements hold the sorted halves, the 16 after that are used as temporary
+                    // space by `sort8_stable`.
+                    sort8_stable(v_base, scratch_base.add(len), scratch_base, is_less);
 
                     sort8_stable(
                         v_base.add(len_div_2),
-                        scratch_base.add(T::SMALL_SORT_THRESHOLD + 8),
+                        scratch_base.add(len + 8),
                         scratch_base.add(len_div_2),
                         is_less,
                     );
@@ -0,0 +1,231 @@
+//! Type specific implementation choices, based on specialization and other nightly-only features.
+//! With the `stable` feature this module is disabled and each trait has a single generic impl next
+//! to its definition.
+
+use core::cmp::Ordering;
+use core::mem::{self, MaybeUninit};
+
+use crate::argsort::{argsort_indirect, ArgsortImpl, ArgsortIndex};
+use crate::quicksort::{
+    partition_hoare_branchy_cyclic, partition_lomuto_branchless_cyclic, PartitionImpl,
+    MAX_BRANCHLESS_PARTITION_SIZE,
+};
+use crate::radix::{radix_sort, RadixKey, SortImpl};
+use crate::smallsort::{
+    insertion_sort_shift_left, small_sort_general, small_sort_general_with_scratch,
+    small_sort_network, SmallSortImpl, SMALL_SORT_GENERAL_SCRATCH_EXTRA,
+    SMALL_SORT_GENERAL_SCRATCH_THRESHOLD,
+};
+use crate::unstable_sort;
+
+// // #[rustc_unsafe_specialization_marker]
+// trait Freeze {}
+
+/// Can the type have interior mutability, this is checked by testing if T is Freeze. If the type can
+/// have interior mutability it may alter itself during comparison in a way that must be observed
+/// after the sort operation concludes. Otherwise a type like Mutex<Option<Box<str>>> could lead to
+/// double free.
+///
+/// # Safety
+///
+/// Implementing types must not have interior mutability.
+pub(crate) unsafe auto trait Freeze {}
+
+impl<T: ?Sized> !Freeze for core::cell::UnsafeCell<T> {}
+unsafe impl<T: ?Sized> Freeze for core::marker::PhantomData<T> {}
+unsafe impl<T: ?Sized> Freeze for *const T {}
+unsafe impl<T: ?Sized> Freeze for *mut T {}
+unsafe impl<T: ?Sized> Freeze for &T {}
+unsafe impl<T: ?Sized> Freeze for &mut T {}
+
+#[must_use]
+const fn has_efficient_in_place_swap<T>() -> bool {
+    const MEM_SIZE_U64: usize = mem::size_of::<u64>();
+
+    mem::size_of::<T>() <= MEM_SIZE_U64
+}
+
+#[test]
+fn type_info() {
+    assert!(has_efficient_in_place_swap::<i32>());
+    assert!(has_efficient_in_place_swap::<u64>());
+    assert!(!has_efficient_in_place_swap::<u128>());
+    assert!(!has_efficient_in_place_swap::<String>());
+}
+
+trait IsTrue<const B: bool> {}
+impl IsTrue<true> for () {}
+
+// --- SortImpl ---
+
+impl<T: Ord> SortImpl for T {
+    default fn sort(v: &mut [T]) {
+        unstable_sort(v, T::lt);
+    }
+}
+
+/// Specialize for primitive integers, where the natural order is the order of their bytes.
+impl<T: RadixKey> SortImpl for T {
+    fn sort(v: &mut [T]) {
+        radix_sort(v);
+    }
+}
+
+// --- SmallSortImpl ---
+
+impl<T> SmallSortImpl for T {
+    default const SMALL_SORT_THRESHOLD: usize = 20;
+
+    default fn small_sort<F>(v: &mut [T], is_less: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        if v.len() >= 2 {
+            insertion_sort_shift_left(v, 1, is_less);
+        }
+    }
+
+    default fn small_sort_threshold_with_scratch(_scratch_len: usize) -> usize {
+        T::SMALL_SORT_THRESHOLD
+    }
+
+    default fn small_sort_with_scratch<F>(
+        v: &mut [T],
+        _scratch: &mut [MaybeUninit<T>],
+        is_less: &mut F,
+    ) where
+        F: FnMut(&T, &T) -> bool,
+    {
+        T::small_sort(v, is_less);
+    }
+}
+
+impl<T: Freeze> SmallSortImpl for T {
+    default const SMALL_SORT_THRESHOLD: usize = 20;
+
+    default fn small_sort<F>(v: &mut [T], is_less: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        small_sort_general(v, is_less);
+    }
+
+    default fn small_sort_threshold_with_scratch(scratch_len: usize) -> usize {
+        if scratch_len >= SMALL_SORT_GENERAL_SCRATCH_THRESHOLD + SMALL_SORT_GENERAL_SCRATCH_EXTRA {
+            SMALL_SORT_GENERAL_SCRATCH_THRESHOLD
+        } else {
+            T::SMALL_SORT_THRESHOLD
+        }
+    }
+
+    default fn small_sort_with_scratch<F>(
+        v: &mut [T],
+        scratch: &mut [MaybeUninit<T>],
+        is_less: &mut F,
+    ) where
+        F: FnMut(&T, &T) -> bool,
+    {
+        // Use the caller provided scratch space if possible, instead of the one on the stack.
+        if scratch.len() >= v.len() + SMALL_SORT_GENERAL_SCRATCH_EXTRA {
+            small_sort_general_with_scratch(v, scratch, is_less);
+        } else {
+            small_sort_general(v, is_less);
+        }
+    }
+}
+
+impl<T> SmallSortImpl for T
+where
+    T: Freeze + Copy,
+    (): IsTrue<{ has_efficient_in_place_swap::<T>() }>,
+{
+    const SMALL_SORT_THRESHOLD: usize = 32;
+
+    fn small_sort<F>(v: &mut [T], is_less: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        // I suspect that generalized efficient indirect branchless sorting constructs like
+        // sort4_indirect for larger sizes exist. But finding them is an open research problem.
+        // And even then it's not clear that they would be better than in-place sorting-networks
+        // as used in small_sort_network.
+        small_sort_network(v, is_less);
+    }
+
+    // The sorting-networks are faster than the merge based small-sort for these types, they don't
+    // benefit from scratch space.
+    fn small_sort_threshold_with_scratch(_scratch_len: usize) -> usize {
+        T::SMALL_SORT_THRESHOLD
+    }
+
+    fn small_sort_with_scratch<F>(v: &mut [T], _scratch: &mut [MaybeUninit<T>], is_less: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        small_sort_network(v, is_less);
+    }
+}
+
+// --- PartitionImpl ---
+
+impl<T> PartitionImpl for T {
+    default fn partition<F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        partition_hoare_branchy_cyclic(v, pivot, is_less)
+    }
+}
+
+/// Specialize for types that are relatively cheap to copy, where branchless optimizations have
+/// large leverage e.g. `u64` and `String`.
+impl<T> PartitionImpl for T
+where
+    (): IsTrue<{ mem::size_of::<T>() <= MAX_BRANCHLESS_PARTITION_SIZE }>,
+{
+    fn partition<F>(v: &mut [T], pivot: &T, is_less: &mut F) -> usize
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        partition_lomuto_branchless_cyclic(v, pivot, is_less)
+    }
+}
+
+// --- ArgsortImpl ---
+
+impl<T> ArgsortImpl for T {
+    default fn argsort<I, F>(v: &[T], compare: &mut F) -> Vec<usize>
+    where
+        I: ArgsortIndex,
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        argsort_indirect::<T, I, F>(v, compare)
+    }
+}
+
+/// Specialize for types that are cheap to copy. Sorting compact key/index pairs avoids the
+/// indirection of looking up the key for each comparison. Limited to types without interior
+/// mutability, because the comparison function only observes the copies.
+impl<T> ArgsortImpl for T
+where
+    T: Freeze + Copy,
+    (): IsTrue<{ has_efficient_in_place_swap::<T>() }>,
+{
+    fn argsort<I, F>(v: &[T], compare: &mut F) -> Vec<usize>
+    where
+        I: ArgsortIndex,
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mut pairs = v
+            .iter()
+            .enumerate()
+            .map(|(i, elem)| (*elem, I::from_usize(i)))
+            .collect::<Vec<_>>();
+
+        unstable_sort(&mut pairs, |a, b| {
+            compare(&a.0, &b.0).then(a.1.cmp(&b.1)) == Ordering::Less
+        });
+
+        pairs.into_iter().map(|(_, i)| i.to_usize()).collect()
+    }
+}
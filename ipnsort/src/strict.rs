@@ -0,0 +1,57 @@
+use core::mem;
+
+// The checks in this module are only called if the `strict` feature is enabled. Each of them asks
+// `is_less` about a pair of elements that was already ordered by earlier comparisons, if the
+// answer contradicts that order, `is_less` does not implement a strict total order. This costs
+// roughly `2 * len` additional comparisons, one pass after each small-sort or heapsort region and
+// one over the final result.
+
+/// Panics if `is_less` reports any element of `v` as less than its predecessor.
+///
+/// `origin` is the start of the slice the sort was called with, and is used to name indices.
+pub(crate) fn check_sorted<T, F>(v: &[T], origin: *const T, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    for pair in v.windows(2) {
+        check_pair(&pair[0], &pair[1], origin, is_less);
+    }
+}
+
+/// Panics if `is_less` reports any element of `tail` as less than `max`, which was placed before
+/// all of them.
+pub(crate) fn check_not_less<T, F>(max: &T, tail: &[T], origin: *const T, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    for elem in tail {
+        check_pair(max, elem, origin, is_less);
+    }
+}
+
+/// Panics if `is_less` reports `b` as less than `a`, after earlier comparisons placed `a` before
+/// `b`.
+#[inline(always)]
+pub(crate) fn check_pair<T, F>(a: &T, b: &T, origin: *const T, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    if is_less(b, a) {
+        panic_on_ord_violation(index_of(origin, a), index_of(origin, b));
+    }
+}
+
+/// Returns the index of `elem` in the slice starting at `origin`.
+fn index_of<T>(origin: *const T, elem: &T) -> usize {
+    // Zero-sized types never reach the sort implementation.
+    (elem as *const T as usize).wrapping_sub(origin as usize) / mem::size_of::<T>()
+}
+
+#[cold]
+#[inline(never)]
+fn panic_on_ord_violation(a: usize, b: usize) -> ! {
+    panic!(
+        "Ord violation: v[{b}] compared less than v[{a}], after earlier comparisons ordered v[{a}] \
+         before v[{b}]. The comparison function does not implement a strict total order."
+    );
+}
@@ -0,0 +1,178 @@
+use core::mem::{self, ManuallyDrop};
+use core::ptr;
+
+/// Partitions with at most this many strings are sorted by comparing their keys.
+const COMPARISON_SORT_THRESHOLD: usize = 32;
+
+/// Sorts `v` with multikey quicksort, see [`crate::sort_strings`].
+pub(crate) fn sort_strings<T: AsRef<[u8]>>(v: &mut [T]) {
+    // Sorting has no meaningful behavior on zero-sized types.
+    if mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    let len = v.len();
+
+    let (streak_end, was_reversed) = crate::find_streak(v, &mut |a, b| a.as_ref() < b.as_ref());
+    if streak_end == len {
+        if was_reversed {
+            v.reverse();
+        }
+
+        return;
+    }
+
+    // The keys are cached next to the strings, so that partitioning doesn't have to look up the
+    // bytes of the strings. The pairs hold bitwise copies of the elements of `v`, which is left
+    // untouched until all of them are sorted. If `as_ref` panics, the copies are forgotten.
+    let mut pairs = v
+        .iter()
+        // SAFETY: See above, each element ends up either in `v` or nowhere.
+        .map(|elem| (key(elem, 0), ManuallyDrop::new(unsafe { ptr::read(elem) })))
+        .collect::<Vec<_>>();
+
+    let limit = 2 * (len | 1).ilog2();
+    multikey_quicksort(&mut pairs, 0, limit);
+
+    for (dst, (_, elem)) in v.iter_mut().zip(pairs) {
+        // SAFETY: `pairs` holds each element of `v` exactly once. Overwriting them without
+        // dropping them moves them into their sorted position.
+        unsafe { ptr::write(dst, ManuallyDrop::into_inner(elem)) };
+    }
+}
+
+/// Pairs of the key at the current depth and the element it was loaded from.
+type Pair<T> = (u64, ManuallyDrop<T>);
+
+/// Sorts `pairs` by their strings. All of these strings share the same first `depth` bytes, and
+/// the keys hold the bytes that follow.
+///
+/// `limit` is the number of partitions by keys other than the pivot key that are allowed, like
+/// quicksort limits imbalanced partitions. Partitions of the strings equal to the pivot key don't
+/// count, they make progress by moving on to the next key.
+fn multikey_quicksort<T: AsRef<[u8]>>(mut pairs: &mut [Pair<T>], mut depth: usize, mut limit: u32) {
+    loop {
+        let Some(&(first_key, _)) = pairs.first() else {
+            return;
+        };
+
+        // Common prefixes are skipped without partitioning. For other partitions the check usually
+        // stops after a few keys.
+        let equal = if pairs.iter().all(|pair| pair.0 == first_key) {
+            Some(pairs)
+        } else if pairs.len() <= COMPARISON_SORT_THRESHOLD || limit == 0 {
+            // Once the limit is reached, this guarantees *O*(*n* \* log(*n*)) key comparisons
+            // for each key of the strings.
+            sort_by_key(pairs, depth, limit)
+        } else {
+            limit -= 1;
+
+            let key_less = &mut |a: &Pair<T>, b: &Pair<T>| a.0 < b.0;
+            let pivot_pos = crate::pivot::choose_pivot(pairs, key_less);
+            let (lt, gt) = partition(pairs, pivot_pos, key_less);
+
+            let (less, rest) = pairs.split_at_mut(lt);
+            let (equal, greater) = rest.split_at_mut(gt - lt);
+
+            multikey_quicksort(less, depth, limit);
+            multikey_quicksort(greater, depth, limit);
+
+            Some(equal)
+        };
+
+        // Continue with the strings that are equal so far, by their next key.
+        match equal {
+            Some(equal) if equal[0].0 & LEN_MASK == KEY_BYTES as u64 => {
+                pairs = equal;
+                depth += KEY_BYTES;
+                reload_keys(pairs, depth);
+            }
+            // All strings end within the key they share, they are equal.
+            _ => return,
+        }
+    }
+}
+
+/// Sorts `pairs` by their keys, and each run of equal keys except the longest one by the bytes
+/// that follow. Returns that run, if the strings in it continue after the key.
+fn sort_by_key<T: AsRef<[u8]>>(
+    pairs: &mut [Pair<T>],
+    depth: usize,
+    limit: u32,
+) -> Option<&mut [Pair<T>]> {
+    crate::unstable_sort(pairs, |a, b| a.0 < b.0);
+
+    let mut longest: Option<&mut [Pair<T>]> = None;
+    let mut rest = pairs;
+
+    while let Some(&(run_key, _)) = rest.first() {
+        let run_len = rest.iter().take_while(|pair| pair.0 == run_key).count();
+        let (run, tail) = rest.split_at_mut(run_len);
+        rest = tail;
+
+        if run_len == 1 || run_key & LEN_MASK < KEY_BYTES as u64 {
+            continue;
+        }
+
+        // Continuing with the longest run, instead of recursing into it, bounds the recursion
+        // depth by `log2(len)`.
+        let run = if longest.as_ref().is_none_or(|prev| run_len > prev.len()) {
+            match longest.replace(run) {
+                Some(prev) => prev,
+                None => continue,
+            }
+        } else {
+            run
+        };
+
+        reload_keys(run, depth + KEY_BYTES);
+        multikey_quicksort(run, depth + KEY_BYTES, limit);
+    }
+
+    longest
+}
+
+/// Reorders `pairs` into the ones with a key less than the one at `pivot_pos`, the ones with an
+/// equal key and the ones with a greater key. Returns the start and end of the equal part.
+fn partition<E, F>(pairs: &mut [E], pivot_pos: usize, key_less: &mut F) -> (usize, usize)
+where
+    F: FnMut(&E, &E) -> bool,
+{
+    // Two passes of the regular partition are faster than a single three-way partition, which
+    // needs more branches. The second pass is the one quicksort uses for elements equal to the
+    // ancestor pivot.
+    let lt = crate::quicksort::partition(pairs, pivot_pos, key_less);
+    let eq = crate::quicksort::partition(&mut pairs[lt..], 0, &mut |a, b| !key_less(b, a));
+
+    (lt, lt + eq + 1)
+}
+
+fn reload_keys<T: AsRef<[u8]>>(pairs: &mut [Pair<T>], depth: usize) {
+    for (key_val, elem) in pairs {
+        *key_val = key(&**elem, depth);
+    }
+}
+
+/// Number of bytes of a string that make up a key.
+const KEY_BYTES: usize = 7;
+
+/// The lowest byte of a key holds the number of string bytes in it.
+const LEN_MASK: u64 = 0xFF;
+
+/// Returns the next [`KEY_BYTES`] bytes of `elem` starting at `depth`, in the upper bytes of the
+/// key, padded with zeros. The lowest byte holds the number of bytes that were present, so that
+/// strings that end earlier are ordered first.
+#[inline(always)]
+fn key<T: AsRef<[u8]>>(elem: &T, depth: usize) -> u64 {
+    let rest = &elem.as_ref()[depth..];
+
+    if let Some(bytes) = rest.get(..8) {
+        let word = u64::from_be_bytes(bytes.try_into().unwrap());
+        (word & !LEN_MASK) | KEY_BYTES as u64
+    } else {
+        let mut bytes = [0u8; 8];
+        let key_len = rest.len().min(KEY_BYTES);
+        bytes[..key_len].copy_from_slice(&rest[..key_len]);
+        u64::from_be_bytes(bytes) | key_len as u64
+    }
+}
@@ -0,0 +1,226 @@
+#[cfg(feature = "trace")]
+use std::cell::Cell;
+#[cfg(feature = "trace")]
+use std::io::{self, Write};
+#[cfg(feature = "trace")]
+use std::mem;
+#[cfg(feature = "trace")]
+use std::ptr::NonNull;
+
+/// Passes an event to the observer installed with `with_observer`, e.g.
+/// `trace::event!(pivot(v.len(), pivot_pos))`. Expands to nothing if the `trace` feature is
+/// disabled, the arguments are not even evaluated.
+macro_rules! event {
+    ($event:ident($($arg:expr),* $(,)?)) => {
+        #[cfg(feature = "trace")]
+        $crate::trace::notify(|observer| observer.$event($($arg),*));
+    };
+}
+
+pub(crate) use event;
+
+/// Receives the events of the sorts running on the current thread, see [`with_observer`].
+///
+/// All lengths refer to the sub-slice the event happened on, and positions are relative to its
+/// start. Every method does nothing by default.
+#[cfg(feature = "trace")]
+pub trait SortObserver {
+    /// The first `streak_end` elements of the slice are sorted, or strictly descending if
+    /// `reversed`. If `streak_end == len` the slice is sorted without partitioning it.
+    fn streak(&mut self, _len: usize, _streak_end: usize, _reversed: bool) {}
+
+    /// The streak `v[start..end]` was long enough to be kept, it will be merged with the rest of
+    /// the slice once that is sorted. Reversed streaks are reversed first.
+    fn presorted(&mut self, _len: usize, _start: usize, _end: usize) {}
+
+    /// `v[pivot_pos]` was chosen as pivot.
+    fn pivot(&mut self, _len: usize, _pivot_pos: usize) {}
+
+    /// The slice was partitioned, the pivot is now at `mid`, preceded by the `mid` elements that
+    /// are less than it.
+    fn partition(&mut self, _len: usize, _mid: usize) {}
+
+    /// The pivot was equal to the ancestor pivot, so the slice was partitioned into the `mid + 1`
+    /// elements equal to it, which are in their final position, followed by the greater ones.
+    fn equal_partition(&mut self, _len: usize, _mid: usize) {}
+
+    /// The slice was partitioned around two pivots by [`crate::sort_dual_pivot`], which are now at
+    /// `mid1` and `mid2`. Elements less than the first pivot precede it, elements greater than the
+    /// second one follow it, and the rest lies between them. The pivots are chosen without a
+    /// `pivot` event.
+    fn dual_partition(&mut self, _len: usize, _mid1: usize, _mid2: usize) {}
+
+    /// The slice was handed to the small-sort.
+    fn small_sort(&mut self, _len: usize) {}
+
+    /// Too many imbalanced partitions occurred, the slice was handed to heapsort.
+    fn heapsort(&mut self, _len: usize) {}
+
+    /// The slice of integers was sorted by radix sort. Buckets that are too short for another
+    /// radix pass are sorted by comparisons, which reports their own events.
+    fn radix_sort(&mut self, _len: usize) {}
+}
+
+/// Calls `f` and passes the events of all sorts it runs on the current thread to `observer`.
+///
+/// Sorts running on other threads, including the threads started by [`crate::par_sort`], are
+/// not observed. Neither are sorts started by the observer itself.
+///
+/// # Examples
+///
+/// ```
+/// use ipnsort::SortObserver;
+///
+/// #[derive(Default)]
+/// struct HeapsortCount(usize);
+///
+/// impl SortObserver for HeapsortCount {
+///     fn heapsort(&mut self, _len: usize) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let mut v: Vec<u32> = (0..10_000).map(|i| i.wrapping_mul(2654435761)).collect();
+/// let mut observer = HeapsortCount::default();
+///
+/// ipnsort::with_observer(&mut observer, || ipnsort::sort(&mut v));
+/// assert_eq!(observer.0, 0);
+/// ```
+#[cfg(feature = "trace")]
+pub fn with_observer<O, R>(observer: &mut O, f: impl FnOnce() -> R) -> R
+where
+    O: SortObserver,
+{
+    struct Restore(Option<NonNull<dyn SortObserver>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            OBSERVER.set(self.0);
+        }
+    }
+
+    let observer: NonNull<dyn SortObserver + '_> = NonNull::from(observer as &mut dyn SortObserver);
+    // SAFETY: Only the lifetime is extended, `Restore` removes the observer again before this
+    // function returns or unwinds.
+    let observer: NonNull<dyn SortObserver> = unsafe { mem::transmute(observer) };
+
+    let _restore = Restore(OBSERVER.replace(Some(observer)));
+
+    f()
+}
+
+#[cfg(feature = "trace")]
+thread_local! {
+    static OBSERVER: Cell<Option<NonNull<dyn SortObserver>>> = const { Cell::new(None) };
+}
+
+#[cfg(feature = "trace")]
+pub(crate) fn notify(f: impl FnOnce(&mut dyn SortObserver)) {
+    // The observer is taken out while it is called, which ignores events of sorts it runs itself,
+    // instead of handing out a second mutable reference to it.
+    if let Some(mut observer) = OBSERVER.take() {
+        // SAFETY: `with_observer` guarantees that the observer is alive while it is installed.
+        f(unsafe { observer.as_mut() });
+        OBSERVER.set(Some(observer));
+    }
+}
+
+/// Writes every event as a line of JSON, e.g.
+/// `{"event":"partition","len":1000,"mid":480,"balance":0.4805}`.
+///
+/// The `event` field is named after the [`SortObserver`] method and the other fields after its
+/// parameters. Partition events add `balance`, the length of the smaller side divided by the
+/// number of elements besides the pivot, 0.5 is a perfect split.
+///
+/// # Examples
+///
+/// ```
+/// let mut v: Vec<u32> = (0..10_000).map(|i| i.wrapping_mul(2654435761)).collect();
+/// let mut json_lines = ipnsort::JsonLines::new(Vec::new());
+///
+/// ipnsort::with_observer(&mut json_lines, || ipnsort::sort(&mut v));
+///
+/// let output = String::from_utf8(json_lines.finish().unwrap()).unwrap();
+/// assert!(output.starts_with(r#"{"event":"streak","len":10000,"#));
+/// ```
+#[cfg(feature = "trace")]
+pub struct JsonLines<W: Write> {
+    writer: W,
+    result: io::Result<()>,
+}
+
+#[cfg(feature = "trace")]
+impl<W: Write> JsonLines<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            result: Ok(()),
+        }
+    }
+
+    /// Returns the writer, or the first error that occurred while writing to it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.result?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_line(&mut self, args: std::fmt::Arguments) {
+        // Once writing failed, stop writing so that the output doesn't contain gaps.
+        if self.result.is_ok() {
+            self.result = self.writer.write_fmt(args);
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl<W: Write> SortObserver for JsonLines<W> {
+    fn streak(&mut self, len: usize, streak_end: usize, reversed: bool) {
+        self.write_line(format_args!(
+            "{{\"event\":\"streak\",\"len\":{len},\"streak_end\":{streak_end},\"reversed\":{reversed}}}\n"
+        ));
+    }
+
+    fn presorted(&mut self, len: usize, start: usize, end: usize) {
+        self.write_line(format_args!(
+            "{{\"event\":\"presorted\",\"len\":{len},\"start\":{start},\"end\":{end}}}\n"
+        ));
+    }
+
+    fn pivot(&mut self, len: usize, pivot_pos: usize) {
+        self.write_line(format_args!(
+            "{{\"event\":\"pivot\",\"len\":{len},\"pivot_pos\":{pivot_pos}}}\n"
+        ));
+    }
+
+    fn partition(&mut self, len: usize, mid: usize) {
+        let balance = mid.min(len - 1 - mid) as f64 / (len - 1) as f64;
+        self.write_line(format_args!(
+            "{{\"event\":\"partition\",\"len\":{len},\"mid\":{mid},\"balance\":{balance:.4}}}\n"
+        ));
+    }
+
+    fn equal_partition(&mut self, len: usize, mid: usize) {
+        self.write_line(format_args!(
+            "{{\"event\":\"equal_partition\",\"len\":{len},\"mid\":{mid}}}\n"
+        ));
+    }
+
+    fn dual_partition(&mut self, len: usize, mid1: usize, mid2: usize) {
+        self.write_line(format_args!(
+            "{{\"event\":\"dual_partition\",\"len\":{len},\"mid1\":{mid1},\"mid2\":{mid2}}}\n"
+        ));
+    }
+
+    fn small_sort(&mut self, len: usize) {
+        self.write_line(format_args!("{{\"event\":\"small_sort\",\"len\":{len}}}\n"));
+    }
+
+    fn heapsort(&mut self, len: usize) {
+        self.write_line(format_args!("{{\"event\":\"heapsort\",\"len\":{len}}}\n"));
+    }
+
+    fn radix_sort(&mut self, len: usize) {
+        self.write_line(format_args!("{{\"event\":\"radix_sort\",\"len\":{len}}}\n"));
+    }
+}
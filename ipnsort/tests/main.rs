@@ -1,4 +1,4 @@
-use sort_test_tools::{instantiate_sort_tests, Sort};
+use sort_test_tools::{instantiate_sort_tests, patterns, Capabilities, Sort};
 
 struct SortImpl {}
 
@@ -7,6 +7,13 @@ impl Sort for SortImpl {
         "rust_ipnsort_unstable".into()
     }
 
+    fn capabilities() -> Capabilities {
+        Capabilities {
+            panics_on_ord_violation: cfg!(feature = "strict"),
+            ..Capabilities::default()
+        }
+    }
+
     fn sort<T>(arr: &mut [T])
     where
         T: Ord,
@@ -20,6 +27,2042 @@ impl Sort for SortImpl {
     {
         ipnsort::sort_by(arr, compare);
     }
+
+    fn sort_by_key<T, K, F>(arr: &mut [T], f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        ipnsort::sort_by_key(arr, f);
+    }
+
+    fn sort_by_cached_key<T, K, F>(arr: &mut [T], f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        ipnsort::sort_by_cached_key(arr, f);
+    }
 }
 
+// CI runs the full suite with and without the `stable` feature, which replaces the type specific
+// implementations with generic ones, on nightly and with it also on the stable toolchain. And once
+// more with the `strict` and `trace` features.
 instantiate_sort_tests!(SortImpl);
+
+// The sizes and patterns the modules below check their APIs with, unless they need specific ones.
+// Every size runs with every pattern, so miri only gets the short ones.
+#[cfg(miri)]
+const TEST_SIZES: [usize; 11] = [0, 1, 2, 3, 4, 7, 16, 17, 20, 33, 50];
+
+#[cfg(feature = "large_test_sizes")]
+#[cfg(not(miri))]
+const TEST_SIZES: [usize; 18] = [
+    0, 1, 2, 3, 4, 7, 16, 17, 20, 31, 33, 50, 100, 500, 1_000, 5_000, 10_000, 100_000,
+];
+
+#[cfg(not(feature = "large_test_sizes"))]
+#[cfg(not(miri))]
+const TEST_SIZES: [usize; 17] = [
+    0, 1, 2, 3, 4, 7, 16, 17, 20, 31, 33, 50, 100, 500, 1_000, 5_000, 10_000,
+];
+
+fn pattern_fns() -> Vec<fn(usize) -> Vec<i32>> {
+    vec![
+        patterns::random,
+        |len| patterns::random_uniform(len, 0..=1),
+        |len| patterns::random_uniform(len, 0..4),
+        |len| patterns::random_uniform(len, 0..8),
+        |len| patterns::random_uniform(len, 0..16),
+        |len| patterns::random_uniform(len, 0..256),
+        |len| patterns::random_uniform(len, 0..1024),
+        |len| patterns::random_uniform(len, -1_000..1_000),
+        |len| patterns::random_zipf(len, 1.0),
+        |len| patterns::random_zipf(len, 1.03),
+        |len| patterns::random_zipf(len, 2.0),
+        |len| patterns::random_sorted(len, 95.0),
+        patterns::random_random_size,
+        patterns::all_equal,
+        patterns::ascending,
+        patterns::descending,
+        |len| patterns::saw_ascending(len, ((len as f64).log2().round()) as usize),
+        |len| patterns::saw_descending(len, ((len as f64).log2().round()) as usize),
+        |len| patterns::saw_mixed(len, ((len as f64).log2().round()) as usize),
+        |len| patterns::saw_mixed_range(len, 20..50),
+        patterns::pipe_organ,
+    ]
+}
+
+/// Calls `test_fn` with every pattern in every size of `sizes`.
+fn for_each_pattern(sizes: &[usize], mut test_fn: impl FnMut(Vec<i32>)) {
+    for pattern_fn in pattern_fns() {
+        for &len in sizes {
+            // The zipf distribution is undefined for zero elements.
+            test_fn(if len > 0 { pattern_fn(len) } else { Vec::new() });
+        }
+    }
+}
+
+mod select {
+    use std::cmp::Ordering;
+    use std::panic::{self, AssertUnwindSafe};
+
+    use sort_test_tools::{adversary, patterns};
+
+    use super::{for_each_pattern, TEST_SIZES};
+
+    fn check_select(v: &[i32], index: usize) {
+        let mut expected = v.to_vec();
+        expected.sort();
+
+        let mut result = v.to_vec();
+        let (left, nth, right) = ipnsort::select_nth_unstable(&mut result, index);
+        let nth = *nth;
+
+        assert_eq!(nth, expected[index]);
+        assert!(left.iter().all(|x| *x <= nth));
+        assert!(right.iter().all(|x| *x >= nth));
+
+        result.sort();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn patterns_select() {
+        for_each_pattern(&TEST_SIZES, |v| {
+            // Selecting needs at least one element.
+            let len = v.len();
+            if len == 0 {
+                return;
+            }
+
+            let random_index = patterns::random_uniform(1, 0..(len as i32))[0] as usize;
+
+            for index in [0, len / 2, len - 1, random_index] {
+                check_select(&v, index);
+            }
+        });
+    }
+
+    #[test]
+    fn all_indices() {
+        for len in 1..70 {
+            let v = patterns::random_uniform(len, 0..(len as i32 / 2 + 1));
+
+            for index in 0..len {
+                check_select(&v, index);
+            }
+        }
+    }
+
+    #[test]
+    fn select_by_and_by_key() {
+        let v = patterns::random(1_000);
+
+        let mut expected = v.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        let mut result = v.clone();
+        let (_, nth, _) = ipnsort::select_nth_unstable_by(&mut result, 300, |a, b| b.cmp(a));
+        assert_eq!(*nth, expected[300]);
+
+        let mut expected = v.clone();
+        expected.sort_by_key(|x| x.wrapping_abs());
+        let mut result = v;
+        let (_, nth, _) =
+            ipnsort::select_nth_unstable_by_key(&mut result, 700, |x| x.wrapping_abs());
+        assert_eq!(nth.wrapping_abs(), expected[700].wrapping_abs());
+    }
+
+    #[test]
+    fn zst() {
+        let mut v = [(); 10];
+        ipnsort::select_nth_unstable(&mut v, 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_bounds() {
+        let mut v = [1, 2, 3];
+        ipnsort::select_nth_unstable(&mut v, 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty() {
+        let mut v: [i32; 0] = [];
+        ipnsort::select_nth_unstable(&mut v, 0);
+    }
+
+    #[test]
+    fn panic_retain_original_set() {
+        for_each_pattern(&TEST_SIZES, |v| {
+            let len = v.len();
+            if len == 0 {
+                return;
+            }
+
+            // Needs to be non trivial dtor.
+            let mut v: Vec<Vec<i32>> = v.into_iter().map(|x| vec![x]).collect();
+            let mut expected = v.clone();
+            expected.sort();
+
+            let index = len / 3;
+
+            let mut comp_count = 0;
+            let mut v_copy = v.clone();
+            ipnsort::select_nth_unstable_by(&mut v_copy, index, |a, b| {
+                comp_count += 1;
+                a.cmp(b)
+            });
+
+            if comp_count == 0 {
+                return;
+            }
+
+            let panic_threshold = patterns::random_uniform(1, 0..comp_count)[0];
+            let mut comp_counter = 0;
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                ipnsort::select_nth_unstable_by(&mut v, index, |a, b| {
+                    if comp_counter == panic_threshold {
+                        panic!();
+                    }
+                    comp_counter += 1;
+
+                    a.cmp(b)
+                });
+            }));
+            assert!(res.is_err());
+
+            v.sort();
+            assert_eq!(v, expected);
+        });
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn adversarial_comparisons() {
+        // The adversary freezes each pivot as small as possible, so every partition is imbalanced
+        // until the limit is used up and the fallback to `median_of_medians` has to guarantee
+        // linear time.
+        for len in [1_000, 10_000, 100_000] {
+            let index = len / 2;
+            let v = adversary::killer_input_by(len, |ids, compare| {
+                ipnsort::select_nth_unstable_by(ids, index, compare);
+            });
+
+            let mut expected = v.clone();
+            expected.sort();
+
+            let mut result = v.clone();
+            let mut comps = 0;
+            let (left, nth, right) = ipnsort::select_nth_unstable_by(&mut result, index, |a, b| {
+                comps += 1;
+                a.cmp(b)
+            });
+            let nth = *nth;
+
+            assert_eq!(nth, expected[index]);
+            assert!(left.iter().all(|x| *x <= nth));
+            assert!(right.iter().all(|x| *x >= nth));
+
+            // The imbalanced partitions make up to 16 * N comparisons, the fallback about as many.
+            let max_comps = 40 * len;
+            assert!(
+                comps <= max_comps,
+                "{comps} comparisons to select from the {len} elements of the killer input, \
+                 expected at most {max_comps}"
+            );
+        }
+    }
+
+    #[test]
+    fn violate_ord_retain_original_set() {
+        let random_orderings = patterns::random_uniform(5_000, 0..3);
+        let mut random_idx = 0;
+
+        for_each_pattern(&TEST_SIZES, |v| {
+            let len = v.len();
+            if len == 0 {
+                return;
+            }
+
+            let mut v: Vec<Vec<i32>> = v.into_iter().map(|x| vec![x]).collect();
+            let mut expected = v.clone();
+            expected.sort();
+
+            let index = len / 2;
+
+            // It's ok to panic on Ord violation or to complete. In both cases the original
+            // elements must still be present.
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                ipnsort::select_nth_unstable_by(&mut v, index, |_a, _b| {
+                    random_idx = (random_idx + 1) % random_orderings.len();
+                    [Ordering::Less, Ordering::Equal, Ordering::Greater]
+                        [random_orderings[random_idx] as usize]
+                });
+            }));
+
+            v.sort();
+            assert_eq!(v, expected);
+        });
+    }
+}
+
+mod partial_sort {
+    use sort_test_tools::{adversary, instantiate_partial_sort_tests, PartialSort};
+
+    struct PartialSortImpl {}
+
+    impl PartialSort for PartialSortImpl {
+        fn name() -> String {
+            "rust_ipnsort_partial_unstable".into()
+        }
+
+        fn partial_sort<T>(arr: &mut [T], k: usize)
+        where
+            T: Ord,
+        {
+            ipnsort::partial_sort(arr, k);
+        }
+
+        fn partial_sort_by<T, F>(arr: &mut [T], k: usize, compare: F)
+        where
+            F: FnMut(&T, &T) -> std::cmp::Ordering,
+        {
+            ipnsort::partial_sort_by(arr, k, compare);
+        }
+    }
+
+    instantiate_partial_sort_tests!(PartialSortImpl);
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn adversarial_comparisons() {
+        // The adversary freezes each pivot as small as possible. Sorting in reverse makes it the
+        // largest, so every partition keeps all other elements left of `k` until the limit is used
+        // up and the fallback to `median_of_medians` has to guarantee linear time.
+        for len in [1_000, 10_000, 100_000] {
+            for k in [1, 10] {
+                let v = adversary::killer_input_by(len, |ids, compare| {
+                    ipnsort::partial_sort_by(ids, k, |a, b| compare(b, a));
+                });
+
+                let mut expected = v.clone();
+                expected.sort_by(|a, b| b.cmp(a));
+
+                let mut result = v.clone();
+                let mut comps = 0;
+                ipnsort::partial_sort_by(&mut result, k, |a, b| {
+                    comps += 1;
+                    b.cmp(a)
+                });
+
+                assert_eq!(result[..k], expected[..k]);
+
+                // The 16 partitions make up to 16 * N comparisons, the fallback about as many.
+                let max_comps = 40 * len;
+                assert!(
+                    comps <= max_comps,
+                    "{comps} comparisons to find the {k} largest of the {len} elements of the \
+                     killer input, expected at most {max_comps}"
+                );
+            }
+        }
+    }
+}
+
+mod sort_with_scratch {
+    use std::cmp::Ordering;
+
+    use sort_test_tools::{instantiate_sort_tests, Sort};
+
+    struct SortImpl {}
+
+    impl Sort for SortImpl {
+        fn name() -> String {
+            "rust_ipnsort_scratch_unstable".into()
+        }
+
+        fn sort<T>(arr: &mut [T])
+        where
+            T: Ord,
+        {
+            Self::sort_by(arr, T::cmp);
+        }
+
+        fn sort_by<T, F>(arr: &mut [T], mut compare: F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            // Cover too small, barely large enough and larger than needed scratch buffers.
+            let scratch_len = [0, 20, 36, 47, 48, 10_000][arr.len() % 6];
+            let mut scratch = Vec::<T>::with_capacity(scratch_len);
+
+            ipnsort::sort_with_scratch(
+                arr,
+                &mut scratch.spare_capacity_mut()[..scratch_len],
+                |a, b| compare(a, b) == Ordering::Less,
+            );
+        }
+    }
+
+    instantiate_sort_tests!(SortImpl);
+}
+
+mod sort_dual_pivot {
+    use std::cmp::Ordering;
+
+    use sort_test_tools::{instantiate_sort_tests, Sort};
+
+    struct SortImpl {}
+
+    impl Sort for SortImpl {
+        fn name() -> String {
+            "rust_ipnsort_dual_pivot_unstable".into()
+        }
+
+        fn sort<T>(arr: &mut [T])
+        where
+            T: Ord,
+        {
+            ipnsort::sort_dual_pivot(arr);
+        }
+
+        fn sort_by<T, F>(arr: &mut [T], compare: F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            ipnsort::sort_by_dual_pivot(arr, compare);
+        }
+    }
+
+    instantiate_sort_tests!(SortImpl);
+}
+
+mod sort_randomized {
+    use std::cmp::Ordering;
+
+    use sort_test_tools::{adversary, instantiate_sort_tests, patterns, Sort};
+
+    struct SortImpl {}
+
+    impl Sort for SortImpl {
+        fn name() -> String {
+            "rust_ipnsort_randomized_unstable".into()
+        }
+
+        fn sort<T>(arr: &mut [T])
+        where
+            T: Ord,
+        {
+            ipnsort::sort_randomized(arr, Some(arr.len() as u64));
+        }
+
+        fn sort_by<T, F>(arr: &mut [T], compare: F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            ipnsort::sort_by_randomized(arr, Some(0x5eed), compare);
+        }
+    }
+
+    // The tests rely on repeated sorts of the same input making the same comparisons, which the
+    // seed derived from addresses doesn't guarantee.
+    instantiate_sort_tests!(SortImpl);
+
+    #[test]
+    fn address_seed() {
+        for len in [0, 1, 20, 100, 10_000] {
+            for mut v in [patterns::random(len), patterns::random_uniform(len, 0..20)] {
+                let mut expected = v.clone();
+                expected.sort();
+
+                ipnsort::sort_randomized(&mut v, None);
+                assert_eq!(v, expected);
+            }
+        }
+    }
+
+    type SortBy<T> = fn(&mut [T], &mut dyn FnMut(&T, &T) -> Ordering);
+
+    fn count_comparisons(v: &[i32], sort_by: SortBy<i32>) -> usize {
+        let mut v = v.to_vec();
+        let mut comparisons = 0;
+        sort_by(&mut v, &mut |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+
+        assert!(v.windows(2).all(|w| w[0] <= w[1]));
+        comparisons
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn adversary() {
+        let deterministic: SortBy<i32> = |v, compare| ipnsort::sort_by(v, compare);
+        let seeded: SortBy<i32> = |v, compare| ipnsort::sort_by_randomized(v, Some(1), compare);
+
+        for len in [1_000, 10_000, 100_000] {
+            let random = patterns::random(len);
+
+            // An input prepared against the fixed pivot positions drives the deterministic sort
+            // into heapsort, but is as fast as a random one for the randomized sort.
+            let killer = adversary::killer_input_by(len, |v, compare| ipnsort::sort_by(v, compare));
+            let random_comparisons = count_comparisons(&random, deterministic);
+            let killer_comparisons = count_comparisons(&killer, deterministic);
+            assert!(
+                killer_comparisons > random_comparisons * 2,
+                "{len}: {killer_comparisons} {random_comparisons}"
+            );
+
+            let random_comparisons = count_comparisons(&random, seeded);
+            let killer_comparisons = count_comparisons(&killer, seeded);
+            assert!(
+                killer_comparisons < random_comparisons * 5 / 4,
+                "{len}: {killer_comparisons} {random_comparisons}"
+            );
+
+            // The adversary adapts to any seed while it runs, but the input it builds doesn't
+            // carry over to another one.
+            let killer = adversary::killer_input_by(len, |v, compare| {
+                ipnsort::sort_by_randomized(v, Some(2), compare)
+            });
+            let killer_comparisons = count_comparisons(&killer, seeded);
+            assert!(
+                killer_comparisons < random_comparisons * 5 / 4,
+                "{len}: {killer_comparisons} {random_comparisons}"
+            );
+        }
+    }
+}
+
+mod argsort {
+    use std::fmt::Debug;
+
+    use sort_test_tools::ffi_types::F128;
+
+    use super::{for_each_pattern, TEST_SIZES};
+
+    fn check_argsort<T: Ord + Debug>(v: &[T], indices: &[usize], reverse: bool) {
+        // Every index must be present exactly once.
+        assert_eq!(indices.len(), v.len());
+        let mut seen = vec![false; v.len()];
+        for &i in indices {
+            assert!(!seen[i]);
+            seen[i] = true;
+        }
+
+        // The stable sort of the indices is the only valid result.
+        let mut expected = (0..v.len()).collect::<Vec<_>>();
+        if reverse {
+            expected.sort_by_key(|&i| std::cmp::Reverse(&v[i]));
+        } else {
+            expected.sort_by_key(|&i| &v[i]);
+        }
+
+        assert_eq!(indices, expected);
+    }
+
+    fn test_impl<T: Ord + Debug>(type_into_fn: impl Fn(i32) -> T) {
+        for_each_pattern(&TEST_SIZES, |v| {
+            let v = v.into_iter().map(&type_into_fn).collect::<Vec<_>>();
+
+            check_argsort(&v, &ipnsort::argsort(&v), false);
+            check_argsort(&v, &ipnsort::argsort_by(&v, |a, b| b.cmp(a)), true);
+        });
+    }
+
+    #[test]
+    fn patterns_i32() {
+        test_impl(|val| val);
+    }
+
+    #[test]
+    fn patterns_u64() {
+        test_impl(|val| (val as u64).wrapping_mul(i32::MAX as u64));
+    }
+
+    #[test]
+    fn patterns_f128() {
+        test_impl(F128::new);
+    }
+
+    #[test]
+    fn patterns_string() {
+        test_impl(|val| format!("{val}"));
+    }
+
+    #[test]
+    fn zst() {
+        let v = [(); 10];
+        assert_eq!(ipnsort::argsort(&v), (0..10).collect::<Vec<_>>());
+    }
+}
+
+mod sort_dedup {
+    use std::cell::Cell;
+    use std::fmt::Debug;
+    use std::panic::{self, AssertUnwindSafe};
+
+    use sort_test_tools::patterns;
+
+    use super::{for_each_pattern, TEST_SIZES};
+
+    fn test_impl<T: Ord + Clone + Debug>(type_into_fn: impl Fn(i32) -> T) {
+        for_each_pattern(&TEST_SIZES, |v| {
+            let v = v.into_iter().map(&type_into_fn).collect::<Vec<_>>();
+
+            let mut expected = v.clone();
+            expected.sort();
+            let mut expected_counts = Vec::<(T, usize)>::new();
+            for val in &expected {
+                match expected_counts.last_mut() {
+                    Some((last, count)) if last == val => *count += 1,
+                    _ => expected_counts.push((val.clone(), 1)),
+                }
+            }
+
+            let mut sorted = v.clone();
+            assert_eq!(ipnsort::sort_count(&mut sorted), expected_counts);
+            assert_eq!(sorted, expected);
+
+            expected.dedup();
+            let mut deduped = v.clone();
+            ipnsort::sort_dedup(&mut deduped);
+            assert_eq!(deduped, expected);
+
+            expected.reverse();
+            let mut deduped = v;
+            ipnsort::sort_dedup_by(&mut deduped, |a, b| b.cmp(a));
+            assert_eq!(deduped, expected);
+        });
+    }
+
+    #[test]
+    fn patterns_i32() {
+        test_impl(|val| val);
+    }
+
+    #[test]
+    fn patterns_string() {
+        test_impl(|val| format!("{val}"));
+    }
+
+    #[test]
+    fn by_key() {
+        let mut v = patterns::random(1_000);
+        let counts = ipnsort::sort_count_by(&mut v, |a, b| a.rem_euclid(10).cmp(&b.rem_euclid(10)));
+
+        assert_eq!(counts.len(), 10);
+        assert_eq!(counts.iter().map(|&(_, count)| count).sum::<usize>(), 1_000);
+        for (digit, &(val, count)) in counts.iter().enumerate() {
+            assert_eq!(val.rem_euclid(10), digit as i32);
+            assert_eq!(
+                count,
+                v.iter()
+                    .filter(|x| x.rem_euclid(10) == val.rem_euclid(10))
+                    .count()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn fewer_comparisons() {
+        // The elements the equal partitions isolated are not compared again.
+        let v = patterns::random_uniform(100_000, 0..16);
+
+        let comparisons = Cell::new(0);
+        let mut compare = |a: &i32, b: &i32| {
+            comparisons.set(comparisons.get() + 1);
+            a.cmp(b)
+        };
+
+        let mut deduped = v.clone();
+        ipnsort::sort_dedup_by(&mut deduped, &mut compare);
+        let fused = comparisons.replace(0);
+
+        let mut expected = v;
+        ipnsort::sort_by(&mut expected, &mut compare);
+        expected.dedup_by(|a, b| compare(a, b).is_eq());
+        let separate = comparisons.get();
+
+        assert_eq!(deduped, expected);
+        assert!(fused < separate - 90_000, "{fused} {separate}");
+    }
+
+    #[test]
+    fn zst() {
+        let mut v = vec![(); 10];
+        ipnsort::sort_dedup(&mut v);
+        assert_eq!(v.len(), 1);
+        assert_eq!(ipnsort::sort_count(&mut [(); 10]), [((), 10)]);
+    }
+
+    #[test]
+    fn panic_retain_original_set() {
+        let v = patterns::random_uniform(1_000, 0..20);
+
+        for panic_after in [0, 10, 500, 5_000] {
+            let mut comparisons = 0;
+            let mut deduped = v.clone();
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                ipnsort::sort_dedup_by(&mut deduped, |a, b| {
+                    comparisons += 1;
+                    if comparisons > panic_after {
+                        panic!();
+                    }
+                    a.cmp(b)
+                });
+            }));
+            assert!(res.is_err());
+
+            deduped.sort();
+            let mut expected = v.clone();
+            expected.sort();
+            assert_eq!(deduped, expected);
+        }
+    }
+}
+
+mod sort_columns {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use sort_test_tools::patterns;
+
+    use super::TEST_SIZES;
+
+    // Columns with few distinct values, so that the later columns decide the order of many rows.
+    fn columns(len: usize) -> (Vec<u8>, Vec<String>, Vec<i32>) {
+        let a = patterns::random_uniform(len, 0..4)
+            .into_iter()
+            .map(|val| val as u8)
+            .collect();
+        let b = patterns::random_uniform(len, 0..16)
+            .into_iter()
+            .map(|val| format!("{val}"))
+            .collect();
+        let c = patterns::random_uniform(len, 0..(len as i32 / 4 + 1));
+
+        (a, b, c)
+    }
+
+    #[test]
+    fn three_columns() {
+        for len in TEST_SIZES {
+            let (mut a, mut b, mut c) = columns(len);
+
+            let mut expected = a
+                .iter()
+                .cloned()
+                .zip(b.iter().cloned())
+                .zip(c.iter().cloned())
+                .map(|((a, b), c)| (a, b, c))
+                .collect::<Vec<_>>();
+            expected.sort();
+
+            ipnsort::sort_columns((&mut a[..], &mut b[..], &mut c[..]));
+
+            let result = a
+                .into_iter()
+                .zip(b)
+                .zip(c)
+                .map(|((a, b), c)| (a, b, c))
+                .collect::<Vec<_>>();
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn one_and_six_columns() {
+        for len in TEST_SIZES {
+            let mut v = patterns::random(len);
+            let mut expected = v.clone();
+            expected.sort();
+            ipnsort::sort_columns((&mut v[..],));
+            assert_eq!(v, expected);
+
+            let (mut a, mut b, mut c) = columns(len);
+            let (mut d, mut e, mut f) = columns(len);
+            let mut expected = (0..len)
+                .map(|i| (a[i], b[i].clone(), c[i], d[i], e[i].clone(), f[i]))
+                .collect::<Vec<_>>();
+            expected.sort();
+
+            ipnsort::sort_columns((
+                &mut a[..],
+                &mut b[..],
+                &mut c[..],
+                &mut d[..],
+                &mut e[..],
+                &mut f[..],
+            ));
+
+            for (i, row) in expected.into_iter().enumerate() {
+                assert_eq!(row, (a[i], b[i].clone(), c[i], d[i], e[i].clone(), f[i]));
+            }
+        }
+    }
+
+    #[test]
+    fn by_callback() {
+        for len in TEST_SIZES {
+            // A column that is not part of the key, the sort has to be stable.
+            let ids = (0..len).collect::<Vec<_>>();
+            let keys = patterns::random_uniform(len, 0..16);
+
+            let mut expected = ids
+                .iter()
+                .cloned()
+                .zip(keys.iter().cloned())
+                .collect::<Vec<_>>();
+            expected.sort_by_key(|row| std::cmp::Reverse(row.1));
+
+            let mut swaps = 0;
+            let mut columns = (ids, keys);
+            ipnsort::sort_columns_by(
+                &mut columns,
+                len,
+                |(_, keys), a, b| keys[b].cmp(&keys[a]),
+                |(ids, keys), a, b| {
+                    assert_ne!(a, b);
+                    ids.swap(a, b);
+                    keys.swap(a, b);
+                    swaps += 1;
+                },
+            );
+
+            let result = columns.0.into_iter().zip(columns.1).collect::<Vec<_>>();
+            assert_eq!(result, expected);
+            assert!(swaps < len.max(1));
+        }
+    }
+
+    #[test]
+    fn doc_examples() {
+        let mut names = ["c", "a", "b", "a"];
+        let mut ages = [30, 40, 20, 10];
+        ipnsort::sort_columns((&mut names[..], &mut ages[..]));
+        assert_eq!(names, ["a", "a", "b", "c"]);
+        assert_eq!(ages, [10, 40, 20, 30]);
+
+        let mut columns = (vec![3, 1, 2], vec![0.5f64, 0.9, 0.1]);
+        ipnsort::sort_columns_by(
+            &mut columns,
+            3,
+            |(_, scores), a, b| scores[b].total_cmp(&scores[a]),
+            |(ids, scores), a, b| {
+                ids.swap(a, b);
+                scores.swap(a, b);
+            },
+        );
+        assert_eq!(columns.0, [1, 3, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn length_mismatch() {
+        let mut a = [1, 2, 3];
+        let mut b = [1, 2];
+        ipnsort::sort_columns((&mut a[..], &mut b[..]));
+    }
+
+    #[test]
+    fn panic_leaves_columns_unchanged() {
+        let mut columns = (patterns::random(1_000), patterns::random(1_000));
+        let original = columns.clone();
+
+        for panic_after in [0, 100, 5_000] {
+            let mut comparisons = 0;
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                ipnsort::sort_columns_by(
+                    &mut columns,
+                    1_000,
+                    |(a, _), i, j| {
+                        comparisons += 1;
+                        if comparisons > panic_after {
+                            panic!();
+                        }
+                        a[i].cmp(&a[j])
+                    },
+                    |(a, b), i, j| {
+                        a.swap(i, j);
+                        b.swap(i, j);
+                    },
+                );
+            }));
+            assert!(res.is_err());
+            assert_eq!(columns, original);
+        }
+
+        // Without a panic the rows are sorted.
+        ipnsort::sort_columns_by(
+            &mut columns,
+            1_000,
+            |(a, _), i, j| a[i].cmp(&a[j]),
+            |(a, b), i, j| {
+                a.swap(i, j);
+                b.swap(i, j);
+            },
+        );
+        assert!(columns.0.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
+
+mod sort_floats {
+    use super::{for_each_pattern, TEST_SIZES};
+
+    // Maps some of the values to the edge cases of the total order, the rest is scaled into
+    // numbers of both signs.
+    fn to_f64(val: i32) -> f64 {
+        match val.rem_euclid(64) {
+            0 => f64::NAN,
+            1 => -f64::NAN,
+            2 => f64::from_bits(f64::NAN.to_bits() | 0xBEEF),
+            3 => f64::INFINITY,
+            4 => f64::NEG_INFINITY,
+            5 => 0.0,
+            6 => -0.0,
+            7 => f64::MIN_POSITIVE / 4.0,
+            8 => -f64::MIN_POSITIVE / 4.0,
+            9 => f64::MAX,
+            10 => f64::MIN,
+            _ => val as f64 / 7.0,
+        }
+    }
+
+    fn test_impl<
+        T: ipnsort::Float + Copy + PartialEq + std::fmt::Debug,
+        B: Eq + std::fmt::Debug,
+    >(
+        type_into_fn: impl Fn(i32) -> T,
+        total_cmp: impl Fn(&T, &T) -> std::cmp::Ordering,
+        to_bits: impl Fn(T) -> B,
+    ) {
+        for_each_pattern(&TEST_SIZES, |v| {
+            let v = v.into_iter().map(&type_into_fn).collect::<Vec<_>>();
+
+            let mut expected = v.clone();
+            expected.sort_by(&total_cmp);
+
+            let mut sorted = v.clone();
+            ipnsort::sort_floats(&mut sorted);
+
+            // Compare the bits, to distinguish the zeros and NaNs.
+            assert_eq!(
+                sorted.into_iter().map(&to_bits).collect::<Vec<_>>(),
+                expected.into_iter().map(&to_bits).collect::<Vec<_>>()
+            );
+        });
+    }
+
+    #[test]
+    fn patterns_f64() {
+        test_impl(to_f64, f64::total_cmp, f64::to_bits);
+    }
+
+    #[test]
+    fn patterns_f32() {
+        test_impl(|val| to_f64(val) as f32, f32::total_cmp, f32::to_bits);
+    }
+
+    #[test]
+    fn nan_position() {
+        let mut v = [3.0, -f64::NAN, f64::NAN, -0.0, f64::NEG_INFINITY, 0.0, -3.0];
+        ipnsort::sort_floats(&mut v);
+
+        assert!(v[0].is_nan() && v[0].is_sign_negative());
+        assert_eq!(v[1..6], [f64::NEG_INFINITY, -3.0, -0.0, 0.0, 3.0]);
+        assert!(v[3].is_sign_negative() && v[4].is_sign_positive());
+        assert!(v[6].is_nan() && v[6].is_sign_positive());
+    }
+}
+
+mod sort_strings {
+    use sort_test_tools::patterns;
+
+    #[cfg(miri)]
+    const TEST_SIZES: [usize; 9] = [0, 1, 2, 3, 20, 33, 50, 100, 129];
+
+    #[cfg(feature = "large_test_sizes")]
+    #[cfg(not(miri))]
+    const TEST_SIZES: [usize; 12] = [0, 1, 2, 3, 20, 33, 50, 100, 129, 1_000, 10_000, 100_000];
+
+    #[cfg(not(feature = "large_test_sizes"))]
+    #[cfg(not(miri))]
+    const TEST_SIZES: [usize; 11] = [0, 1, 2, 3, 20, 33, 50, 100, 129, 1_000, 10_000];
+
+    type PatternFn = fn(usize) -> Vec<String>;
+
+    fn pattern_fns() -> Vec<(&'static str, PatternFn)> {
+        vec![
+            ("padded", |len| {
+                patterns::random(len)
+                    .iter()
+                    .map(|val| format!("{:010}", val.saturating_abs()))
+                    .collect()
+            }),
+            ("common_prefix", |len| {
+                patterns::random_uniform(len, 0..1_000)
+                    .iter()
+                    .map(|val| format!("crate::module::submodule::Type{val}::method"))
+                    .collect()
+            }),
+            ("prefixes_of_each_other", |len| {
+                // Strings that are prefixes of others, including the empty string.
+                patterns::random_uniform(len, 0..40)
+                    .iter()
+                    .map(|&val| "ab".repeat(val as usize / 2) + &"a".repeat(val as usize % 2))
+                    .collect()
+            }),
+            ("all_equal", |len| vec!["same".to_string(); len]),
+            ("ascending", |len| {
+                patterns::ascending(len)
+                    .iter()
+                    .map(|val| format!("{val:08}"))
+                    .collect()
+            }),
+            ("descending", |len| {
+                patterns::descending(len)
+                    .iter()
+                    .map(|val| format!("{val:08}"))
+                    .collect()
+            }),
+        ]
+    }
+
+    fn check<T: AsRef<[u8]> + Ord + Clone + std::fmt::Debug>(name: &str, v: Vec<T>) {
+        let mut expected = v.clone();
+        expected.sort();
+
+        let mut sorted = v;
+        ipnsort::sort_strings(&mut sorted);
+        assert_eq!(sorted, expected, "{name}");
+    }
+
+    #[test]
+    fn strings() {
+        for (name, pattern_fn) in pattern_fns() {
+            for len in TEST_SIZES {
+                check(name, pattern_fn(len));
+            }
+        }
+    }
+
+    #[test]
+    fn str_slices() {
+        let len = if cfg!(miri) { 129 } else { 10_000 };
+        for (name, pattern_fn) in pattern_fns() {
+            let strings = pattern_fn(len);
+            check(name, strings.iter().map(|s| s.as_str()).collect());
+        }
+    }
+
+    #[test]
+    fn bytes() {
+        // All byte values, including the ones that aren't valid UTF-8 and zero bytes.
+        for len in TEST_SIZES {
+            let v = patterns::random(len)
+                .iter()
+                .map(|val| val.to_le_bytes()[..(val.rem_euclid(5)) as usize].to_vec())
+                .collect();
+            check("bytes", v);
+        }
+    }
+
+    #[test]
+    fn long_common_prefix() {
+        let (prefix_len, len) = if cfg!(miri) {
+            (1_000, 100)
+        } else {
+            (10_000, 1_000)
+        };
+        let prefix = "x".repeat(prefix_len);
+        let v = patterns::random(len)
+            .iter()
+            .map(|val| format!("{prefix}{val}"))
+            .collect();
+        check("long_common_prefix", v);
+    }
+}
+
+mod sort_array {
+    use std::cmp::Ordering;
+
+    use sort_test_tools::patterns;
+
+    /// Returns the comparators `sort_array` uses for `N`, as pairs of positions in the array. This
+    /// relies on the sorting networks performing the same comparisons for every input.
+    fn extract_network<const N: usize>() -> Vec<(usize, usize)> {
+        let mut v = [0u8; N];
+        let base = v.as_ptr() as usize;
+        let pos = |elem: &u8| {
+            let pos = elem as *const u8 as usize - base;
+            assert!(pos < N);
+            pos
+        };
+
+        let mut network = Vec::new();
+        ipnsort::sort_array_by(&mut v, |a, b| {
+            let (a, b) = (pos(a), pos(b));
+            network.push((a.min(b), a.max(b)));
+            Ordering::Equal
+        });
+
+        network
+    }
+
+    /// Checks that `network` sorts every input of 0s and 1s, which by the 0-1 principle means it
+    /// sorts every input. Each bit of a lane holds the value of a wire for one of 128 inputs.
+    fn check_zero_one<const N: usize>(network: &[(usize, usize)]) {
+        // The leading comparators on distinct wires turn every pair of 0s and 1s into a sorted
+        // pair, leaving 3 instead of 4 possible states per pair. This makes checking N = 32
+        // feasible without skipping any possible output of the first layer.
+        let mut used = [false; N];
+        let mut digits = Vec::new();
+        for &(a, b) in network {
+            if used[a] || used[b] {
+                break;
+            }
+            used[a] = true;
+            used[b] = true;
+            digits.push(vec![a, b]);
+        }
+        digits.extend((0..N).filter(|&w| !used[w]).map(|w| vec![w]));
+
+        // The possible 0-1 values of the wires in a digit, sorted for pairs.
+        let states = |digit: &[usize], state: usize| -> Vec<(usize, bool)> {
+            match digit {
+                [a, b] => vec![(*a, state == 2), (*b, state >= 1)],
+                [w] => vec![(*w, state == 1)],
+                _ => unreachable!(),
+            }
+        };
+        let radix = |digit: &[usize]| digit.len() + 1;
+
+        // Enumerate as many digits as fit into the lanes, the others in the loop.
+        let mut lane_count = 1;
+        let mut inner_len = 0;
+        while inner_len < digits.len() && lane_count * radix(&digits[inner_len]) <= 128 {
+            lane_count *= radix(&digits[inner_len]);
+            inner_len += 1;
+        }
+        let valid_lanes = if lane_count == 128 {
+            u128::MAX
+        } else {
+            (1u128 << lane_count) - 1
+        };
+
+        let mut inner_wires = [0u128; N];
+        for lane in 0..lane_count {
+            let mut rest = lane;
+            for digit in &digits[..inner_len] {
+                for (w, val) in states(digit, rest % radix(digit)) {
+                    inner_wires[w] |= (val as u128) << lane;
+                }
+                rest /= radix(digit);
+            }
+        }
+
+        let outer_digits = &digits[inner_len..];
+        let mut counter = vec![0; outer_digits.len()];
+        loop {
+            let mut wires = inner_wires;
+            for (digit, &state) in outer_digits.iter().zip(&counter) {
+                for (w, val) in states(digit, state) {
+                    wires[w] = if val { valid_lanes } else { 0 };
+                }
+            }
+
+            for &(a, b) in network {
+                let (min, max) = (wires[a] & wires[b], wires[a] | wires[b]);
+                wires[a] = min;
+                wires[b] = max;
+            }
+
+            for w in 1..N {
+                assert_eq!(
+                    wires[w - 1] & !wires[w],
+                    0,
+                    "N = {N}, network = {network:?}"
+                );
+            }
+
+            // Advance the mixed radix counter.
+            let mut i = 0;
+            while i < counter.len() {
+                counter[i] += 1;
+                if counter[i] < radix(&outer_digits[i]) {
+                    break;
+                }
+                counter[i] = 0;
+                i += 1;
+            }
+            if i == counter.len() {
+                break;
+            }
+        }
+    }
+
+    fn check_len<const N: usize>() {
+        let network = extract_network::<N>();
+        check_zero_one::<N>(&network);
+
+        // Make sure the extracted network matches what `sort_array` does with other values.
+        for _ in 0..10 {
+            let v = patterns::random(N);
+            let mut expected = v.clone();
+            for &(a, b) in &network {
+                if expected[b] < expected[a] {
+                    expected.swap(a, b);
+                }
+            }
+
+            let mut sorted: [i32; N] = v.try_into().unwrap();
+            ipnsort::sort_array(&mut sorted);
+            assert_eq!(sorted.as_slice(), expected.as_slice());
+        }
+    }
+
+    macro_rules! check_lens {
+        ($($len:literal),*) => {
+            $(check_len::<$len>();)*
+        };
+    }
+
+    #[test]
+    fn zero_one_principle() {
+        check_lens!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16);
+
+        // Up to 3^16 inputs per length, prohibitively expensive in miri.
+        if !cfg!(miri) {
+            check_lens!(17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32);
+        }
+    }
+
+    fn check_general<const N: usize>() {
+        for pattern_fn in [
+            patterns::random,
+            |len| patterns::random_uniform(len, 0..4),
+            patterns::ascending,
+            patterns::descending,
+        ] {
+            let v = pattern_fn(N);
+            let mut expected = v.clone();
+            expected.sort();
+
+            let mut sorted: [i32; N] = v.clone().try_into().unwrap();
+            ipnsort::sort_array(&mut sorted);
+            assert_eq!(sorted.as_slice(), expected.as_slice());
+
+            let mut sorted_strings: [String; N] = v
+                .iter()
+                .map(|val| format!("{val:010}"))
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap();
+            ipnsort::sort_array_by(&mut sorted_strings, |a, b| b.cmp(a));
+            assert!(sorted_strings.windows(2).all(|w| w[0] >= w[1]));
+        }
+    }
+
+    #[test]
+    fn patterns() {
+        check_general::<7>();
+        check_general::<32>();
+        check_general::<33>();
+        check_general::<100>();
+        check_general::<1_000>();
+    }
+
+    #[test]
+    fn panic_retain_original_set() {
+        let v = patterns::random(24);
+
+        for panic_after in [0, 1, 50, 100] {
+            let mut sorted: [String; 24] = v
+                .iter()
+                .map(|val| format!("{val}"))
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap();
+
+            let mut count = 0;
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                ipnsort::sort_array_by(&mut sorted, |a, b| {
+                    count += 1;
+                    if count > panic_after {
+                        panic!("explicit panic");
+                    }
+                    a.cmp(b)
+                });
+            }));
+            assert!(res.is_err());
+
+            let mut expected = v.iter().map(|val| format!("{val}")).collect::<Vec<_>>();
+            expected.sort();
+            sorted.sort();
+            assert_eq!(sorted.as_slice(), expected.as_slice());
+        }
+    }
+}
+
+mod presorted {
+    use std::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+
+    use sort_test_tools::patterns;
+
+    #[cfg(miri)]
+    const TEST_SIZES: [usize; 5] = [21, 33, 50, 100, 500];
+
+    #[cfg(feature = "large_test_sizes")]
+    #[cfg(not(miri))]
+    const TEST_SIZES: [usize; 9] = [21, 33, 50, 100, 500, 1_000, 4_097, 10_000, 100_000];
+
+    #[cfg(not(feature = "large_test_sizes"))]
+    #[cfg(not(miri))]
+    const TEST_SIZES: [usize; 8] = [21, 33, 50, 100, 500, 1_000, 4_097, 10_000];
+
+    // The presorted part is `percent` of the slice, at the start or the end, ascending or
+    // descending. Its values are distinct and spread over the whole `i32` range, reversed streaks
+    // have to be strictly descending to be detected.
+    fn presorted(len: usize, percent: f64, at_end: bool, reversed: bool) -> Vec<i32> {
+        let sorted_len = ((len as f64) * (percent / 100.0)).round() as usize;
+        let step = (u32::MAX / sorted_len as u32) as i64;
+
+        let mut v: Vec<i32> = (0..sorted_len)
+            .map(|i| (i32::MIN as i64 + i as i64 * step) as i32)
+            .chain(patterns::random(len - sorted_len))
+            .collect();
+
+        if reversed {
+            v[..sorted_len].reverse();
+        }
+        if at_end {
+            v.rotate_left(sorted_len);
+        }
+
+        v
+    }
+
+    fn for_each_pattern(mut test_fn: impl FnMut(Vec<i32>)) {
+        for len in TEST_SIZES {
+            for percent in [50.0, 75.0, 95.0, 99.0] {
+                for at_end in [false, true] {
+                    for reversed in [false, true] {
+                        test_fn(presorted(len, percent, at_end, reversed));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check<T: Ord + Clone + std::fmt::Debug>(v: Vec<T>) {
+        let mut expected = v.clone();
+        expected.sort();
+
+        let mut sorted = v;
+        ipnsort::sort(&mut sorted);
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn sorts() {
+        for_each_pattern(check);
+    }
+
+    #[test]
+    fn sorts_with_scratch() {
+        let mut scratch = vec![std::mem::MaybeUninit::uninit(); 200];
+
+        for_each_pattern(|v| {
+            let mut expected = v.clone();
+            expected.sort();
+
+            let mut sorted = v;
+            ipnsort::sort_with_scratch(&mut sorted, &mut scratch, |a, b| a < b);
+            assert_eq!(sorted, expected);
+        });
+    }
+
+    #[test]
+    fn sorts_strings() {
+        for_each_pattern(|v| check(v.iter().map(|val| format!("{val:010}")).collect()));
+    }
+
+    #[test]
+    fn sorts_large_types() {
+        // Too large for the stack buffer, the merge only rotates.
+        for_each_pattern(|v| {
+            if v.len() <= 1_000 {
+                check(v.iter().map(|&val| (val, [0u64; 600])).collect());
+            }
+        });
+    }
+
+    #[test]
+    fn interior_mutability() {
+        // Every comparison increments both elements. The merge moves elements instead of copying
+        // them, so no increment may get lost.
+        let v = presorted(10_000, 95.0, true, false);
+        let mut cells: Vec<(i32, Cell<u32>)> = v.iter().map(|&val| (val, Cell::new(0))).collect();
+
+        let mut comps = 0;
+        ipnsort::sort_by(&mut cells, |a, b| {
+            comps += 1;
+            a.1.set(a.1.get() + 1);
+            b.1.set(b.1.get() + 1);
+            a.0.cmp(&b.0)
+        });
+
+        assert!(cells.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert_eq!(cells.iter().map(|c| c.1.get()).sum::<u32>(), comps * 2);
+    }
+
+    #[test]
+    fn panic_retains_elements() {
+        // Each length is sorted about a hundred times, too often for the longer ones in miri.
+        let lens: &[usize] = if cfg!(miri) {
+            &[100]
+        } else {
+            &[100, 1_000, 10_000]
+        };
+
+        for &len in lens {
+            let v: Vec<String> = presorted(len, 90.0, false, false)
+                .iter()
+                .map(|val| val.to_string())
+                .collect();
+
+            let mut comps = 0;
+            ipnsort::sort_by(&mut v.clone(), |a, b| {
+                comps += 1;
+                a.cmp(b)
+            });
+
+            let mut expected = v.clone();
+            expected.sort();
+
+            // The last comparisons are done by the merge.
+            let merge_comps = comps / 4;
+            for panic_at in ((comps - merge_comps)..comps).step_by(merge_comps / 100 + 1) {
+                let mut sorted = v.clone();
+                let mut count = 0;
+                let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                    ipnsort::sort_by(&mut sorted, |a, b| {
+                        count += 1;
+                        if count == panic_at {
+                            panic!();
+                        }
+                        a.cmp(b)
+                    })
+                }));
+
+                assert!(res.is_err());
+                sorted.sort();
+                assert_eq!(sorted, expected);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn fewer_comparisons() {
+        let len = 100_000;
+        let count_comps = |mut v: Vec<i32>| {
+            let mut comps = 0;
+            ipnsort::sort_by(&mut v, |a, b| {
+                comps += 1;
+                a.cmp(b)
+            });
+            comps
+        };
+
+        let random_comps = count_comps(patterns::random(len));
+        for (at_end, reversed) in [(false, false), (false, true), (true, false), (true, true)] {
+            let presorted_comps = count_comps(presorted(len, 95.0, at_end, reversed));
+            assert!(
+                presorted_comps * 4 < random_comps,
+                "{presorted_comps} {random_comps}"
+            );
+        }
+    }
+}
+
+mod sort_ints {
+    use super::for_each_pattern;
+
+    // Sizes above 1024 take the radix sort, and large ones recurse into buckets of it.
+    #[cfg(miri)]
+    const TEST_SIZES: [usize; 5] = [0, 1, 20, 1_023, 1_024];
+
+    #[cfg(feature = "large_test_sizes")]
+    #[cfg(not(miri))]
+    const TEST_SIZES: [usize; 9] = [0, 1, 20, 100, 1_023, 1_024, 4_097, 100_000, 1_000_000];
+
+    #[cfg(not(feature = "large_test_sizes"))]
+    #[cfg(not(miri))]
+    const TEST_SIZES: [usize; 7] = [0, 1, 20, 100, 1_023, 1_024, 4_097];
+
+    // Maps some of the values to the extremes of the type, the rest wraps around.
+    fn test_impl<T: Ord + Copy + std::fmt::Debug>(type_into_fn: impl Fn(i32) -> T, min: T, max: T) {
+        for_each_pattern(&TEST_SIZES, |v| {
+            let v = v
+                .into_iter()
+                .map(|val| match val.rem_euclid(97) {
+                    0 => min,
+                    1 => max,
+                    _ => type_into_fn(val),
+                })
+                .collect::<Vec<_>>();
+
+            let mut expected = v.clone();
+            expected.sort();
+
+            let mut sorted = v;
+            ipnsort::sort(&mut sorted);
+            assert_eq!(sorted, expected, "len: {}", expected.len());
+        });
+    }
+
+    #[test]
+    fn patterns_u8() {
+        test_impl(|val| val as u8, u8::MIN, u8::MAX);
+    }
+
+    #[test]
+    fn patterns_i8() {
+        test_impl(|val| val as i8, i8::MIN, i8::MAX);
+    }
+
+    #[test]
+    fn patterns_u16() {
+        test_impl(|val| val as u16, u16::MIN, u16::MAX);
+    }
+
+    #[test]
+    fn patterns_i16() {
+        test_impl(|val| val as i16, i16::MIN, i16::MAX);
+    }
+
+    #[test]
+    fn patterns_u32() {
+        test_impl(|val| val as u32, u32::MIN, u32::MAX);
+    }
+
+    #[test]
+    fn patterns_i32() {
+        test_impl(|val| val, i32::MIN, i32::MAX);
+    }
+
+    #[test]
+    fn patterns_u64() {
+        // Values with few significant bits in the middle of the key.
+        test_impl(|val| (val as u16 as u64) << 24, u64::MIN, u64::MAX);
+    }
+
+    #[test]
+    fn patterns_i64() {
+        test_impl(|val| val as i64, i64::MIN, i64::MAX);
+    }
+}
+
+mod incremental {
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+
+    use ipnsort::{IncrementalSort, Step};
+    use sort_test_tools::{instantiate_sort_tests, patterns, Sort};
+
+    struct SortImpl {}
+
+    impl Sort for SortImpl {
+        fn name() -> String {
+            "rust_ipnsort_incremental_unstable".into()
+        }
+
+        fn sort<T>(arr: &mut [T])
+        where
+            T: Ord,
+        {
+            Self::sort_by(arr, T::cmp);
+        }
+
+        fn sort_by<T, F>(arr: &mut [T], compare: F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            // Vary the budget, so that the steps end in all phases of the sort.
+            let mut budget = arr.len() % 7;
+            let mut sort = IncrementalSort::new_by(arr, compare);
+            while sort.step(budget) == Step::Pending {
+                budget = (budget * 5 + 3) % 1000;
+            }
+        }
+    }
+
+    instantiate_sort_tests!(SortImpl);
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn steps_stay_within_budget() {
+        let len = 100_000;
+        let budget = 10_000;
+
+        for (name, mut v) in [
+            ("random", patterns::random(len)),
+            ("random_d20", patterns::random_uniform(len, 0..20)),
+            ("ascending", (0..len as i32).collect()),
+        ] {
+            let mut expected = v.clone();
+            ipnsort::sort(&mut expected);
+
+            let comparisons = Cell::new(0);
+            let mut sort = IncrementalSort::new_by(&mut v, |a, b| {
+                comparisons.set(comparisons.get() + 1);
+                a.cmp(b)
+            });
+
+            let mut steps = 0;
+            let mut max_step = 0;
+            loop {
+                let before = comparisons.get();
+                let step = sort.step(budget);
+                steps += 1;
+                max_step = max_step.max(comparisons.get() - before);
+                if step == Step::Done {
+                    break;
+                }
+            }
+
+            // Steps only exceed their budget by choosing a pivot or small-sorting.
+            assert!(max_step < budget + 1000, "{name}: {max_step}");
+            assert!(
+                steps >= comparisons.get() / (budget + 1000),
+                "{name}: {steps}"
+            );
+
+            // Further steps do nothing.
+            assert_eq!(sort.step(budget), Step::Done);
+            drop(sort);
+
+            assert_eq!(v, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn zero_budget() {
+        let mut v = patterns::random(1_000);
+        let mut sort = IncrementalSort::new(&mut v);
+        assert_eq!(sort.step(0), Step::Pending);
+        assert_eq!(sort.step(usize::MAX), Step::Done);
+        assert!(v.windows(2).all(|w| w[0] <= w[1]));
+
+        let mut v = [1];
+        assert_eq!(IncrementalSort::new(&mut v).step(0), Step::Done);
+    }
+}
+
+mod par_sort {
+    use std::cmp::Ordering;
+    use std::sync::{Mutex, PoisonError};
+
+    use sort_test_tools::{instantiate_sort_tests, patterns, Sort};
+
+    // Not all test types are `Send`, e.g. the ones holding an `Rc`. The sort only moves elements
+    // between threads without cloning or dropping them, and the comparator function is serialized
+    // with a `Mutex`.
+    #[repr(transparent)]
+    struct AssertSend<T>(T);
+
+    unsafe impl<T> Send for AssertSend<T> {}
+    unsafe impl<T> Sync for AssertSend<T> {}
+
+    impl<T> AssertSend<T> {
+        // Closures capture the fields they use, this makes them capture the wrapper.
+        fn get(&self) -> &T {
+            &self.0
+        }
+    }
+
+    struct SortImpl {}
+
+    impl Sort for SortImpl {
+        fn name() -> String {
+            "rust_ipnsort_par_unstable".into()
+        }
+
+        fn sort<T>(arr: &mut [T])
+        where
+            T: Ord,
+        {
+            Self::sort_by(arr, T::cmp);
+        }
+
+        fn sort_by<T, F>(arr: &mut [T], compare: F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            // A small cutoff, so that the test sizes cover the parallel code.
+            let config = ipnsort::ParConfig {
+                threads: 4,
+                sequential_cutoff: 64,
+            };
+
+            let compare = AssertSend(Mutex::new(compare));
+
+            // SAFETY: `AssertSend<T>` has the same layout as `T`.
+            let arr = unsafe { &mut *(arr as *mut [T] as *mut [AssertSend<T>]) };
+
+            ipnsort::par_sort_with_config(arr, &config, |a, b| {
+                let mut compare = compare.get().lock().unwrap_or_else(PoisonError::into_inner);
+                compare(a.get(), b.get())
+            });
+        }
+    }
+
+    instantiate_sort_tests!(SortImpl);
+
+    #[test]
+    fn thread_counts() {
+        // Long enough to split a few times, longer ones are prohibitively expensive in miri.
+        let lens: &[usize] = if cfg!(miri) {
+            &[0, 1, 2, 1000, 1001, 3_000]
+        } else {
+            &[0, 1, 2, 1000, 1001, 20_000, 100_000]
+        };
+
+        for threads in [0, 1, 2, 3, 8, 64] {
+            let config = ipnsort::ParConfig {
+                threads,
+                sequential_cutoff: 1000,
+            };
+
+            for &len in lens {
+                for pattern in [patterns::random, patterns::descending, |len| {
+                    patterns::random_uniform(len, 0..10)
+                }] {
+                    let mut v = pattern(len);
+                    let mut expected = v.clone();
+                    expected.sort();
+
+                    ipnsort::par_sort_with_config(&mut v, &config, |a, b| a.cmp(b));
+                    assert_eq!(v, expected, "threads: {threads} len: {len}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn default_config() {
+        let mut v = patterns::random(1_000_000);
+        let mut expected = v.clone();
+        expected.sort();
+
+        ipnsort::par_sort(&mut v);
+        assert_eq!(v, expected);
+
+        ipnsort::par_sort_by(&mut v, |a, b| b.cmp(a));
+        expected.reverse();
+        assert_eq!(v, expected);
+    }
+}
+
+#[cfg(feature = "strict")]
+mod strict {
+    use std::cmp::Ordering;
+    use std::panic::{self, AssertUnwindSafe};
+
+    use sort_test_tools::patterns;
+
+    fn panic_message(f: impl FnOnce()) -> String {
+        let payload = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+        *payload.downcast::<String>().unwrap()
+    }
+
+    #[test]
+    fn names_indices() {
+        // Every result is out of order for this comparison function, the final check has to
+        // report the first pair.
+        let mut v = [2, 1, 3];
+        let msg = panic_message(|| ipnsort::sort_by(&mut v, |_, _| Ordering::Less));
+
+        assert!(
+            msg.starts_with("Ord violation: v[1] compared less than v[0]"),
+            "{msg}"
+        );
+    }
+
+    #[test]
+    fn partial_sort_tail() {
+        let input = patterns::random(1_000);
+        let k = 10;
+
+        // The last `len - k` comparisons check the elements behind `k` against `v[k - 1]`.
+        let mut count = 0;
+        ipnsort::partial_sort_by(&mut input.clone(), k, |a, b| {
+            count += 1;
+            a.cmp(b)
+        });
+
+        let check_start = count - (input.len() - k);
+        let mut count = 0;
+        let msg = panic_message(|| {
+            ipnsort::partial_sort_by(&mut input.clone(), k, |a, b| {
+                count += 1;
+                if count > check_start {
+                    Ordering::Less
+                } else {
+                    a.cmp(b)
+                }
+            })
+        });
+
+        assert!(
+            msg.starts_with("Ord violation: v[10] compared less than v[9]"),
+            "{msg}"
+        );
+    }
+
+    #[test]
+    fn total_order_does_not_panic() {
+        let mut v = patterns::random_uniform(10_000, 0..10);
+        ipnsort::sort(&mut v);
+        assert!(v.windows(2).all(|w| w[0] <= w[1]));
+
+        let mut v = patterns::random(10_000);
+        ipnsort::partial_sort(&mut v, 100);
+        assert!(v[..100].windows(2).all(|w| w[0] <= w[1]));
+    }
+}
+
+#[cfg(feature = "trace")]
+mod trace {
+    use ipnsort::SortObserver;
+    use sort_test_tools::patterns;
+
+    #[derive(Default)]
+    struct Recorder {
+        streaks: Vec<(usize, usize)>,
+        presorted: usize,
+        pivots: Vec<(usize, usize)>,
+        partitions: Vec<(usize, usize)>,
+        equal_partitions: Vec<(usize, usize)>,
+        dual_partitions: Vec<(usize, usize, usize)>,
+        small_sorted: usize,
+        heapsorted: usize,
+    }
+
+    impl SortObserver for Recorder {
+        fn streak(&mut self, len: usize, streak_end: usize, _reversed: bool) {
+            self.streaks.push((len, streak_end));
+        }
+
+        fn presorted(&mut self, _len: usize, start: usize, end: usize) {
+            self.presorted += end - start;
+        }
+
+        fn pivot(&mut self, len: usize, pivot_pos: usize) {
+            self.pivots.push((len, pivot_pos));
+        }
+
+        fn partition(&mut self, len: usize, mid: usize) {
+            self.partitions.push((len, mid));
+        }
+
+        fn equal_partition(&mut self, len: usize, mid: usize) {
+            self.equal_partitions.push((len, mid));
+        }
+
+        fn dual_partition(&mut self, len: usize, mid1: usize, mid2: usize) {
+            self.dual_partitions.push((len, mid1, mid2));
+        }
+
+        fn small_sort(&mut self, len: usize) {
+            self.small_sorted += len;
+        }
+
+        fn heapsort(&mut self, len: usize) {
+            self.heapsorted += len;
+        }
+    }
+
+    // `sort` radix sorts integers, `sort_by` always compares them.
+    fn record(v: &mut [i32]) -> Recorder {
+        let mut recorder = Recorder::default();
+        ipnsort::with_observer(&mut recorder, || ipnsort::sort_by(v, |a, b| a.cmp(b)));
+        recorder
+    }
+
+    #[test]
+    fn events_match_patterns() {
+        let len = 10_000;
+
+        for (name, pattern_fn) in [
+            ("random", patterns::random as fn(usize) -> Vec<i32>),
+            ("random_d20", |len| patterns::random_uniform(len, 0..20)),
+            ("random_s95", |len| patterns::random_sorted(len, 95.0)),
+            ("saw_mixed", |len| {
+                patterns::saw_mixed(len, ((len as f64).log2().round()) as usize)
+            }),
+        ] {
+            let mut v = pattern_fn(len);
+            let recorder = record(&mut v);
+            assert!(v.windows(2).all(|w| w[0] <= w[1]), "{name}");
+
+            assert_eq!(recorder.streaks[0].0, len, "{name}");
+
+            // Every pivot is followed by exactly one partition of the same slice.
+            assert_eq!(
+                recorder.pivots.len(),
+                recorder.partitions.len() + recorder.equal_partitions.len(),
+                "{name}"
+            );
+            for &(len, pos) in &recorder.pivots {
+                assert!(pos < len, "{name}");
+            }
+            for &(len, mid) in recorder.partitions.iter().chain(&recorder.equal_partitions) {
+                assert!(mid < len, "{name}");
+            }
+
+            // Each element ends up as a pivot, in an equal partition, in a small-sort, in heapsort
+            // or in a presorted streak exactly once.
+            let equal: usize = recorder
+                .equal_partitions
+                .iter()
+                .map(|&(_, mid)| mid + 1)
+                .sum();
+            assert_eq!(
+                recorder.partitions.len()
+                    + equal
+                    + recorder.small_sorted
+                    + recorder.heapsorted
+                    + recorder.presorted,
+                len,
+                "{name}"
+            );
+        }
+
+        let mut v = patterns::random_uniform(len, 0..20);
+        assert!(!record(&mut v).equal_partitions.is_empty());
+
+        let mut v = patterns::random_sorted(len, 95.0);
+        assert!(record(&mut v).presorted >= len * 95 / 100);
+    }
+
+    #[test]
+    fn dual_pivot_events() {
+        let len = 10_000;
+
+        for (name, pattern_fn) in [
+            ("random", patterns::random as fn(usize) -> Vec<i32>),
+            ("random_s95", |len| patterns::random_sorted(len, 95.0)),
+        ] {
+            let mut v = pattern_fn(len);
+            let mut recorder = Recorder::default();
+            ipnsort::with_observer(&mut recorder, || ipnsort::sort_dual_pivot(&mut v));
+            assert!(v.windows(2).all(|w| w[0] <= w[1]), "{name}");
+
+            assert!(recorder.partitions.is_empty(), "{name}");
+            assert!(!recorder.dual_partitions.is_empty(), "{name}");
+            for &(len, mid1, mid2) in &recorder.dual_partitions {
+                assert!(mid1 < mid2 && mid2 < len, "{name}");
+            }
+
+            // Each element ends up as one of the pivots, in an equal partition, in a small-sort, in
+            // heapsort or in a presorted streak exactly once. Only the elements between two equal
+            // pivots are skipped, which requires duplicates these patterns practically don't have.
+            let equal: usize = recorder
+                .equal_partitions
+                .iter()
+                .map(|&(_, mid)| mid + 1)
+                .sum();
+            assert_eq!(
+                recorder.dual_partitions.len() * 2
+                    + equal
+                    + recorder.small_sorted
+                    + recorder.heapsorted
+                    + recorder.presorted,
+                len,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn sorted_input_has_no_partitions() {
+        let mut v = (0..10_000).rev().collect::<Vec<i32>>();
+        let recorder = record(&mut v);
+
+        assert_eq!(recorder.streaks, [(10_000, 10_000)]);
+        assert!(recorder.pivots.is_empty());
+    }
+
+    #[test]
+    fn json_lines() {
+        let mut v = patterns::random(10_000);
+        let mut json_lines = ipnsort::JsonLines::new(Vec::new());
+        ipnsort::with_observer(&mut json_lines, || {
+            ipnsort::sort_by(&mut v, |a, b| a.cmp(b))
+        });
+
+        let output = String::from_utf8(json_lines.finish().unwrap()).unwrap();
+        let mut partitions = 0;
+        for line in output.lines() {
+            assert!(
+                line.starts_with("{\"event\":\"") && line.ends_with('}'),
+                "{line}"
+            );
+
+            if let Some(fields) = line.strip_prefix("{\"event\":\"partition\",") {
+                let balance = fields.split("\"balance\":").nth(1).unwrap();
+                let balance: f64 = balance.trim_end_matches('}').parse().unwrap();
+                assert!((0.0..=0.5).contains(&balance), "{line}");
+                partitions += 1;
+            }
+        }
+
+        assert!(partitions > 0);
+    }
+
+    #[cfg(not(feature = "stable"))]
+    #[test]
+    fn radix_sort() {
+        #[derive(Default)]
+        struct RadixSorted(Vec<usize>);
+
+        impl SortObserver for RadixSorted {
+            fn radix_sort(&mut self, len: usize) {
+                self.0.push(len);
+            }
+        }
+
+        let radix_sorted = |mut v: Vec<i32>| {
+            let mut observer = RadixSorted::default();
+            ipnsort::with_observer(&mut observer, || ipnsort::sort(&mut v));
+            observer.0
+        };
+
+        assert_eq!(radix_sorted(patterns::random(10_000)), [10_000]);
+        assert_eq!(
+            radix_sorted(patterns::random_uniform(10_000, 0..20)),
+            [10_000]
+        );
+
+        assert!(radix_sorted(patterns::random(1_000)).is_empty());
+        assert!(radix_sorted(patterns::random_sorted(10_000, 95.0)).is_empty());
+        assert!(radix_sorted(patterns::descending(10_000)).is_empty());
+    }
+
+    #[test]
+    fn no_observer_outside_scope() {
+        let mut recorder = Recorder::default();
+        ipnsort::with_observer(&mut recorder, || {});
+
+        ipnsort::sort(&mut patterns::random(1_000));
+        assert!(recorder.streaks.is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+use std::cmp::Ordering;
+
+use crate::Sort;
+
+/// Builds an input of length `len` that drives `S` into its worst case, with the adversary from
+/// M. D. McIlroy, "A Killer Adversary for Quicksort" (1999).
+///
+/// The values are decided while `S` sorts. All elements start out as gas, which compares greater
+/// than everything else. When two gas elements are compared, one of them is frozen to the
+/// smallest value not used yet. The adversary freezes the one it suspects to be the pivot, the
+/// gas element compared most recently, so partitions end up as unbalanced as possible.
+///
+/// Sorting the returned input makes a sort that only depends on the results of the comparisons
+/// repeat the same comparisons.
+pub fn killer_input<S: Sort>(len: usize) -> Vec<i32> {
+    killer_input_by(len, |ids, compare| <S as Sort>::sort_by(ids, compare))
+}
+
+/// Same as [`killer_input`], but sorts with `sort_by`, which has to sort the ids it's given with
+/// the comparison function it's given.
+pub fn killer_input_by(
+    len: usize,
+    sort_by: impl FnOnce(&mut [u64], &mut dyn FnMut(&u64, &u64) -> Ordering),
+) -> Vec<i32> {
+    let gas = len as i32;
+    let mut values = vec![gas; len];
+    let mut solid = 0;
+    let mut candidate = 0;
+
+    // Otherwise the first comparisons of a sort that looks for a presorted streak freeze the
+    // elements in the order it reads them, which makes the input sorted.
+    if len >= 2 {
+        values[1] = solid;
+        solid += 1;
+    }
+
+    let mut freeze = |values: &mut Vec<i32>, idx: usize| {
+        values[idx] = solid;
+        solid += 1;
+    };
+
+    // The elements are the indices of the values, u64 is supported by all sorts that support
+    // `sort_by`.
+    let mut ids = (0..len as u64).collect::<Vec<_>>();
+    sort_by(&mut ids, &mut |a, b| {
+        let (a, b) = (*a as usize, *b as usize);
+
+        if values[a] == gas && values[b] == gas {
+            if a == candidate {
+                freeze(&mut values, a);
+            } else {
+                freeze(&mut values, b);
+            }
+        }
+
+        if values[a] == gas {
+            candidate = a;
+        } else if values[b] == gas {
+            candidate = b;
+        }
+
+        values[a].cmp(&values[b])
+    });
+
+    // Elements that were never frozen only compared with solid ones, any order of them is
+    // consistent with the comparisons.
+    for value in values.iter_mut().filter(|value| **value == gas) {
+        *value = solid;
+        solid += 1;
+    }
+
+    values
+}
@@ -0,0 +1,188 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::any::{type_name, TypeId};
+use std::cell::Cell;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::ffi_types::{FFIOneKiloByte, FFIString};
+use crate::{patterns, Capabilities, ElementTypes, Sort};
+
+/// A [`GlobalAlloc`] that forwards to [`System`] and counts the allocations of the thread that
+/// runs [`measure`]. It only works as the `#[global_allocator]` of a binary, so tests that use it
+/// have to be a test binary of their own:
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOC: sort_test_tools::alloc_tracking::CountingAlloc = CountingAlloc;
+/// ```
+///
+/// Memory allocated by other threads, or by C and C++ code, is not seen.
+pub struct CountingAlloc;
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Const initialized and without destructor, using it from the allocator doesn't allocate.
+    static MEASUREMENT: Cell<Option<Measurement>> = const { Cell::new(None) };
+}
+
+#[derive(Clone, Copy, Default)]
+struct Measurement {
+    allocs: usize,
+    /// Negative if memory allocated before [`measure`] was freed.
+    live_bytes: isize,
+    peak_bytes: isize,
+}
+
+fn record(bytes: isize, is_alloc: bool) {
+    INSTALLED.store(true, Ordering::Relaxed);
+
+    // Fails during thread teardown, nothing is measured then.
+    let _ = MEASUREMENT.try_with(|measurement| {
+        if let Some(mut m) = measurement.get() {
+            m.allocs += is_alloc as usize;
+            m.live_bytes += bytes;
+            m.peak_bytes = m.peak_bytes.max(m.live_bytes);
+            measurement.set(Some(m));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size() as isize, true);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size() as isize, true);
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record(-(layout.size() as isize), false);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size as isize - layout.size() as isize, true);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// What the current thread allocated while [`measure`] ran.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocReport {
+    /// Calls to `alloc`, `alloc_zeroed` and `realloc`.
+    pub allocs: usize,
+    /// The most bytes that were allocated at the same time, memory allocated before [`measure`]
+    /// doesn't count.
+    pub peak_bytes: usize,
+}
+
+/// Runs `f` and returns what the current thread allocated meanwhile. Panics if [`CountingAlloc`]
+/// isn't the global allocator, which would report nothing.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, AllocReport) {
+    assert!(
+        INSTALLED.load(Ordering::Relaxed),
+        "CountingAlloc has to be the #[global_allocator] of the test binary"
+    );
+
+    MEASUREMENT.set(Some(Measurement::default()));
+    let result = f();
+    let measurement = MEASUREMENT.take().unwrap();
+
+    let report = AllocReport {
+        allocs: measurement.allocs,
+        peak_bytes: measurement.peak_bytes as usize,
+    };
+
+    (result, report)
+}
+
+/// Panics with `context` if `report` exceeds `max_allocs` or `max_bytes`.
+pub fn assert_within(context: &str, report: AllocReport, max_allocs: usize, max_bytes: usize) {
+    assert!(
+        report.allocs <= max_allocs && report.peak_bytes <= max_bytes,
+        "{context}: {} allocations with a peak of {} bytes, expected at most {max_allocs} allocations and {max_bytes} bytes",
+        report.allocs,
+        report.peak_bytes
+    );
+}
+
+/// Memory a sort with [`Capabilities::scratch_len`] may allocate on top of the scratch elements,
+/// for example for its stack of runs.
+const BOOKKEEPING_BYTES: usize = 1024;
+
+/// How often a sort with [`Capabilities::scratch_len`] may allocate, for example once for the
+/// scratch elements and a few times for a growing stack of runs.
+const MAX_ALLOCS: usize = 4;
+
+/// Inputs larger than this are skipped, 100k elements of [`FFIOneKiloByte`] would take a while.
+const MAX_INPUT_BYTES: usize = 1 << 24;
+
+const TEST_LENS: [usize; 34] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 32,
+    33, 64, 100, 256, 1_000, 4_096, 10_000, 100_000,
+];
+
+/// Checks the allocations of [`Sort::sort`] and [`Sort::sort_by`] for every length in
+/// `TEST_LENS` and several element types against [`Sort::capabilities`]. A sort that doesn't
+/// allocate must not allocate at all, one with a [`Capabilities::scratch_len`] must stay within
+/// it, plus some bookkeeping. Sorts that declare neither are only run.
+pub fn check_sort<S: Sort>() {
+    check_sort_type::<S, u64>(|val| val as u64);
+    check_sort_type::<S, FFIString>(|val| FFIString::new(format!("{:010}", val.saturating_abs())));
+    check_sort_type::<S, FFIOneKiloByte>(FFIOneKiloByte::new);
+}
+
+fn check_sort_type<S: Sort, T: Ord + 'static>(from_i32: impl Fn(i32) -> T) {
+    let capabilities = <S as Sort>::capabilities();
+    if !capabilities
+        .types
+        .contains(&ElementTypes::Only(vec![TypeId::of::<T>()]))
+    {
+        return;
+    }
+
+    let patterns: [fn(usize) -> Vec<i32>; 3] =
+        [patterns::random, patterns::ascending, patterns::descending];
+
+    for len in TEST_LENS {
+        if len * mem::size_of::<T>() > MAX_INPUT_BYTES {
+            continue;
+        }
+
+        for pattern in patterns {
+            let mut v = pattern(len).into_iter().map(&from_i32).collect::<Vec<T>>();
+
+            let ((), report) = measure(|| <S as Sort>::sort(&mut v));
+            check_report::<S, T>(&capabilities, "sort", len, report);
+
+            if capabilities.sort_by {
+                let ((), report) = measure(|| <S as Sort>::sort_by(&mut v, |a, b| b.cmp(a)));
+                check_report::<S, T>(&capabilities, "sort_by", len, report);
+            }
+        }
+    }
+}
+
+fn check_report<S: Sort, T>(
+    capabilities: &Capabilities,
+    op: &str,
+    len: usize,
+    report: AllocReport,
+) {
+    let context = format!(
+        "{} {op} of {len} elements of {}",
+        <S as Sort>::name(),
+        type_name::<T>()
+    );
+
+    if !capabilities.allocates {
+        assert_within(&context, report, 0, 0);
+    } else if let Some(scratch_len) = capabilities.scratch_len {
+        let max_bytes = scratch_len(len) * mem::size_of::<T>() + BOOKKEEPING_BYTES;
+        assert_within(&context, report, MAX_ALLOCS, max_bytes);
+    }
+}
@@ -0,0 +1,216 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::ffi_types::FFIString;
+
+/// An element that reports to a registry when it's created, cloned, compared and dropped, see
+/// [`track`].
+///
+/// The identity of an element is the address of its heap allocated string, which stays the same
+/// when the element is moved, also by the C++ sorts, which see it as an [`FFIString`]. A C++ sort
+/// that moves out of an element leaves it with a null pointer, comparing or dropping such an
+/// element doesn't touch the string it had. A use after drop goes unnoticed if a new element got
+/// the string allocation of the dropped one.
+#[repr(transparent)]
+pub struct DropTracked(FFIString);
+
+impl DropTracked {
+    pub fn new(val: i32) -> Self {
+        let tracked = Self(FFIString::new(format!("{:010}", val.saturating_abs())));
+        with_registry(|registry| registry.create(tracked.identity()));
+
+        tracked
+    }
+
+    /// The elements as seen by the C++ sorts.
+    pub fn as_ffi_strings_mut(v: &mut [Self]) -> &mut [FFIString] {
+        // SAFETY: `DropTracked` is a transparent wrapper around `FFIString`.
+        unsafe { &mut *(v as *mut [Self] as *mut [FFIString]) }
+    }
+
+    /// The element behind a reference handed out by a C++ sort.
+    pub fn from_ffi_string(val: &FFIString) -> &Self {
+        // SAFETY: `DropTracked` is a transparent wrapper around `FFIString`.
+        unsafe { &*(val as *const FFIString as *const Self) }
+    }
+
+    /// Only looks at the pointer to the string, the element may already be dropped.
+    fn identity(&self) -> Option<usize> {
+        let data = self.0.data_ptr();
+        (!data.is_null()).then_some(data as usize)
+    }
+}
+
+impl Clone for DropTracked {
+    fn clone(&self) -> Self {
+        let is_live = with_registry(|registry| registry.observe(self.identity(), self));
+        if is_live == Some(false) {
+            // Reading the string could be a use-after-free, the clone is a moved-from element.
+            return Self(FFIString::null());
+        }
+
+        let clone = Self(self.0.clone());
+        with_registry(|registry| {
+            registry.report.clones += 1;
+            registry.create(clone.identity());
+        });
+
+        clone
+    }
+}
+
+impl Drop for DropTracked {
+    fn drop(&mut self) {
+        let identity = self.identity();
+        let is_live = with_registry(|registry| registry.release(identity)).unwrap_or(true);
+
+        if !is_live {
+            // Freeing the string a second time would be UB, the test fails on the report instead.
+            mem::forget(mem::replace(&mut self.0, FFIString::null()));
+        }
+    }
+}
+
+impl PartialEq for DropTracked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for DropTracked {}
+
+impl PartialOrd for DropTracked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DropTracked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let is_live = with_registry(|registry| {
+            // Both are observed, also if the first one was already dropped.
+            registry.observe(self.identity(), self) & registry.observe(other.identity(), other)
+        })
+        .unwrap_or(true);
+
+        if is_live {
+            self.0.cmp(&other.0)
+        } else {
+            // Reading the string could be a use-after-free, the result doesn't matter anymore.
+            Ordering::Equal
+        }
+    }
+}
+
+impl fmt::Debug for DropTracked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.identity() {
+            Some(identity) => write!(f, "DropTracked({identity:#x})"),
+            None => write!(f, "DropTracked(moved-from)"),
+        }
+    }
+}
+
+/// What happened to the [`DropTracked`] elements while [`track`] ran.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DropReport {
+    /// Elements created with [`DropTracked::new`] or [`Clone::clone`].
+    pub created: usize,
+    pub clones: usize,
+    pub drops: usize,
+    /// How often an element was compared or cloned at a different address than the time before.
+    pub moves: usize,
+    /// Elements dropped that were already dropped.
+    pub double_drops: usize,
+    /// Elements compared or cloned after they were dropped, or after a C++ sort moved out of them.
+    pub uses_after_drop: usize,
+    /// Elements that were never dropped.
+    pub leaks: usize,
+}
+
+impl DropReport {
+    /// Returns `true` if every element was dropped exactly once and none was used after that.
+    pub fn is_sound(&self) -> bool {
+        self.double_drops == 0 && self.uses_after_drop == 0 && self.leaks == 0
+    }
+}
+
+/// Runs `f` and returns what happened to the [`DropTracked`] elements meanwhile. All elements
+/// created by `f` have to be dropped by the time it returns, or they count as leaks.
+///
+/// Only one `track` runs at a time, the elements may be used from other threads.
+pub fn track<R>(f: impl FnOnce() -> R) -> (R, DropReport) {
+    static TRACKING: Mutex<()> = Mutex::new(());
+
+    // A failing test poisons the mutexes, which says nothing about the next one.
+    let _tracking = TRACKING.lock().unwrap_or_else(|err| err.into_inner());
+    *registry() = Some(Registry::default());
+
+    let result = f();
+
+    let mut registry = registry().take().unwrap();
+    registry.report.leaks = registry.live.len();
+
+    (result, registry.report)
+}
+
+#[derive(Default)]
+struct Registry {
+    /// The last address of each live element.
+    live: HashMap<usize, usize>,
+    report: DropReport,
+}
+
+impl Registry {
+    fn create(&mut self, identity: Option<usize>) {
+        self.live.insert(identity.unwrap(), 0);
+        self.report.created += 1;
+    }
+
+    /// Returns `false` if the element was already dropped.
+    fn observe(&mut self, identity: Option<usize>, addr: *const DropTracked) -> bool {
+        match identity.and_then(|identity| self.live.get_mut(&identity)) {
+            Some(last_addr) => {
+                if *last_addr != 0 && *last_addr != addr as usize {
+                    self.report.moves += 1;
+                }
+                *last_addr = addr as usize;
+                true
+            }
+            None => {
+                self.report.uses_after_drop += 1;
+                false
+            }
+        }
+    }
+
+    /// Returns `false` if the element was already dropped.
+    fn release(&mut self, identity: Option<usize>) -> bool {
+        let Some(identity) = identity else {
+            // Moved-from by a C++ sort, the string is owned by another element.
+            return true;
+        };
+
+        if self.live.remove(&identity).is_some() {
+            self.report.drops += 1;
+            true
+        } else {
+            self.report.double_drops += 1;
+            false
+        }
+    }
+}
+
+fn registry() -> MutexGuard<'static, Option<Registry>> {
+    static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+    REGISTRY.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Returns `None` outside of [`track`].
+fn with_registry<R>(f: impl FnOnce(&mut Registry) -> R) -> Option<R> {
+    registry().as_mut().map(f)
+}
@@ -16,6 +16,10 @@ pub struct FFIString {
     capacity: usize,
 }
 
+// SAFETY: Like a `String`, the value exclusively owns the buffer `data` points to.
+unsafe impl Send for FFIString {}
+unsafe impl Sync for FFIString {}
+
 impl FFIString {
     pub fn new(val: String) -> Self {
         let (data, len, capacity) = val.into_raw_parts();
@@ -26,6 +30,21 @@ impl FFIString {
         }
     }
 
+    /// The state a C++ sort leaves a value in after moving out of it.
+    pub(crate) fn null() -> Self {
+        Self {
+            data: ptr::null_mut(),
+            len: 0,
+            capacity: 0,
+        }
+    }
+
+    /// The string buffer, null after a C++ sort moved out of the value. Unlike [`Self::as_str`]
+    /// this doesn't read the buffer, which may already be freed.
+    pub(crate) fn data_ptr(&self) -> *const c_char {
+        self.data
+    }
+
     pub fn as_str(&self) -> Option<&str> {
         // SAFETY: See `as_str_unchecked`.
         unsafe {
@@ -79,6 +98,13 @@ impl Ord for FFIString {
     }
 }
 
+impl AsRef<[u8]> for FFIString {
+    fn as_ref(&self) -> &[u8] {
+        // SAFETY: See `as_str_unchecked`.
+        unsafe { self.as_str_unchecked().as_bytes() }
+    }
+}
+
 impl std::fmt::Debug for FFIString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "\"{:?}\"", self.as_str())
@@ -183,3 +209,43 @@ impl Ord for F128 {
         self.partial_cmp(other).unwrap()
     }
 }
+
+// Floats ordered by `total_cmp`, the common workaround for `f32` and `f64` not implementing `Ord`.
+// `repr(transparent)` allows sorting them as slices of the underlying float type.
+macro_rules! total_order_float_type {
+    ($name:ident, $float:ty) => {
+        #[repr(transparent)]
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name(pub $float);
+
+        impl $name {
+            pub fn new(val: i32) -> Self {
+                // Keep the order of the input, but produce values with a fractional part.
+                Self(val as $float / 8.0)
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+    };
+}
+
+total_order_float_type!(F32, f32);
+total_order_float_type!(F64, f64);
@@ -1,8 +1,14 @@
-#![feature(vec_into_raw_parts)]
+use std::any::TypeId;
 
 pub trait Sort {
     fn name() -> String;
 
+    /// Describes what the implementation supports. The generated tests skip what it doesn't,
+    /// instead of failing.
+    fn capabilities() -> Capabilities {
+        Capabilities::default()
+    }
+
     fn sort<T>(arr: &mut [T])
     where
         T: Ord;
@@ -10,8 +16,131 @@ pub trait Sort {
     fn sort_by<T, F>(arr: &mut [T], compare: F)
     where
         F: FnMut(&T, &T) -> std::cmp::Ordering;
+
+    /// Sorts by the keys `f` returns for the elements. The default implementation compares the
+    /// keys in [`Sort::sort_by`], so `f` is called twice per comparison.
+    fn sort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        Self::sort_by(arr, |a, b| f(a).cmp(&f(b)));
+    }
+
+    /// Sorts by the keys `f` returns for the elements, calling it once per element. The result
+    /// must be the same as with [`slice::sort_by_cached_key`], which is stable.
+    ///
+    /// The default implementation sorts (key, index) pairs with [`Sort::sort`] and then moves the
+    /// elements into place. The indices are unique, so the result is stable even if the sort is
+    /// not.
+    fn sort_by_cached_key<T, K, F>(arr: &mut [T], f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        let mut pairs = arr
+            .iter()
+            .map(f)
+            .enumerate()
+            .map(|(i, key)| (key, i))
+            .collect::<Vec<_>>();
+
+        Self::sort(&mut pairs);
+
+        // Same as `slice::sort_by_cached_key`, follow the indices of already moved elements to
+        // where they were swapped to.
+        for i in 0..arr.len() {
+            let mut index = pairs[i].1;
+            while index < i {
+                index = pairs[index].1;
+            }
+            pairs[i].1 = index;
+            arr.swap(i, index);
+        }
+    }
+}
+
+/// What a [`Sort`] implementation supports. The default describes an unstable sort that accepts
+/// every type and may allocate.
+#[derive(Clone, Debug)]
+pub struct Capabilities {
+    /// Elements that compare equal keep their relative order.
+    pub stable: bool,
+    /// [`Sort::sort_by`] is implemented instead of panicking.
+    pub sort_by: bool,
+    /// The element types [`Sort::sort`] and [`Sort::sort_by`] accept, others panic.
+    pub types: ElementTypes,
+    /// [`Sort::sort`] and [`Sort::sort_by`] may allocate memory on the heap.
+    pub allocates: bool,
+    /// The most elements the heap memory of [`Sort::sort`] and [`Sort::sort_by`] can hold for a
+    /// slice of the given length, e.g. `len / 2` for a merge sort with a half-sized buffer. `None`
+    /// if unknown or if the sort doesn't allocate.
+    pub scratch_len: Option<fn(usize) -> usize>,
+    /// The sort may be called from several threads at the same time.
+    pub thread_safe: bool,
+    /// [`Sort::sort_by`] panics instead of returning an order that contradicts the comparison
+    /// function.
+    pub panics_on_ord_violation: bool,
+    /// The elements are sorted by C or C++ code. There, the comparison function may mutate
+    /// elements it only gets a shared reference to, which is unsound in Rust.
+    pub ffi: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            stable: false,
+            sort_by: true,
+            types: ElementTypes::Any,
+            allocates: true,
+            scratch_len: None,
+            thread_safe: true,
+            panics_on_ord_violation: false,
+            ffi: false,
+        }
+    }
+}
+
+/// A set of element types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ElementTypes {
+    /// Every type that implements [`Ord`], including ones only known to the caller.
+    Any,
+    /// Only the listed types.
+    Only(Vec<TypeId>),
+}
+
+impl ElementTypes {
+    /// Returns `true` if every type in `other` is also in `self`.
+    pub fn contains(&self, other: &ElementTypes) -> bool {
+        match (self, other) {
+            (ElementTypes::Any, _) => true,
+            (ElementTypes::Only(_), ElementTypes::Any) => false,
+            (ElementTypes::Only(types), ElementTypes::Only(others)) => {
+                others.iter().all(|other| types.contains(other))
+            }
+        }
+    }
+}
+
+/// Same as [`Sort`] but only the `k` smallest elements have to end up sorted in `arr[..k]`.
+pub trait PartialSort {
+    fn name() -> String;
+
+    fn partial_sort<T>(arr: &mut [T], k: usize)
+    where
+        T: Ord;
+
+    fn partial_sort_by<T, F>(arr: &mut [T], k: usize, compare: F)
+    where
+        F: FnMut(&T, &T) -> std::cmp::Ordering;
 }
 
+pub mod adversary;
+pub mod alloc_tracking;
+pub mod drop_tracking;
 pub mod ffi_types;
+pub mod partial_sort_tests;
 pub mod patterns;
+pub mod shrink;
 pub mod tests;
@@ -0,0 +1,347 @@
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::ffi_types::FFIString;
+use crate::patterns;
+use crate::tests::{get_or_init_random_seed_with_name, TEST_SIZES};
+use crate::PartialSort;
+
+fn get_or_init_random_seed<S: PartialSort>() -> u64 {
+    get_or_init_random_seed_with_name(<S as PartialSort>::name)
+}
+
+/// Returns a selection of `k` values worth testing for `len`.
+fn test_ks(len: usize) -> Vec<usize> {
+    let random_k = patterns::random_uniform(1, 0..=(len as i32))[0] as usize;
+
+    let mut ks = vec![
+        0,
+        1,
+        len / 10,
+        len / 2,
+        len.saturating_sub(1),
+        len,
+        random_k,
+    ];
+    ks.retain(|k| *k <= len);
+    ks.sort();
+    ks.dedup();
+
+    ks
+}
+
+fn partial_sort_comp<T: Ord + Clone + Debug, S: PartialSort>(v: &[T], k: usize) {
+    let _seed = get_or_init_random_seed::<S>();
+
+    let mut stdlib_sorted = v.to_vec();
+    stdlib_sorted.sort();
+
+    let mut testsort_sorted = v.to_vec();
+    <S as PartialSort>::partial_sort(&mut testsort_sorted, k);
+
+    if testsort_sorted[..k] != stdlib_sorted[..k] {
+        if v.len() <= 100 {
+            eprintln!("Orginal:  {:?}", v);
+            eprintln!("Expected: {:?}", &stdlib_sorted[..k]);
+            eprintln!("Got:      {:?}", &testsort_sorted[..k]);
+        }
+
+        panic!("Test assertion failed! len: {} k: {}", v.len(), k);
+    }
+
+    // The remaining elements are in unspecified order, but they must be the remaining elements.
+    testsort_sorted[k..].sort();
+    assert!(testsort_sorted == stdlib_sorted);
+}
+
+fn test_impl<T: Ord + Clone + Debug, S: PartialSort>(pattern_fn: impl Fn(usize) -> Vec<T>) {
+    test_impl_sizes::<T, S>(&TEST_SIZES, pattern_fn);
+}
+
+fn test_impl_sizes<T: Ord + Clone + Debug, S: PartialSort>(
+    test_sizes: &[usize],
+    pattern_fn: impl Fn(usize) -> Vec<T>,
+) {
+    for test_len in test_sizes.iter().copied() {
+        let test_data = pattern_fn(test_len);
+
+        for k in test_ks(test_data.len()) {
+            partial_sort_comp::<T, S>(&test_data, k);
+        }
+    }
+}
+
+/// All pattern generators in `patterns`, parameterized the same way as the `tests` module does.
+fn pattern_fns() -> Vec<fn(usize) -> Vec<i32>> {
+    vec![
+        patterns::random,
+        |size| patterns::random_uniform(size, 0..=1),
+        |size| patterns::random_uniform(size, 0..4),
+        |size| patterns::random_uniform(size, 0..256),
+        |size| {
+            if size > 3 {
+                patterns::random_zipf(size, 1.0)
+            } else {
+                Vec::new()
+            }
+        },
+        |size| {
+            if size > 3 {
+                patterns::random_zipf(size, 2.0)
+            } else {
+                Vec::new()
+            }
+        },
+        |size| patterns::random_sorted(size, 50.0),
+        |size| patterns::random_sorted(size, 95.0),
+        patterns::random_random_size,
+        patterns::all_equal,
+        patterns::ascending,
+        patterns::descending,
+        |size| patterns::saw_ascending(size, ((size as f64).log2().round()) as usize),
+        |size| patterns::saw_descending(size, ((size as f64).log2().round()) as usize),
+        |size| patterns::saw_mixed(size, ((size as f64).log2().round()) as usize),
+        |size| patterns::saw_mixed_range(size, 20..50),
+        patterns::pipe_organ,
+    ]
+}
+
+// --- TESTS ---
+
+pub fn basic<S: PartialSort>() {
+    partial_sort_comp::<i32, S>(&[], 0);
+    partial_sort_comp::<(), S>(&[(), (), ()], 2);
+    partial_sort_comp::<i32, S>(&[2, 3], 1);
+    partial_sort_comp::<i32, S>(&[3, 2], 1);
+    partial_sort_comp::<i32, S>(&[2, 3, 99, 6], 2);
+    partial_sort_comp::<i32, S>(&[15, -1, 3, -1, -3, -1, 7], 4);
+}
+
+pub fn patterns_i32<S: PartialSort>() {
+    // Testing every pattern with the largest size takes too long.
+    for pattern_fn in pattern_fns() {
+        test_impl_sizes::<i32, S>(&TEST_SIZES[..TEST_SIZES.len() - 1], pattern_fn);
+    }
+}
+
+pub fn random_type_u64<S: PartialSort>() {
+    test_impl::<u64, S>(|size| {
+        patterns::random(size)
+            .iter()
+            .map(|val| -> u64 {
+                // Extends the value into the 64 bit range,
+                // while preserving input order.
+                let x = ((*val as i64) + (i32::MAX as i64) + 1) as u64;
+                x.checked_mul(i32::MAX as u64).unwrap()
+            })
+            .collect()
+    });
+}
+
+pub fn random_cell_i32<S: PartialSort>() {
+    test_impl::<Cell<i32>, S>(|size| patterns::random(size).into_iter().map(Cell::new).collect());
+}
+
+pub fn random_ffi_str<S: PartialSort>() {
+    test_impl::<FFIString, S>(|test_len| {
+        patterns::random(test_len)
+            .into_iter()
+            .map(|val| FFIString::new(format!("{:010}", val.saturating_abs())))
+            .collect::<Vec<_>>()
+    });
+}
+
+pub fn random_str<S: PartialSort>() {
+    test_impl::<String, S>(|test_len| {
+        patterns::random(test_len)
+            .into_iter()
+            .map(|val| format!("{}", val))
+            .collect::<Vec<_>>()
+    });
+}
+
+pub fn partial_sort_vs_partial_sort_by<S: PartialSort>() {
+    let _seed = get_or_init_random_seed::<S>();
+
+    // Ensure that partial_sort and partial_sort_by produce the same result.
+    let mut input_normal = [800, 3, -801, 5, -801, -3, 60, 200, 50, 7, 10];
+    let expected = [-801, -801, -3, 3, 5, 7];
+
+    let mut input_partial_sort_by = input_normal.to_vec();
+
+    <S as PartialSort>::partial_sort(&mut input_normal, 6);
+    <S as PartialSort>::partial_sort_by(&mut input_partial_sort_by, 6, |a, b| a.cmp(b));
+
+    assert_eq!(input_normal[..6], expected);
+    assert_eq!(input_partial_sort_by[..6], expected);
+}
+
+pub fn panic_retain_original_set<S: PartialSort>() {
+    let _seed = get_or_init_random_seed::<S>();
+
+    for pattern_fn in pattern_fns() {
+        for test_len in &TEST_SIZES[..TEST_SIZES.len() - 2] {
+            // Needs to be non trivial dtor.
+            let mut test_data: Vec<Vec<i32>> = pattern_fn(*test_len)
+                .into_iter()
+                .map(|val| vec![val])
+                .collect();
+
+            let mut expected = test_data.clone();
+            expected.sort();
+
+            let k = test_data.len() / 2;
+
+            let mut required_comps = 0;
+            <S as PartialSort>::partial_sort_by(&mut test_data.clone(), k, |a, b| {
+                required_comps += 1;
+                a.cmp(b)
+            });
+
+            if required_comps == 0 {
+                continue;
+            }
+
+            // Calculate a specific comparison that should panic.
+            // Ensure that it can be any of the possible comparisons and that it always panics.
+            let panic_threshold = patterns::random_uniform(1, 0..required_comps)[0];
+            let mut comp_counter = 0;
+
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                <S as PartialSort>::partial_sort_by(&mut test_data, k, |a, b| {
+                    if comp_counter == panic_threshold {
+                        panic!();
+                    }
+                    comp_counter += 1;
+
+                    a.cmp(b)
+                });
+            }));
+
+            assert!(res.is_err());
+
+            // If the elements don't match, it means the set of elements hasn't remained the same.
+            test_data.sort();
+            assert!(test_data == expected);
+        }
+    }
+}
+
+type InvalidOrdFn<'a> = Box<dyn FnMut(&Vec<i32>, &Vec<i32>) -> Ordering + 'a>;
+
+pub fn violate_ord_retain_original_set<S: PartialSort>() {
+    let _seed = get_or_init_random_seed::<S>();
+
+    // Make sure we get a good distribution of random orderings, that are repeatable with the seed.
+    let random_orderings = patterns::random_uniform(5_000, 0..3);
+    let mut random_idx = 0;
+
+    let mut invalid_ord_comp_functions: Vec<InvalidOrdFn> = vec![
+        Box::new(|_a, _b| -> Ordering {
+            // random
+            random_idx = (random_idx + 1) % random_orderings.len();
+            [Ordering::Less, Ordering::Equal, Ordering::Greater]
+                [random_orderings[random_idx] as usize]
+        }),
+        Box::new(|_a, _b| -> Ordering {
+            // everything is less
+            Ordering::Less
+        }),
+        Box::new(|_a, _b| -> Ordering {
+            // everything is greater
+            Ordering::Greater
+        }),
+        Box::new(|a, b| -> Ordering {
+            // equal means less else greater
+            if a == b {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        }),
+    ];
+
+    for comp_func in &mut invalid_ord_comp_functions {
+        for pattern_fn in pattern_fns() {
+            for test_len in &TEST_SIZES[..TEST_SIZES.len() - 2] {
+                let mut test_data: Vec<Vec<i32>> = pattern_fn(*test_len)
+                    .into_iter()
+                    .map(|val| vec![val])
+                    .collect();
+
+                let mut expected = test_data.clone();
+                expected.sort();
+
+                let k = test_data.len() / 3;
+
+                // It's ok to panic on Ord violation or to complete.
+                // In both cases the original elements must still be present.
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                    <S as PartialSort>::partial_sort_by(&mut test_data, k, &mut *comp_func);
+                }));
+
+                test_data.sort();
+                assert!(test_data == expected);
+            }
+        }
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! instantiate_partial_sort_test_impl_inner {
+    ($sort_impl:ty, miri_yes, $sort_name:ident) => {
+        #[test]
+        fn $sort_name() {
+            sort_test_tools::partial_sort_tests::$sort_name::<$sort_impl>();
+        }
+    };
+    ($sort_impl:ty, miri_no, $sort_name:ident) => {
+        #[test]
+        #[cfg(not(miri))]
+        fn $sort_name() {
+            sort_test_tools::partial_sort_tests::$sort_name::<$sort_impl>();
+        }
+
+        #[test]
+        #[cfg(miri)]
+        #[ignore]
+        fn $sort_name() {}
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! instantiate_partial_sort_test_impl {
+    ($sort_impl:ty, $([$miri_use:ident, $sort_name:ident]),*) => {
+        $(
+            sort_test_tools::instantiate_partial_sort_test_impl_inner!(
+                $sort_impl,
+                $miri_use,
+                $sort_name
+            );
+        )*
+    };
+}
+
+// Same as `instantiate_sort_tests` but for `PartialSort` implementations. The generated test
+// functions have the same names as some of the sort tests, so instantiate them in their own module.
+#[macro_export]
+macro_rules! instantiate_partial_sort_tests {
+    ($sort_impl:ty) => {
+        sort_test_tools::instantiate_partial_sort_test_impl!(
+            $sort_impl,
+            [miri_yes, basic],
+            [miri_yes, panic_retain_original_set],
+            [miri_yes, partial_sort_vs_partial_sort_by],
+            [miri_no, patterns_i32],
+            [miri_yes, random_cell_i32],
+            [miri_yes, random_ffi_str],
+            [miri_no, random_str],
+            [miri_yes, random_type_u64],
+            [miri_no, violate_ord_retain_original_set]
+        );
+    };
+}
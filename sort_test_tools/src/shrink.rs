@@ -0,0 +1,84 @@
+/// Upper limit of how often the test is re-run. Large inputs that only fail as a whole would
+/// otherwise be tried with each element dropped, which can take hours.
+const MAX_RUNS: usize = 10_000;
+
+/// Returns the smallest input derived from `input` for which `fails` returns `true`, which it has
+/// to for `input` itself. Tries halves of the input, the input with a range of elements dropped
+/// and the input with its values replaced by their ranks, for as long as one of them still fails.
+pub fn shrink(input: &[i32], mut fails: impl FnMut(&[i32]) -> bool) -> Vec<i32> {
+    let mut runs = 0;
+    let mut fails = |candidate: &[i32]| {
+        runs += 1;
+        runs <= MAX_RUNS && fails(candidate)
+    };
+
+    let mut current = input.to_vec();
+    loop {
+        let len_before = current.len();
+
+        while current.len() >= 2 {
+            let half = current.len() / 2;
+            if fails(&current[..half]) {
+                current.truncate(half);
+            } else if fails(&current[half..]) {
+                current.drain(..half);
+            } else {
+                break;
+            }
+        }
+
+        let mut range_len = current.len() / 2;
+        while range_len >= 1 {
+            let mut start = 0;
+            while start < current.len() {
+                let end = (start + range_len).min(current.len());
+                let candidate = [&current[..start], &current[end..]].concat();
+                if fails(&candidate) {
+                    current = candidate;
+                } else {
+                    start = end;
+                }
+            }
+            range_len /= 2;
+        }
+
+        let ranks = to_ranks(&current);
+        let ranks_changed = ranks != current && fails(&ranks);
+        if ranks_changed {
+            current = ranks;
+        }
+
+        // Once the runs are used up, nothing fails anymore and this returns.
+        if current.len() == len_before && !ranks_changed {
+            return current;
+        }
+    }
+}
+
+/// Replaces the values with their rank among the distinct values, e.g. `[30, -5, 30, 7]` becomes
+/// `[2, 0, 2, 1]`.
+pub fn to_ranks<T: Ord + Clone>(v: &[T]) -> Vec<i32> {
+    let distinct = distinct_sorted(v);
+
+    v.iter()
+        .map(|val| distinct.binary_search(val).unwrap() as i32)
+        .collect()
+}
+
+/// Returns the distinct values of `v` in ascending order, `to_ranks(v)[i]` is the index of `v[i]`
+/// in it.
+pub fn distinct_sorted<T: Ord + Clone>(v: &[T]) -> Vec<T> {
+    let mut distinct = v.to_vec();
+    distinct.sort();
+    distinct.dedup();
+
+    distinct
+}
+
+/// Formats the result of [`shrink`] as a statement that can be pasted into a test.
+pub fn reproducer(original_len: usize, shrunk: &[i32]) -> String {
+    format!(
+        "Minimal reproducer, shrunk from {original_len} elements:\nlet mut v: [i32; {}] = {shrunk:?};",
+        shrunk.len()
+    )
+}
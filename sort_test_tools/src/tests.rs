@@ -1,5 +1,7 @@
+use std::any::{type_name, TypeId};
 use std::cell::Cell;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::env;
 use std::fmt::Debug;
 use std::fs;
@@ -8,30 +10,34 @@ use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 use std::sync::Mutex;
 
+use crate::drop_tracking::{self, DropTracked};
 use crate::ffi_types::{FFIOneKiloByte, FFIString, F128};
-use crate::patterns;
-use crate::Sort;
+use crate::{adversary, patterns, shrink, Capabilities, ElementTypes, Sort};
 
 #[cfg(miri)]
-const TEST_SIZES: [usize; 18] = [
+pub(crate) const TEST_SIZES: [usize; 18] = [
     0, 1, 2, 3, 4, 5, 6, 7, 8, 10, 15, 20, 24, 33, 50, 100, 280, 400,
 ];
 
 #[cfg(feature = "large_test_sizes")]
 #[cfg(not(miri))]
-const TEST_SIZES: [usize; 30] = [
+pub(crate) const TEST_SIZES: [usize; 30] = [
     0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 15, 16, 17, 20, 24, 30, 32, 33, 35, 50, 100, 200, 500, 1_000,
     2_048, 5_000, 10_000, 100_000, 1_000_000,
 ];
 
 #[cfg(not(feature = "large_test_sizes"))]
 #[cfg(not(miri))]
-const TEST_SIZES: [usize; 28] = [
+pub(crate) const TEST_SIZES: [usize; 28] = [
     0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 15, 16, 17, 20, 24, 30, 32, 33, 35, 50, 100, 200, 500, 1_000,
     2_048, 5_000, 10_000,
 ];
 
 fn get_or_init_random_seed<S: Sort>() -> u64 {
+    get_or_init_random_seed_with_name(<S as Sort>::name)
+}
+
+pub(crate) fn get_or_init_random_seed_with_name(name_fn: fn() -> String) -> u64 {
     static SEED_WRITTEN: Mutex<bool> = Mutex::new(false);
     let seed = patterns::random_init_seed();
 
@@ -39,7 +45,7 @@ fn get_or_init_random_seed<S: Sort>() -> u64 {
     if !*seed_writer {
         // Always write the seed before doing anything to ensure reproducibility of crashes.
         io::stdout()
-            .write_all(format!("\nSeed: {seed}\nTesting: {}\n\n", <S as Sort>::name()).as_bytes())
+            .write_all(format!("\nSeed: {seed}\nTesting: {}\n\n", name_fn()).as_bytes())
             .unwrap();
         io::stdout().flush().unwrap();
 
@@ -49,6 +55,74 @@ fn get_or_init_random_seed<S: Sort>() -> u64 {
     seed
 }
 
+/// What a test needs from a [`Sort`] implementation, see [`Sort::capabilities`].
+#[derive(Clone, Debug)]
+pub struct Requirements {
+    pub stable: bool,
+    pub sort_by: bool,
+    pub types: ElementTypes,
+    pub panics_on_ord_violation: bool,
+    pub ffi: bool,
+}
+
+impl Default for Requirements {
+    fn default() -> Self {
+        Self {
+            stable: false,
+            sort_by: false,
+            types: ElementTypes::Only(Vec::new()),
+            panics_on_ord_violation: false,
+            ffi: false,
+        }
+    }
+}
+
+impl Requirements {
+    /// Returns why `capabilities` don't meet the requirements, or `None` if they do.
+    pub fn unmet_by(&self, capabilities: &Capabilities) -> Option<&'static str> {
+        if self.stable && !capabilities.stable {
+            Some("not stable")
+        } else if self.sort_by && !capabilities.sort_by {
+            Some("sort_by not supported")
+        } else if !capabilities.types.contains(&self.types) {
+            Some("element type not supported")
+        } else if self.panics_on_ord_violation && !capabilities.panics_on_ord_violation {
+            Some("doesn't panic on Ord violations")
+        } else if self.ffi && !capabilities.ffi {
+            Some("not sorted across FFI")
+        } else {
+            None
+        }
+    }
+}
+
+/// Runs `test_fn` if `S` meets `requirements`, otherwise reports that the test was skipped. Tests
+/// of sorts that aren't thread-safe run one at a time.
+pub fn run_with_requirements<S: Sort>(test_name: &str, requirements: &Requirements, test_fn: fn()) {
+    static NOT_THREAD_SAFE: Mutex<()> = Mutex::new(());
+
+    let capabilities = <S as Sort>::capabilities();
+    if let Some(reason) = requirements.unmet_by(&capabilities) {
+        // The test harness captures println, which would hide the skip for passing tests.
+        let message = format!(
+            "\nSkipped {test_name} for {}: {reason}\n",
+            <S as Sort>::name()
+        );
+        io::stdout().write_all(message.as_bytes()).unwrap();
+        io::stdout().flush().unwrap();
+        return;
+    }
+
+    // A failing test poisons the mutex, which says nothing about the next one.
+    let _guard = (!capabilities.thread_safe).then(|| {
+        NOT_THREAD_SAFE
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    });
+
+    test_fn();
+}
+
 fn sort_comp<T: Ord + Clone + Debug, S: Sort>(v: &mut [T]) {
     let seed = get_or_init_random_seed::<S>();
 
@@ -91,11 +165,52 @@ fn sort_comp<T: Ord + Clone + Debug, S: Sort>(v: &mut [T]) {
                 }
             }
 
+            eprintln!("{}", shrink_sort_failure::<T, S>(&original_clone));
             panic!("Test assertion failed!")
         }
     }
 }
 
+fn sort_fails<T: Ord + Clone, S: Sort>(v: &[T]) -> bool {
+    let mut expected = v.to_vec();
+    expected.sort();
+
+    let mut v = v.to_vec();
+    let res = panic::catch_unwind(AssertUnwindSafe(|| <S as Sort>::sort(&mut v)));
+
+    res.is_err() || v != expected
+}
+
+/// Shrinks an input `S::sort` sorts incorrectly and returns the reproducer, see [`shrink`].
+fn shrink_sort_failure<T: Ord + Clone + Debug, S: Sort>(original: &[T]) -> String {
+    // Most failures don't depend on the type, and ranks as i32 make a reproducer for any type.
+    let ranks = shrink::to_ranks(original);
+    let supports_i32 = <S as Sort>::capabilities()
+        .types
+        .contains(&ElementTypes::Only(vec![TypeId::of::<i32>()]));
+    if supports_i32 && sort_fails::<i32, S>(&ranks) {
+        let shrunk = shrink::shrink(&ranks, sort_fails::<i32, S>);
+        return shrink::reproducer(original.len(), &shrunk);
+    }
+
+    // Otherwise the ranks index the distinct elements of the original input.
+    let distinct = shrink::distinct_sorted(original);
+    let from_ranks = |ranks: &[i32]| -> Vec<T> {
+        ranks
+            .iter()
+            .map(|&rank| distinct[rank as usize].clone())
+            .collect()
+    };
+    let shrunk = shrink::shrink(&ranks, |ranks| sort_fails::<T, S>(&from_ranks(ranks)));
+
+    format!(
+        "{}\nIt only fails as {}, the ranks stand for: {:?}",
+        shrink::reproducer(original.len(), &shrunk),
+        type_name::<T>(),
+        from_ranks(&shrunk)
+    )
+}
+
 fn test_impl<T: Ord + Clone + Debug, S: Sort>(pattern_fn: impl Fn(usize) -> Vec<T>) {
     for test_len in TEST_SIZES {
         let mut test_data = pattern_fn(test_len);
@@ -400,30 +515,70 @@ pub fn pipe_organ<S: Sort>() {
     test_impl::<i32, S>(patterns::pipe_organ);
 }
 
-pub fn stability<S: Sort>() {
-    let _seed = get_or_init_random_seed::<S>();
+// For cpp_sorts that only support u64 we can pack the two i32 inside a u64.
+fn i32_tup_as_u64(val: (i32, i32)) -> u64 {
+    let a_bytes = val.0.to_le_bytes();
+    let b_bytes = val.1.to_le_bytes();
 
-    if <S as Sort>::name().contains("unstable") {
-        // It would be great to mark the test as skipped, but that isn't possible as of now.
-        return;
-    }
+    u64::from_le_bytes([a_bytes, b_bytes].concat().try_into().unwrap())
+}
 
-    // For cpp_sorts that only support u64 we can pack the two i32 inside a u64.
-    fn i32_tup_as_u64(val: (i32, i32)) -> u64 {
-        let a_bytes = val.0.to_le_bytes();
-        let b_bytes = val.1.to_le_bytes();
+fn i32_tup_from_u64(val: u64) -> (i32, i32) {
+    let bytes = val.to_le_bytes();
 
-        u64::from_le_bytes([a_bytes, b_bytes].concat().try_into().unwrap())
-    }
+    let a = i32::from_le_bytes(bytes[0..4].try_into().unwrap());
+    let b = i32::from_le_bytes(bytes[4..8].try_into().unwrap());
+
+    (a, b)
+}
+
+fn sorts_stable<S: Sort>(keys: &[i32]) -> bool {
+    let mut counts = HashMap::new();
 
-    fn i32_tup_from_u64(val: u64) -> (i32, i32) {
-        let bytes = val.to_le_bytes();
+    // create a vector like [(6, 1), (5, 1), (6, 2), ...],
+    // where the first item of each tuple is the key, but
+    // the second item represents which occurrence of that
+    // number this element is, i.e., the second elements
+    // will occur in sorted order.
+    let mut v: Vec<_> = keys
+        .iter()
+        .map(|&key| {
+            let count = counts.entry(key).or_insert(0);
+            *count += 1;
+            i32_tup_as_u64((key, *count))
+        })
+        .collect();
 
-        let a = i32::from_le_bytes(bytes[0..4].try_into().unwrap());
-        let b = i32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    // Only sort on the first element, so an unstable sort
+    // may mix up the counts.
+    <S as Sort>::sort_by(&mut v, |a_packed, b_packed| {
+        let a = i32_tup_from_u64(*a_packed).0;
+        let b = i32_tup_from_u64(*b_packed).0;
 
-        (a, b)
+        a.cmp(&b)
+    });
+
+    // This comparison includes the count (the second item
+    // of the tuple), so elements with equal first items
+    // will need to be ordered with increasing
+    // counts... i.e., exactly asserting that this sort is
+    // stable.
+    v.windows(2)
+        .all(|w| i32_tup_from_u64(w[0]) <= i32_tup_from_u64(w[1]))
+}
+
+fn assert_sorts_stable<S: Sort>(keys: &[i32]) {
+    if !sorts_stable::<S>(keys) {
+        let shrunk = shrink::shrink(keys, |keys| !sorts_stable::<S>(keys));
+        panic!(
+            "Elements with equal keys were reordered.\n{}",
+            shrink::reproducer(keys.len(), &shrunk)
+        );
     }
+}
+
+pub fn stability<S: Sort>() {
+    let _seed = get_or_init_random_seed::<S>();
 
     let large_range = if cfg!(miri) { 100..110 } else { 3000..3010 };
     let rounds = if cfg!(miri) { 1 } else { 10 };
@@ -433,14 +588,7 @@ pub fn stability<S: Sort>() {
 
     for len in (2..55).chain(large_range) {
         for _ in 0..rounds {
-            let mut counts = [0; 10];
-
-            // create a vector like [(6, 1), (5, 1), (6, 2), ...],
-            // where the first item of each tuple is random, but
-            // the second item represents which occurrence of that
-            // number this element is, i.e., the second elements
-            // will occur in sorted order.
-            let orig: Vec<_> = (0..len)
+            let keys: Vec<_> = (0..len)
                 .map(|_| {
                     let n = rand_vals[rand_idx];
                     rand_idx += 1;
@@ -448,29 +596,11 @@ pub fn stability<S: Sort>() {
                         rand_idx = 0;
                     }
 
-                    counts[n as usize] += 1;
-                    i32_tup_as_u64((n, counts[n as usize]))
+                    n
                 })
                 .collect();
 
-            let mut v = orig.clone();
-            // Only sort on the first element, so an unstable sort
-            // may mix up the counts.
-            <S as Sort>::sort_by(&mut v, |a_packed, b_packed| {
-                let a = i32_tup_from_u64(*a_packed).0;
-                let b = i32_tup_from_u64(*b_packed).0;
-
-                a.cmp(&b)
-            });
-
-            // This comparison includes the count (the second item
-            // of the tuple), so elements with equal first items
-            // will need to be ordered with increasing
-            // counts... i.e., exactly asserting that this sort is
-            // stable.
-            assert!(v
-                .windows(2)
-                .all(|w| i32_tup_from_u64(w[0]) <= i32_tup_from_u64(w[1])));
+            assert_sorts_stable::<S>(&keys);
         }
     }
 }
@@ -478,65 +608,13 @@ pub fn stability<S: Sort>() {
 pub fn stability_with_patterns<S: Sort>() {
     let _seed = get_or_init_random_seed::<S>();
 
-    if <S as Sort>::name().contains("unstable") {
-        // It would be great to mark the test as skipped, but that isn't possible as of now.
-        return;
-    }
-
-    // For cpp_sorts that only support u64 we can pack the two i32 inside a u64.
-    fn i32_tup_as_u64(val: (i32, i32)) -> u64 {
-        let a_bytes = val.0.to_le_bytes();
-        let b_bytes = val.1.to_le_bytes();
-
-        u64::from_le_bytes([a_bytes, b_bytes].concat().try_into().unwrap())
-    }
-
-    fn i32_tup_from_u64(val: u64) -> (i32, i32) {
-        let bytes = val.to_le_bytes();
-
-        let a = i32::from_le_bytes(bytes[0..4].try_into().unwrap());
-        let b = i32::from_le_bytes(bytes[4..8].try_into().unwrap());
-
-        (a, b)
-    }
-
     let test_fn = |test_len: usize, pattern_fn: fn(usize) -> Vec<i32>| {
-        let pattern = pattern_fn(test_len);
-
-        let mut counts = [0i32; 128];
-
-        // create a vector like [(6, 1), (5, 1), (6, 2), ...],
-        // where the first item of each tuple is random, but
-        // the second item represents which occurrence of that
-        // number this element is, i.e., the second elements
-        // will occur in sorted order.
-        let orig: Vec<_> = pattern
+        let keys: Vec<_> = pattern_fn(test_len)
             .iter()
-            .map(|val| {
-                let n = val.saturating_abs() % counts.len() as i32;
-                counts[n as usize] += 1;
-                i32_tup_as_u64((n, counts[n as usize]))
-            })
+            .map(|val| val.saturating_abs() % 128)
             .collect();
 
-        let mut v = orig.clone();
-        // Only sort on the first element, so an unstable sort
-        // may mix up the counts.
-        <S as Sort>::sort_by(&mut v, |a_packed, b_packed| {
-            let a = i32_tup_from_u64(*a_packed).0;
-            let b = i32_tup_from_u64(*b_packed).0;
-
-            a.cmp(&b)
-        });
-
-        // This comparison includes the count (the second item
-        // of the tuple), so elements with equal first items
-        // will need to be ordered with increasing
-        // counts... i.e., exactly asserting that this sort is
-        // stable.
-        assert!(v
-            .windows(2)
-            .all(|w| i32_tup_from_u64(w[0]) <= i32_tup_from_u64(w[1])));
+        assert_sorts_stable::<S>(&keys);
     };
 
     test_impl_custom(test_fn);
@@ -645,11 +723,8 @@ pub fn observable_is_less_u64<S: Sort>() {
     // is marked as mutable. Thus allowing member functions to mutate it even though they only have
     // access to a const reference. Now this int could be a pointer that was cleared inside the
     // comparison function, but this clearing is potentially not observable after the sort and it
-    // will be freed again. C and C++ have no concept similar to UnsafeCell.
-    if <S as Sort>::name().contains("rust_") {
-        // It would be great to mark the test as skipped, but that isn't possible as of now.
-        return;
-    }
+    // will be freed again. C and C++ have no concept similar to UnsafeCell. So this only tests
+    // sorts that cross FFI.
 
     use std::mem;
 
@@ -934,6 +1009,69 @@ pub fn panic_retain_original_set_cell_i32<S: Sort>() {
     panic_retain_original_set_impl::<S, Cell<i32>>(|val| Cell::new(val), |val| val.get());
 }
 
+pub fn drop_accounting<S: Sort>() {
+    let _seed = get_or_init_random_seed::<S>();
+
+    // Every element has to be dropped exactly once, and none may be used after it was dropped.
+    // Retaining the original set of values, as checked by the other tests, doesn't show that.
+    let test_fn = |test_len: usize, pattern_fn: fn(usize) -> Vec<i32>| {
+        let pattern = pattern_fn(test_len);
+
+        let ((), report) = drop_tracking::track(|| {
+            let mut test_data = pattern
+                .iter()
+                .map(|val| DropTracked::new(*val))
+                .collect::<Vec<_>>();
+
+            <S as Sort>::sort(&mut test_data);
+            <S as Sort>::sort_by(&mut test_data, |a, b| b.cmp(a));
+        });
+
+        assert!(report.is_sound(), "{report:?} with {test_len} elements");
+    };
+
+    test_impl_custom(test_fn);
+}
+
+pub fn drop_accounting_comp_panic<S: Sort>() {
+    let _seed = get_or_init_random_seed::<S>();
+
+    // Same as `drop_accounting`, but the comparison function panics. This is where sorts that
+    // move elements into temporary memory are most likely to drop an element twice or not at all.
+    let test_fn = |test_len: usize, pattern_fn: fn(usize) -> Vec<i32>| {
+        let pattern = pattern_fn(test_len);
+
+        let (res, report) = drop_tracking::track(|| {
+            let mut test_data = pattern
+                .iter()
+                .map(|val| DropTracked::new(*val))
+                .collect::<Vec<_>>();
+
+            let required_comps = calc_comps_required::<DropTracked, S>(&test_data, |a, b| a.cmp(b));
+            let panic_threshold =
+                patterns::random_uniform(1, 1..=required_comps as i32)[0] as usize - 1;
+
+            let mut comp_counter = 0;
+
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                <S as Sort>::sort_by(&mut test_data, |a, b| {
+                    if comp_counter == panic_threshold {
+                        panic!();
+                    }
+                    comp_counter += 1;
+
+                    a.cmp(b)
+                });
+            }))
+        });
+
+        assert!(res.is_err());
+        assert!(report.is_sound(), "{report:?} with {test_len} elements");
+    };
+
+    test_impl_custom(test_fn);
+}
+
 fn panic_observable_is_less_impl<S: Sort, T: Ord + Clone>(
     type_into_fn: impl Fn(i32) -> T + Copy,
     type_from_fn: impl Fn(&T) -> i32,
@@ -1031,6 +1169,7 @@ pub fn panic_observable_is_less_cell_i32<S: Sort>() {
 fn violate_ord_retain_original_set_impl<S: Sort, T: Ord>(
     type_into_fn: impl Fn(i32) -> T + Copy,
     type_from_fn: impl Fn(&T) -> i32,
+    is_strict: bool,
 ) {
     let _seed = get_or_init_random_seed::<S>();
 
@@ -1056,43 +1195,45 @@ fn violate_ord_retain_original_set_impl<S: Sort, T: Ord>(
         random_orderings[ridx] as usize
     };
 
-    let mut random_idx_a = 0;
-    let mut random_idx_b = 0;
-    let mut random_idx_c = 0;
-
-    let mut last_element_a = -1;
-    let mut last_element_b = -1;
-
-    let mut rand_counter_b = 0;
-    let mut rand_counter_c = 0;
-
-    let mut streak_counter_a = 0;
-    let mut streak_counter_b = 0;
+    // The state of the comparison functions carries over from one input to the next. A copy of it
+    // replays a failing input while it's shrunk.
+    #[derive(Clone, Debug)]
+    struct CompState {
+        random_idx_a: usize,
+        random_idx_b: usize,
+        random_idx_c: usize,
+        last_element_a: i32,
+        last_element_b: i32,
+        rand_counter_b: usize,
+        rand_counter_c: usize,
+        streak_counter_a: usize,
+        streak_counter_b: usize,
+    }
 
     // Examples, a = 3, b = 5, c = 9.
     // Correct Ord -> 10010 | is_less(a, b) is_less(a, a) is_less(b, a) is_less(a, c) is_less(c, a)
-    let mut invalid_ord_comp_functions: Vec<Box<dyn FnMut(&T, &T) -> Ordering>> = vec![
-        Box::new(|_a, _b| -> Ordering {
+    let invalid_ord_comp_functions: Vec<Box<dyn Fn(&mut CompState, &T, &T) -> Ordering>> = vec![
+        Box::new(|state, _a, _b| -> Ordering {
             // random
             // Eg. is_less(3, 5) == true, is_less(3, 5) == false
 
-            let idx = get_random_0_1_or_2(&mut random_idx_a);
+            let idx = get_random_0_1_or_2(&mut state.random_idx_a);
             [Ordering::Less, Ordering::Equal, Ordering::Greater][idx]
         }),
-        Box::new(|_a, _b| -> Ordering {
+        Box::new(|_state, _a, _b| -> Ordering {
             // everything is less -> 11111
             Ordering::Less
         }),
-        Box::new(|_a, _b| -> Ordering {
+        Box::new(|_state, _a, _b| -> Ordering {
             // everything is equal -> 00000
             Ordering::Equal
         }),
-        Box::new(|_a, _b| -> Ordering {
+        Box::new(|_state, _a, _b| -> Ordering {
             // everything is greater -> 00000
             // Eg. is_less(3, 5) == false, is_less(5, 3) == false, is_less(3, 3) == false
             Ordering::Greater
         }),
-        Box::new(|a, b| -> Ordering {
+        Box::new(|_state, a, b| -> Ordering {
             // equal means less else greater -> 01000
             if a == b {
                 Ordering::Less
@@ -1100,16 +1241,16 @@ fn violate_ord_retain_original_set_impl<S: Sort, T: Ord>(
                 Ordering::Greater
             }
         }),
-        Box::new(|a, b| -> Ordering {
+        Box::new(|state, a, b| -> Ordering {
             // Transitive breaker. remember last element -> 10001
-            let lea = last_element_a;
-            let leb = last_element_b;
+            let lea = state.last_element_a;
+            let leb = state.last_element_b;
 
             let a_as_i32 = type_from_fn(a);
             let b_as_i32 = type_from_fn(b);
 
-            last_element_a = a_as_i32;
-            last_element_b = b_as_i32;
+            state.last_element_a = a_as_i32;
+            state.last_element_b = b_as_i32;
 
             if a_as_i32 == lea && b_as_i32 != leb {
                 b.cmp(a)
@@ -1117,27 +1258,27 @@ fn violate_ord_retain_original_set_impl<S: Sort, T: Ord>(
                 a.cmp(b)
             }
         }),
-        Box::new(|a, b| -> Ordering {
+        Box::new(|state, a, b| -> Ordering {
             // Sampled random 1% of comparisons are reversed.
-            rand_counter_b += get_random_0_1_or_2(&mut random_idx_b);
-            if rand_counter_b >= 100 {
-                rand_counter_b = 0;
+            state.rand_counter_b += get_random_0_1_or_2(&mut state.random_idx_b);
+            if state.rand_counter_b >= 100 {
+                state.rand_counter_b = 0;
                 b.cmp(a)
             } else {
                 a.cmp(b)
             }
         }),
-        Box::new(|a, b| -> Ordering {
+        Box::new(|state, a, b| -> Ordering {
             // Sampled random 33% of comparisons are reversed.
-            rand_counter_c += get_random_0_1_or_2(&mut random_idx_c);
-            if rand_counter_c >= 3 {
-                rand_counter_c = 0;
+            state.rand_counter_c += get_random_0_1_or_2(&mut state.random_idx_c);
+            if state.rand_counter_c >= 3 {
+                state.rand_counter_c = 0;
                 b.cmp(a)
             } else {
                 a.cmp(b)
             }
         }),
-        Box::new(|a, b| -> Ordering {
+        Box::new(|state, a, b| -> Ordering {
             // STREAK_LEN comparisons yield a.cmp(b) then STREAK_LEN comparisons less. This can
             // discover bugs that neither, random Ord, or just Less or Greater can find. Because it
             // can push a pointer further than expected. Random Ord will average out how far a
@@ -1145,48 +1286,108 @@ fn violate_ord_retain_original_set_impl<S: Sort, T: Ord>(
             // analysis and never enter interesting code.
             const STREAK_LEN: usize = 50;
 
-            streak_counter_a += 1;
-            if streak_counter_a <= STREAK_LEN {
+            state.streak_counter_a += 1;
+            if state.streak_counter_a <= STREAK_LEN {
                 a.cmp(b)
             } else {
-                if streak_counter_a == STREAK_LEN * 2 {
-                    streak_counter_a = 0;
+                if state.streak_counter_a == STREAK_LEN * 2 {
+                    state.streak_counter_a = 0;
                 }
                 Ordering::Less
             }
         }),
-        Box::new(|a, b| -> Ordering {
+        Box::new(|state, a, b| -> Ordering {
             // See above.
             const STREAK_LEN: usize = 50;
 
-            streak_counter_b += 1;
-            if streak_counter_b <= STREAK_LEN {
+            state.streak_counter_b += 1;
+            if state.streak_counter_b <= STREAK_LEN {
                 a.cmp(b)
             } else {
-                if streak_counter_b == STREAK_LEN * 2 {
-                    streak_counter_b = 0;
+                if state.streak_counter_b == STREAK_LEN * 2 {
+                    state.streak_counter_b = 0;
                 }
                 Ordering::Greater
             }
         }),
     ];
 
-    for comp_func in &mut invalid_ord_comp_functions {
-        let test_fn = |test_len: usize, pattern_fn: fn(usize) -> Vec<i32>| {
-            let mut test_data: Vec<T> =
-                pattern_fn(test_len).into_iter().map(type_into_fn).collect();
-            let sum_before: i64 = test_data.iter().map(|x| type_from_fn(x) as i64).sum();
+    let mut state = CompState {
+        random_idx_a: 0,
+        random_idx_b: 0,
+        random_idx_c: 0,
+        last_element_a: -1,
+        last_element_b: -1,
+        rand_counter_b: 0,
+        rand_counter_c: 0,
+        streak_counter_a: 0,
+        streak_counter_b: 0,
+    };
 
-            // It's ok to panic on Ord violation or to complete.
-            // In both cases the original elements must still be present.
-            let _ = panic::catch_unwind(AssertUnwindSafe(|| {
-                <S as Sort>::sort_by(&mut test_data, &mut *comp_func);
-            }));
+    // If `is_strict`, the sort has to panic instead of returning a result that contradicts the
+    // comparison function. Remember the last answer given for each pair of element locations, and
+    // require that every adjacent pair of the result was last compared as in order.
 
-            // If the sum before and after don't match, it means the set of elements hasn't remained the
-            // same.
-            let sum_after: i64 = test_data.iter().map(|x| type_from_fn(x) as i64).sum();
-            assert_eq!(sum_before, sum_after);
+    // Returns a description of the failure, if the sort doesn't retain the original elements.
+    let check = |comp_func: &dyn Fn(&mut CompState, &T, &T) -> Ordering,
+                 state: &mut CompState,
+                 pattern: &[i32]|
+     -> Result<(), String> {
+        let mut test_data: Vec<T> = pattern.iter().copied().map(type_into_fn).collect();
+        let sum_before: i64 = test_data.iter().map(|x| type_from_fn(x) as i64).sum();
+
+        let mut last_answers = HashMap::new();
+
+        // It's ok to panic on Ord violation or to complete.
+        // In both cases the original elements must still be present.
+        let sort_result = panic::catch_unwind(AssertUnwindSafe(|| {
+            <S as Sort>::sort_by(&mut test_data, |a, b| {
+                let ord = comp_func(state, a, b);
+                if is_strict {
+                    last_answers.insert((a as *const T, b as *const T), ord);
+                }
+                ord
+            });
+        }));
+
+        if is_strict && sort_result.is_ok() {
+            for (i, pair) in test_data.windows(2).enumerate() {
+                let last_answer = last_answers.get(&(&pair[1] as *const T, &pair[0] as *const T));
+                if !matches!(last_answer, Some(Ordering::Equal | Ordering::Greater)) {
+                    return Err(format!(
+                        "strict sort returned v[{}] after v[{i}], last comparison: {last_answer:?}",
+                        i + 1
+                    ));
+                }
+            }
+        }
+
+        // If the sum before and after don't match, it means the set of elements hasn't remained the
+        // same.
+        let sum_after: i64 = test_data.iter().map(|x| type_from_fn(x) as i64).sum();
+        if sum_before != sum_after {
+            return Err(format!(
+                "sum before {sum_before} and after {sum_after} differ"
+            ));
+        }
+
+        Ok(())
+    };
+
+    for (comp_idx, comp_func) in invalid_ord_comp_functions.iter().enumerate() {
+        let test_fn = |test_len: usize, pattern_fn: fn(usize) -> Vec<i32>| {
+            let pattern = pattern_fn(test_len);
+            let state_before = state.clone();
+
+            if let Err(failure) = check(comp_func.as_ref(), &mut state, &pattern) {
+                let shrunk = shrink::shrink(&pattern, |pattern| {
+                    check(comp_func.as_ref(), &mut state_before.clone(), pattern).is_err()
+                });
+                panic!(
+                    "{failure}, with comparison function {comp_idx} and {state_before:?}.\n{}",
+                    shrink::reproducer(pattern.len(), &shrunk)
+                );
+            }
         };
 
         test_impl_custom(test_fn);
@@ -1201,18 +1402,35 @@ fn violate_ord_retain_original_set_impl<S: Sort, T: Ord>(
 }
 
 pub fn violate_ord_retain_original_set_i32<S: Sort>() {
-    violate_ord_retain_original_set_impl::<S, i32>(|val| val, |val| *val);
+    violate_ord_retain_original_set_impl::<S, i32>(|val| val, |val| *val, false);
 }
 
 pub fn violate_ord_retain_original_set_ffi_string<S: Sort>() {
     violate_ord_retain_original_set_impl::<S, FFIString>(
         |val| FFIString::new(format!("{:010}", val.saturating_abs())),
         |val| val.as_str().unwrap().parse::<i32>().unwrap(),
+        false,
     );
 }
 
 pub fn violate_ord_retain_original_set_cell_i32<S: Sort>() {
-    violate_ord_retain_original_set_impl::<S, Cell<i32>>(|val| Cell::new(val), |val| val.get());
+    violate_ord_retain_original_set_impl::<S, Cell<i32>>(Cell::new, |val| val.get(), false);
+}
+
+pub fn violate_ord_strict_i32<S: Sort>() {
+    violate_ord_retain_original_set_impl::<S, i32>(|val| val, |val| *val, true);
+}
+
+pub fn violate_ord_strict_ffi_string<S: Sort>() {
+    violate_ord_retain_original_set_impl::<S, FFIString>(
+        |val| FFIString::new(format!("{:010}", val.saturating_abs())),
+        |val| val.as_str().unwrap().parse::<i32>().unwrap(),
+        true,
+    );
+}
+
+pub fn violate_ord_strict_cell_i32<S: Sort>() {
+    violate_ord_retain_original_set_impl::<S, Cell<i32>>(Cell::new, |val| val.get(), true);
 }
 
 pub fn sort_vs_sort_by<S: Sort>() {
@@ -1231,6 +1449,97 @@ pub fn sort_vs_sort_by<S: Sort>() {
     assert_eq!(input_sort_by, expected);
 }
 
+pub fn sort_by_key<S: Sort>() {
+    let _seed = get_or_init_random_seed::<S>();
+
+    for test_len in TEST_SIZES {
+        // Different elements share a key, so only the keys of the result are known.
+        let key_fn = |val: &i32| val.rem_euclid(1000);
+        for pattern in [
+            patterns::random(test_len),
+            patterns::random_uniform(test_len, 0..16),
+        ] {
+            let mut expected = pattern.clone();
+            expected.sort_by_key(key_fn);
+
+            let mut v = pattern;
+            <S as Sort>::sort_by_key(&mut v, key_fn);
+
+            assert!(v.iter().map(key_fn).eq(expected.iter().map(key_fn)));
+            v.sort();
+            expected.sort();
+            assert_eq!(v, expected);
+        }
+
+        // A key that allocates and orders differently than the elements.
+        if test_len <= 100_000 {
+            let mut expected = patterns::random(test_len);
+            let mut v = expected.clone();
+
+            expected.sort_by_key(|val| val.to_string());
+            <S as Sort>::sort_by_key(&mut v, |val| val.to_string());
+            assert_eq!(v, expected);
+        }
+    }
+}
+
+pub fn sort_by_cached_key<S: Sort>() {
+    let _seed = get_or_init_random_seed::<S>();
+
+    // Elements with equal keys have to keep their order.
+    let key_fns: [fn(&i32) -> String; 2] = [
+        |val| val.to_string(),
+        |val| format!("{}", val.rem_euclid(16)),
+    ];
+
+    for test_len in TEST_SIZES {
+        if test_len > 100_000 {
+            continue;
+        }
+
+        for key_fn in key_fns {
+            let mut expected = patterns::random(test_len);
+            let mut v = expected.clone();
+            expected.sort_by_cached_key(key_fn);
+
+            let mut key_calls = 0;
+            <S as Sort>::sort_by_cached_key(&mut v, |val| {
+                key_calls += 1;
+                key_fn(val)
+            });
+
+            // Slices with fewer than two elements need no keys.
+            assert_eq!(v, expected);
+            assert!(key_calls <= test_len);
+        }
+    }
+}
+
+pub fn adversarial_comparisons<S: Sort>() {
+    let _seed = get_or_init_random_seed::<S>();
+
+    // Quicksorts without a worst-case guarantee make O(N^2) comparisons for the inputs the
+    // adversary builds. Larger sizes would turn a failure into a test that takes hours.
+    for test_len in TEST_SIZES {
+        if !(2..=10_000).contains(&test_len) {
+            continue;
+        }
+
+        let killer_input = adversary::killer_input::<S>(test_len);
+        let comps = calc_comps_required::<i32, S>(&killer_input, |a, b| a.cmp(b));
+
+        // A quicksort that falls back to heapsort after 2 * log2(N) imbalanced partitions, like
+        // ipnsort, makes up to 2 * N comparisons per partition, two per element with two pivots,
+        // so 4 * N * log2(N) before the fallback. Heapsort adds up to about 2 * N * log2(N) more.
+        let max_comps = (6.0 * test_len as f64 * (test_len as f64).log2()) as u32;
+        assert!(
+            comps <= max_comps,
+            "{comps} comparisons to sort the {test_len} elements of the killer input, expected at \
+             most {max_comps}"
+        );
+    }
+}
+
 pub fn int_edge<S: Sort>() {
     let _seed = get_or_init_random_seed::<S>();
 
@@ -1270,20 +1579,67 @@ pub fn int_edge<S: Sort>() {
     sort_comp::<i32, S>(&mut large);
 }
 
+/// Builds the [`Requirements`] of a test from a comma separated list of `stable`, `sort_by`,
+/// `any_type`, `types(T, ..)`, `panics_on_ord_violation` and `ffi`, each preceded by a comma.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! requirements {
+    (@ $requirements:ident) => {};
+    (@ $requirements:ident, stable $($rest:tt)*) => {
+        $requirements.stable = true;
+        $crate::requirements!(@ $requirements $($rest)*);
+    };
+    (@ $requirements:ident, sort_by $($rest:tt)*) => {
+        $requirements.sort_by = true;
+        $crate::requirements!(@ $requirements $($rest)*);
+    };
+    (@ $requirements:ident, any_type $($rest:tt)*) => {
+        $requirements.types = $crate::ElementTypes::Any;
+        $crate::requirements!(@ $requirements $($rest)*);
+    };
+    (@ $requirements:ident, types($($type:ty),+) $($rest:tt)*) => {
+        $requirements.types =
+            $crate::ElementTypes::Only(vec![$(std::any::TypeId::of::<$type>()),+]);
+        $crate::requirements!(@ $requirements $($rest)*);
+    };
+    (@ $requirements:ident, panics_on_ord_violation $($rest:tt)*) => {
+        $requirements.panics_on_ord_violation = true;
+        $crate::requirements!(@ $requirements $($rest)*);
+    };
+    (@ $requirements:ident, ffi $($rest:tt)*) => {
+        $requirements.ffi = true;
+        $crate::requirements!(@ $requirements $($rest)*);
+    };
+    ($($requirement:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut requirements = $crate::tests::Requirements::default();
+        $crate::requirements!(@ requirements $($requirement)*);
+        requirements
+    }};
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! instantiate_sort_test_impl_inner {
-    ($sort_impl:ty, miri_yes, $sort_name:ident) => {
+    ($sort_impl:ty, miri_yes, $sort_name:ident $($requirement:tt)*) => {
         #[test]
         fn $sort_name() {
-            sort_test_tools::tests::$sort_name::<$sort_impl>();
+            sort_test_tools::tests::run_with_requirements::<$sort_impl>(
+                stringify!($sort_name),
+                &sort_test_tools::requirements!($($requirement)*),
+                sort_test_tools::tests::$sort_name::<$sort_impl>,
+            );
         }
     };
-    ($sort_impl:ty, miri_no, $sort_name:ident) => {
+    ($sort_impl:ty, miri_no, $sort_name:ident $($requirement:tt)*) => {
         #[test]
         #[cfg(not(miri))]
         fn $sort_name() {
-            sort_test_tools::tests::$sort_name::<$sort_impl>();
+            sort_test_tools::tests::run_with_requirements::<$sort_impl>(
+                stringify!($sort_name),
+                &sort_test_tools::requirements!($($requirement)*),
+                sort_test_tools::tests::$sort_name::<$sort_impl>,
+            );
         }
 
         #[test]
@@ -1296,68 +1652,114 @@ macro_rules! instantiate_sort_test_impl_inner {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! instantiate_sort_test_impl {
-    ($sort_impl:ty, $([$miri_use:ident, $sort_name:ident]),*) => {
+    ($sort_impl:ty, $([$miri_use:ident, $sort_name:ident $($requirement:tt)*]),*) => {
         $(
-            sort_test_tools::instantiate_sort_test_impl_inner!($sort_impl, $miri_use, $sort_name);
+            sort_test_tools::instantiate_sort_test_impl_inner!(
+                $sort_impl, $miri_use, $sort_name $($requirement)*
+            );
         )*
     };
 }
 
 // Some tests are not tested with miri to avoid prohibitively long test times.
 // This leaves coverage holes, but the way they are selected should make for relatively small holes.
+//
+// Each test lists what it needs from the sort, see `requirements!`. Tests with types only known
+// to the test itself need `any_type`. Tests the sort doesn't support are skipped and reported as
+// such, see `Sort::capabilities`.
 #[macro_export]
 macro_rules! instantiate_sort_tests {
     ($sort_impl:ty) => {
         sort_test_tools::instantiate_sort_test_impl!(
             $sort_impl,
-            [miri_no, all_equal],
-            [miri_yes, ascending],
-            [miri_no, saw_ascending],
-            [miri_yes, basic],
-            [miri_yes, comp_panic],
-            [miri_yes, descending],
-            [miri_no, saw_descending],
-            [miri_yes, dyn_val],
+            [miri_yes, adversarial_comparisons, sort_by, types(i32, u64)],
+            [miri_no, all_equal, types(i32)],
+            [miri_yes, ascending, types(i32)],
+            [miri_no, saw_ascending, types(i32)],
+            [miri_yes, basic, types(i32, ())],
+            [miri_yes, comp_panic, sort_by, any_type],
+            [miri_yes, descending, types(i32)],
+            [miri_no, saw_descending, types(i32)],
+            [miri_yes, drop_accounting, sort_by, types($crate::drop_tracking::DropTracked)],
+            [
+                miri_no,
+                drop_accounting_comp_panic,
+                sort_by,
+                types($crate::drop_tracking::DropTracked)
+            ],
+            [miri_yes, dyn_val, any_type],
             [miri_yes, fixed_seed],
-            [miri_yes, int_edge],
-            [miri_yes, observable_is_less],
-            [miri_yes, observable_is_less_mut_ptr],
-            [miri_yes, observable_is_less_u64],
-            [miri_yes, panic_observable_is_less_i32],
-            [miri_no, panic_observable_is_less_ffi_string],
-            [miri_no, panic_observable_is_less_cell_i32],
-            [miri_yes, panic_retain_original_set_i32],
-            [miri_no, panic_retain_original_set_ffi_string],
-            [miri_no, panic_retain_original_set_cell_i32],
-            [miri_yes, pipe_organ],
-            [miri_yes, random],
-            [miri_no, random_binary],
-            [miri_yes, random_d1024],
-            [miri_no, random_d16],
-            [miri_yes, random_d256],
-            [miri_yes, random_d4],
-            [miri_no, random_d8],
-            [miri_yes, random_f128],
-            [miri_yes, random_ffi_str],
-            [miri_yes, random_large_val],
-            [miri_yes, random_narrow],
-            [miri_yes, random_s50],
-            [miri_yes, random_s95],
-            [miri_no, random_str],
-            [miri_yes, random_type_u128],
-            [miri_yes, random_type_u64],
-            [miri_yes, random_cell_i32],
-            [miri_yes, random_z1],
-            [miri_no, random_z1_03],
-            [miri_no, random_z2],
-            [miri_yes, saw_mixed],
-            [miri_yes, saw_mixed_range],
-            [miri_yes, sort_vs_sort_by],
-            [miri_yes, stability],
-            [miri_no, stability_with_patterns],
-            [miri_yes, violate_ord_retain_original_set_i32],
-            [miri_no, violate_ord_retain_original_set_ffi_string],
-            [miri_no, violate_ord_retain_original_set_cell_i32]
+            [miri_yes, int_edge, types(i32, u64)],
+            [miri_yes, observable_is_less, sort_by, any_type],
+            [miri_yes, observable_is_less_mut_ptr, sort_by, any_type],
+            [miri_yes, observable_is_less_u64, sort_by, ffi, types(u64)],
+            [miri_yes, panic_observable_is_less_i32, sort_by, any_type],
+            [miri_no, panic_observable_is_less_ffi_string, sort_by, any_type],
+            [miri_no, panic_observable_is_less_cell_i32, sort_by, any_type],
+            [miri_yes, panic_retain_original_set_i32, sort_by, types(i32)],
+            [
+                miri_no,
+                panic_retain_original_set_ffi_string,
+                sort_by,
+                types($crate::ffi_types::FFIString)
+            ],
+            [miri_no, panic_retain_original_set_cell_i32, sort_by, types(std::cell::Cell<i32>)],
+            [miri_yes, pipe_organ, types(i32)],
+            [miri_yes, random, types(i32)],
+            [miri_no, random_binary, types(i32)],
+            [miri_yes, random_d1024, types(i32)],
+            [miri_no, random_d16, types(i32)],
+            [miri_yes, random_d256, types(i32)],
+            [miri_yes, random_d4, types(i32)],
+            [miri_no, random_d8, types(i32)],
+            [miri_yes, random_f128, types($crate::ffi_types::F128)],
+            [miri_yes, random_ffi_str, types($crate::ffi_types::FFIString)],
+            [miri_yes, random_large_val, types($crate::ffi_types::FFIOneKiloByte)],
+            [miri_yes, random_narrow, types(i32)],
+            [miri_yes, random_s50, types(i32)],
+            [miri_yes, random_s95, types(i32)],
+            [miri_no, random_str, types(String)],
+            [miri_yes, random_type_u128, types(u128)],
+            [miri_yes, random_type_u64, types(u64)],
+            [miri_yes, random_cell_i32, types(std::cell::Cell<i32>)],
+            [miri_yes, random_z1, types(i32)],
+            [miri_no, random_z1_03, types(i32)],
+            [miri_no, random_z2, types(i32)],
+            [miri_yes, saw_mixed, types(i32)],
+            [miri_yes, saw_mixed_range, types(i32)],
+            [miri_yes, sort_by_cached_key, types(i32, (String, usize))],
+            [miri_yes, sort_by_key, sort_by, types(i32)],
+            [miri_yes, sort_vs_sort_by, sort_by, types(i32)],
+            [miri_yes, stability, stable, sort_by, types(u64)],
+            [miri_no, stability_with_patterns, stable, sort_by, types(u64)],
+            [miri_yes, violate_ord_retain_original_set_i32, sort_by, types(i32)],
+            [
+                miri_no,
+                violate_ord_retain_original_set_ffi_string,
+                sort_by,
+                types($crate::ffi_types::FFIString)
+            ],
+            [
+                miri_no,
+                violate_ord_retain_original_set_cell_i32,
+                sort_by,
+                types(std::cell::Cell<i32>)
+            ],
+            [miri_yes, violate_ord_strict_i32, sort_by, panics_on_ord_violation, types(i32)],
+            [
+                miri_no,
+                violate_ord_strict_ffi_string,
+                sort_by,
+                panics_on_ord_violation,
+                types($crate::ffi_types::FFIString)
+            ],
+            [
+                miri_no,
+                violate_ord_strict_cell_i32,
+                sort_by,
+                panics_on_ord_violation,
+                types(std::cell::Cell<i32>)
+            ]
         );
     };
 }
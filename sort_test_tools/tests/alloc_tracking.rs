@@ -0,0 +1,89 @@
+// A test binary of its own, because it installs the global allocator.
+
+use std::hint::black_box;
+
+use sort_test_tools::alloc_tracking::{self, AllocReport, CountingAlloc};
+use sort_test_tools::{Capabilities, Sort};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+struct SortImpl<const ALLOCATES: bool> {}
+
+// Claims not to allocate, but only the unstable sort of the standard library keeps that promise.
+impl<const ALLOCATES: bool> Sort for SortImpl<ALLOCATES> {
+    fn name() -> String {
+        if ALLOCATES {
+            "rust_std_stable".into()
+        } else {
+            "rust_std_unstable".into()
+        }
+    }
+
+    fn capabilities() -> Capabilities {
+        Capabilities {
+            allocates: false,
+            ..Capabilities::default()
+        }
+    }
+
+    fn sort<T>(arr: &mut [T])
+    where
+        T: Ord,
+    {
+        if ALLOCATES {
+            arr.sort();
+        } else {
+            arr.sort_unstable();
+        }
+    }
+
+    fn sort_by<T, F>(arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> std::cmp::Ordering,
+    {
+        if ALLOCATES {
+            arr.sort_by(compare);
+        } else {
+            arr.sort_unstable_by(compare);
+        }
+    }
+}
+
+#[test]
+fn counts_allocations_and_peak() {
+    let ((), report) = alloc_tracking::measure(|| {
+        let a = black_box(vec![0u8; 100]);
+        let b = black_box(vec![0u8; 50]);
+        drop(a);
+        let c = black_box(Vec::<u8>::with_capacity(30));
+        drop((b, c));
+    });
+
+    assert_eq!(
+        report,
+        AllocReport {
+            allocs: 3,
+            peak_bytes: 150,
+        }
+    );
+}
+
+#[test]
+fn memory_allocated_before_doesnt_count() {
+    let v = black_box(vec![0u8; 100]);
+    let ((), report) = alloc_tracking::measure(|| drop(v));
+
+    assert_eq!(report, AllocReport::default());
+}
+
+#[test]
+fn non_allocating_sort() {
+    alloc_tracking::check_sort::<SortImpl<false>>();
+}
+
+#[test]
+#[should_panic(expected = "rust_std_stable sort of 1000 elements of u64")]
+fn undeclared_allocation() {
+    alloc_tracking::check_sort::<SortImpl<true>>();
+}
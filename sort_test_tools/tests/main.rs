@@ -1,5 +1,5 @@
 use sort_test_tools::instantiate_sort_tests;
-use sort_test_tools::Sort;
+use sort_test_tools::{Capabilities, Sort};
 
 struct SortImpl {}
 
@@ -8,6 +8,13 @@ impl Sort for SortImpl {
         "rust_std_stable".into()
     }
 
+    fn capabilities() -> Capabilities {
+        Capabilities {
+            stable: true,
+            ..Capabilities::default()
+        }
+    }
+
     fn sort<T>(arr: &mut [T])
     where
         T: Ord,
@@ -24,3 +31,285 @@ impl Sort for SortImpl {
 }
 
 instantiate_sort_tests!(SortImpl);
+
+mod partial_sort {
+    use sort_test_tools::{instantiate_partial_sort_tests, PartialSort};
+
+    struct PartialSortImpl {}
+
+    // Reference implementation built on top of the standard library.
+    impl PartialSort for PartialSortImpl {
+        fn name() -> String {
+            "rust_std_partial_unstable".into()
+        }
+
+        fn partial_sort<T>(arr: &mut [T], k: usize)
+        where
+            T: Ord,
+        {
+            Self::partial_sort_by(arr, k, T::cmp);
+        }
+
+        fn partial_sort_by<T, F>(arr: &mut [T], k: usize, mut compare: F)
+        where
+            F: FnMut(&T, &T) -> std::cmp::Ordering,
+        {
+            if k == 0 {
+                return;
+            }
+
+            if k < arr.len() {
+                arr.select_nth_unstable_by(k - 1, &mut compare);
+            }
+            arr[..k].sort_unstable_by(compare);
+        }
+    }
+
+    instantiate_partial_sort_tests!(PartialSortImpl);
+}
+
+mod shrink {
+    use std::panic;
+
+    use sort_test_tools::{patterns, shrink, Capabilities, Sort};
+
+    // Sorts all but the last element.
+    fn sort_but_last(v: &mut [i32]) {
+        if let Some((_, rest)) = v.split_last_mut() {
+            rest.sort();
+        }
+    }
+
+    #[test]
+    fn minimal_reproducer() {
+        let fails = |v: &[i32]| {
+            let mut v = v.to_vec();
+            sort_but_last(&mut v);
+            !v.is_sorted()
+        };
+
+        let input = patterns::random(10_000);
+        assert!(fails(&input));
+
+        let shrunk = shrink::shrink(&input, fails);
+        assert_eq!(shrunk, [1, 0]);
+        assert_eq!(
+            shrink::reproducer(input.len(), &shrunk),
+            "Minimal reproducer, shrunk from 10000 elements:\nlet mut v: [i32; 2] = [1, 0];"
+        );
+    }
+
+    #[test]
+    fn ranks() {
+        assert_eq!(shrink::to_ranks(&[30, -5, 30, 7]), [2, 0, 2, 1]);
+        assert_eq!(shrink::to_ranks::<i32>(&[]), []);
+        assert_eq!(shrink::distinct_sorted(&["b", "a", "b"]), ["a", "b"]);
+    }
+
+    #[test]
+    fn unstable_sort_reported_as_stable() {
+        struct SortImpl {}
+
+        impl Sort for SortImpl {
+            fn name() -> String {
+                "rust_std_unstable".into()
+            }
+
+            fn capabilities() -> Capabilities {
+                Capabilities {
+                    stable: true,
+                    ..Capabilities::default()
+                }
+            }
+
+            fn sort<T>(arr: &mut [T])
+            where
+                T: Ord,
+            {
+                arr.sort_unstable();
+            }
+
+            fn sort_by<T, F>(arr: &mut [T], compare: F)
+            where
+                F: FnMut(&T, &T) -> std::cmp::Ordering,
+            {
+                arr.sort_unstable_by(compare);
+            }
+        }
+
+        let err = panic::catch_unwind(sort_test_tools::tests::stability::<SortImpl>).unwrap_err();
+        let message = err.downcast_ref::<String>().unwrap();
+        assert!(message.contains("\nlet mut v: [i32; "), "{message}");
+    }
+}
+
+mod adversary {
+    use std::cmp::Ordering;
+
+    use sort_test_tools::{adversary, Sort};
+
+    // Quicksort with a median of three pivot and no worst-case guarantee.
+    struct SortImpl {}
+
+    impl SortImpl {
+        fn quicksort<T, F>(v: &mut [T], compare: &mut F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            if v.len() < 2 {
+                return;
+            }
+
+            let (a, b, c) = (0, v.len() / 2, v.len() - 1);
+            let median = if (compare(&v[a], &v[b]) == Ordering::Less)
+                == (compare(&v[b], &v[c]) == Ordering::Less)
+            {
+                b
+            } else if (compare(&v[b], &v[a]) == Ordering::Less)
+                == (compare(&v[a], &v[c]) == Ordering::Less)
+            {
+                a
+            } else {
+                c
+            };
+            v.swap(0, median);
+
+            let mut lt = 1;
+            for i in 1..v.len() {
+                if compare(&v[i], &v[0]) == Ordering::Less {
+                    v.swap(i, lt);
+                    lt += 1;
+                }
+            }
+            v.swap(0, lt - 1);
+
+            let (left, right) = v.split_at_mut(lt - 1);
+            Self::quicksort(left, compare);
+            Self::quicksort(&mut right[1..], compare);
+        }
+    }
+
+    impl Sort for SortImpl {
+        fn name() -> String {
+            "median_of_3_quicksort_unstable".into()
+        }
+
+        fn sort<T>(arr: &mut [T])
+        where
+            T: Ord,
+        {
+            Self::sort_by(arr, T::cmp);
+        }
+
+        fn sort_by<T, F>(arr: &mut [T], mut compare: F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            Self::quicksort(arr, &mut compare);
+        }
+    }
+
+    #[test]
+    fn quadratic_quicksort() {
+        let len = 2_000;
+        let mut v = adversary::killer_input::<SortImpl>(len);
+
+        let mut expected = (0..len as i32).collect::<Vec<_>>();
+        let mut sorted = v.clone();
+        sorted.sort();
+        assert_eq!(sorted, expected);
+
+        let mut comps = 0;
+        SortImpl::sort_by(&mut v, |a, b| {
+            comps += 1;
+            a.cmp(b)
+        });
+        expected.sort();
+        assert_eq!(v, expected);
+        assert!(comps >= len * len / 8, "{comps}");
+    }
+
+    #[test]
+    fn comparison_bound() {
+        let err =
+            std::panic::catch_unwind(sort_test_tools::tests::adversarial_comparisons::<SortImpl>)
+                .unwrap_err();
+        assert!(err
+            .downcast_ref::<String>()
+            .unwrap()
+            .contains("killer input"));
+    }
+}
+
+mod drop_tracking {
+    use std::{mem, ptr};
+
+    use sort_test_tools::drop_tracking::{self, DropReport, DropTracked};
+
+    #[test]
+    fn counts_clones_moves_and_drops() {
+        let ((), report) = drop_tracking::track(|| {
+            let a = DropTracked::new(1);
+            let b = a.clone();
+            assert!(a == b);
+
+            let a = Box::new(a);
+            assert!(*a == b);
+        });
+
+        assert_eq!(
+            report,
+            DropReport {
+                created: 2,
+                clones: 1,
+                drops: 2,
+                moves: 1,
+                ..DropReport::default()
+            }
+        );
+        assert!(report.is_sound());
+    }
+
+    #[test]
+    fn leak() {
+        let ((), report) = drop_tracking::track(|| mem::forget(DropTracked::new(1)));
+
+        assert_eq!(report.leaks, 1);
+        assert!(!report.is_sound());
+    }
+
+    #[test]
+    fn double_drop() {
+        let ((), report) = drop_tracking::track(|| {
+            let a = DropTracked::new(1);
+            // SAFETY: Not safe, the registry prevents the second drop from freeing the string.
+            let a_copy = unsafe { ptr::read(&a) };
+            drop(a);
+            drop(a_copy);
+        });
+
+        assert_eq!(report.double_drops, 1);
+        assert!(!report.is_sound());
+    }
+
+    #[test]
+    fn use_after_drop() {
+        let ((), report) = drop_tracking::track(|| {
+            // Created first, it could otherwise take over the address and identity of `a`.
+            let b = DropTracked::new(2);
+
+            let a = DropTracked::new(1);
+            // SAFETY: Not safe, the registry prevents reading the freed string.
+            let a_copy = unsafe { ptr::read(&a) };
+            drop(a);
+
+            let _ = a_copy.clone();
+            assert!(a_copy == b);
+            mem::forget(a_copy);
+        });
+
+        assert_eq!(report.uses_after_drop, 2);
+        assert_eq!(report.double_drops, 0);
+        assert!(!report.is_sound());
+    }
+}
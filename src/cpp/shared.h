@@ -4,6 +4,10 @@
 #include <stdint.h>
 
 extern "C" {
+// ipnsort-capi/include/ipnsort.h defines the same types, only define them once.
+#ifndef SORT_SHARED_FFI_TYPES
+#define SORT_SHARED_FFI_TYPES
+
 struct CompResult {
   int8_t cmp_result;
   bool is_panic;
@@ -23,6 +27,8 @@ struct F128 {
 struct FFIOneKiloByte {
   int64_t values[128];
 };
+
+#endif  // SORT_SHARED_FFI_TYPES
 }
 
 #if __cplusplus >= 201703L
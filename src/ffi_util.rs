@@ -53,16 +53,43 @@ macro_rules! make_cpp_sort_by {
     };
 }
 
+// Only the types with an extern function are supported, pass `stable` for stable sorts.
 macro_rules! ffi_sort_impl {
+    ($name:expr, $sort_name_prefix:ident, stable) => {
+        ffi_sort_impl!(@impl $name, $sort_name_prefix, true);
+    };
+    ($name:expr, $sort_name_prefix:ident) => {
+        ffi_sort_impl!(@impl $name, $sort_name_prefix, false);
+    };
     (
+        @impl
         $name:expr,
-        $sort_name_prefix:ident
+        $sort_name_prefix:ident,
+        $stable:expr
     ) => {
+        use std::any::TypeId;
         use std::cmp::Ordering;
 
+        use sort_test_tools::drop_tracking::DropTracked;
         use sort_test_tools::ffi_types::{CompResult, FFIOneKiloByte, FFIString, F128};
-
-        sort_impl!($name);
+        use sort_test_tools::{Capabilities, ElementTypes};
+
+        sort_impl!(
+            $name,
+            capabilities = Capabilities {
+                stable: $stable,
+                types: ElementTypes::Only(vec![
+                    TypeId::of::<i32>(),
+                    TypeId::of::<u64>(),
+                    TypeId::of::<FFIString>(),
+                    TypeId::of::<F128>(),
+                    TypeId::of::<FFIOneKiloByte>(),
+                    TypeId::of::<DropTracked>(),
+                ]),
+                ffi: true,
+                ..Capabilities::default()
+            }
+        );
 
         paste::paste! {
             extern "C" {
@@ -178,6 +205,19 @@ macro_rules! ffi_sort_impl {
                 }
             }
 
+            // Sorted as the strings they wrap, to check how the C++ sorts handle their ownership.
+            impl CppSort for DropTracked {
+                fn sort(data: &mut [Self]) {
+                    <FFIString as CppSort>::sort(DropTracked::as_ffi_strings_mut(data));
+                }
+
+                fn sort_by<F: FnMut(&Self, &Self) -> Ordering>(data: &mut [Self], mut compare: F) {
+                    <FFIString as CppSort>::sort_by(DropTracked::as_ffi_strings_mut(data), |a, b| {
+                        compare(DropTracked::from_ffi_string(a), DropTracked::from_ffi_string(b))
+                    });
+                }
+            }
+
             pub fn sort<T: Ord>(data: &mut [T]) {
                 CppSort::sort(data);
             }
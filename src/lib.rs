@@ -19,8 +19,11 @@
     generic_const_exprs
 )]
 
+// The optional idents name key-based sort functions the module provides, e.g. `sort_by_key`. The
+// others use the default implementations of `Sort`. Implementations that are stable, don't
+// support every type, etc. describe that with `capabilities = ..`.
 macro_rules! sort_impl {
-    ($name:expr) => {
+    ($name:expr, capabilities = $capabilities:expr $(, $key_fn:ident)*) => {
         pub struct SortImpl;
 
         impl sort_test_tools::Sort for SortImpl {
@@ -28,6 +31,10 @@ macro_rules! sort_impl {
                 $name.into()
             }
 
+            fn capabilities() -> sort_test_tools::Capabilities {
+                $capabilities
+            }
+
             #[inline]
             fn sort<T>(arr: &mut [T])
             where
@@ -43,8 +50,22 @@ macro_rules! sort_impl {
             {
                 sort_by(arr, compare);
             }
+
+            $(
+                #[inline]
+                fn $key_fn<T, K, F>(arr: &mut [T], f: F)
+                where
+                    K: Ord,
+                    F: FnMut(&T) -> K,
+                {
+                    $key_fn(arr, f);
+                }
+            )*
         }
     };
+    ($name:expr $(, $key_fn:ident)*) => {
+        sort_impl!($name, capabilities = sort_test_tools::Capabilities::default() $(, $key_fn)*);
+    };
 }
 
 #[allow(unused_macros)]
@@ -14,10 +14,20 @@ pub trait Partition {
         pivot: &T,
         is_less: &mut F,
     ) -> usize;
+
+    /// The most elements the heap memory of [`Partition::partition`] can hold for a slice of
+    /// length `len`.
+    fn scratch_len(_len: usize) -> usize {
+        0
+    }
 }
 
+// Partitions that allocate describe how much with `scratch_len = ..`.
 macro_rules! partition_impl {
     ($name:expr) => {
+        partition_impl!($name, scratch_len = |_len| 0);
+    };
+    ($name:expr, scratch_len = $scratch_len:expr) => {
         pub struct PartitionImpl;
 
         impl crate::other::partition::Partition for PartitionImpl {
@@ -40,6 +50,10 @@ macro_rules! partition_impl {
             {
                 partition(arr, pivot, is_less)
             }
+
+            fn scratch_len(len: usize) -> usize {
+                ($scratch_len)(len)
+            }
         }
     };
 }
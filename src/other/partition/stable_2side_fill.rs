@@ -6,7 +6,7 @@ use std::slice;
 
 use crate::other::partition::get_or_alloc_tls_scratch;
 
-partition_impl!("stable_2side_fill");
+partition_impl!("stable_2side_fill", scratch_len = |len| len);
 
 // Can the type have interior mutability, this is checked by testing if T is Copy. If the type can
 // have interior mutability it may alter itself during comparison in a way that must be observed
@@ -1,8 +1,35 @@
+use std::any::TypeId;
 use std::cmp::Ordering;
 
 use radsort;
+use sort_test_tools::{Capabilities, ElementTypes};
 
-sort_impl!("rust_radsort_radix");
+// `radsort::Key` is also implemented for floats, which don't implement `Ord`, and tuples of up to
+// four keys, which are too many to list.
+sort_impl!(
+    "rust_radsort_radix",
+    capabilities = Capabilities {
+        stable: true,
+        sort_by: false,
+        types: ElementTypes::Only(vec![
+            TypeId::of::<bool>(),
+            TypeId::of::<char>(),
+            TypeId::of::<u8>(),
+            TypeId::of::<u16>(),
+            TypeId::of::<u32>(),
+            TypeId::of::<u64>(),
+            TypeId::of::<u128>(),
+            TypeId::of::<usize>(),
+            TypeId::of::<i8>(),
+            TypeId::of::<i16>(),
+            TypeId::of::<i32>(),
+            TypeId::of::<i64>(),
+            TypeId::of::<i128>(),
+            TypeId::of::<isize>(),
+        ]),
+        ..Capabilities::default()
+    }
+);
 
 trait RadSort: Sized {
     fn sort(data: &mut [Self]);
@@ -2,7 +2,15 @@ use core::cmp::Ordering;
 use core::mem::MaybeUninit;
 use core::ptr;
 
-sort_impl!("sort4_stable_orson");
+use sort_test_tools::Capabilities;
+
+sort_impl!(
+    "sort4_stable_orson",
+    capabilities = Capabilities {
+        stable: true,
+        ..Capabilities::default()
+    }
+);
 
 /// SAFETY: The caller MUST guarantee that `arr_ptr` is valid for 4 reads and `dest_ptr` is valid
 /// for 4 writes.
@@ -3,7 +3,16 @@ use std::cmp::Ordering;
 use std::mem::SizedTypeProperties;
 use std::ptr;
 
-sort_impl!("timsort_evo1_stable");
+use sort_test_tools::Capabilities;
+
+sort_impl!(
+    "timsort_evo1_stable",
+    capabilities = Capabilities {
+        stable: true,
+        scratch_len: Some(|len| len / 2),
+        ..Capabilities::default()
+    }
+);
 
 #[inline]
 pub fn sort<T>(v: &mut [T])
@@ -4,7 +4,16 @@ use std::cmp::Ordering;
 use std::mem::{self, SizedTypeProperties};
 use std::ptr;
 
-sort_impl!("timsort_evo3_stable");
+use sort_test_tools::Capabilities;
+
+sort_impl!(
+    "timsort_evo3_stable",
+    capabilities = Capabilities {
+        stable: true,
+        scratch_len: Some(|len| len),
+        ..Capabilities::default()
+    }
+);
 
 #[inline]
 pub fn sort<T>(v: &mut [T])
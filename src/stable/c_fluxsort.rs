@@ -1 +1 @@
-ffi_sort_impl!("c_fluxsort_stable", fluxsort_stable);
+ffi_sort_impl!("c_fluxsort_stable", fluxsort_stable, stable);
@@ -1 +1 @@
-ffi_sort_impl!("cpp_powersort_stable", powersort_stable);
+ffi_sort_impl!("cpp_powersort_stable", powersort_stable, stable);
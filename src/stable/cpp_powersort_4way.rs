@@ -1 +1 @@
-ffi_sort_impl!("cpp_powersort_4way_stable", powersort_4way_stable);
+ffi_sort_impl!("cpp_powersort_4way_stable", powersort_4way_stable, stable);
@@ -1 +1 @@
-ffi_sort_impl!("cpp_std_libcxx_stable", sort_stable_libcxx);
+ffi_sort_impl!("cpp_std_libcxx_stable", sort_stable_libcxx, stable);
@@ -1 +1 @@
-ffi_sort_impl!("cpp_std_sys_stable", sort_stable_sys);
+ffi_sort_impl!("cpp_std_sys_stable", sort_stable_sys, stable);
@@ -1,8 +1,15 @@
 use std::cmp::Ordering;
 
 use glidesort;
+use sort_test_tools::Capabilities;
 
-sort_impl!("rust_glidesort_stable");
+sort_impl!(
+    "rust_glidesort_stable",
+    capabilities = Capabilities {
+        stable: true,
+        ..Capabilities::default()
+    }
+);
 
 pub fn sort<T: Ord>(data: &mut [T]) {
     glidesort::sort(data);
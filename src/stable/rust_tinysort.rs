@@ -1,8 +1,15 @@
 use std::cmp::Ordering;
 
+use sort_test_tools::Capabilities;
 use tiny_sort;
 
-sort_impl!("rust_tinymergesort_stable");
+sort_impl!(
+    "rust_tinymergesort_stable",
+    capabilities = Capabilities {
+        stable: true,
+        ..Capabilities::default()
+    }
+);
 
 pub fn sort<T: Ord>(data: &mut [T]) {
     tiny_sort::stable::sort(data);
@@ -8,7 +8,16 @@ use std::cmp::Ordering;
 use std::mem::{self, size_of};
 use std::ptr;
 
-sort_impl!("rust_wpwoodjr_stable");
+use sort_test_tools::Capabilities;
+
+sort_impl!(
+    "rust_wpwoodjr_stable",
+    capabilities = Capabilities {
+        stable: true,
+        scratch_len: Some(|len| len / 2),
+        ..Capabilities::default()
+    }
+);
 
 /// Sorts the slice.
 ///
@@ -1,4 +1,9 @@
 pub mod rust_ipnsort;
+pub mod rust_ipnsort_dual_pivot;
+pub mod rust_ipnsort_floats;
+pub mod rust_ipnsort_par;
+pub mod rust_ipnsort_scratch;
+pub mod rust_ipnsort_strings;
 pub mod rust_std;
 
 #[cfg(feature = "rust_dmsort")]
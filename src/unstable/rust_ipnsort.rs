@@ -1,8 +1,17 @@
 use std::cmp::Ordering;
 
 use ipnsort;
+use sort_test_tools::Capabilities;
 
-sort_impl!("rust_ipnsort_unstable");
+sort_impl!(
+    "rust_ipnsort_unstable",
+    capabilities = Capabilities {
+        allocates: false,
+        ..Capabilities::default()
+    },
+    sort_by_key,
+    sort_by_cached_key
+);
 
 pub fn sort<T: Ord>(data: &mut [T]) {
     ipnsort::sort(data);
@@ -11,3 +20,11 @@ pub fn sort<T: Ord>(data: &mut [T]) {
 pub fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(data: &mut [T], compare: F) {
     ipnsort::sort_by(data, compare);
 }
+
+pub fn sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(data: &mut [T], f: F) {
+    ipnsort::sort_by_key(data, f);
+}
+
+pub fn sort_by_cached_key<T, K: Ord, F: FnMut(&T) -> K>(data: &mut [T], f: F) {
+    ipnsort::sort_by_cached_key(data, f);
+}
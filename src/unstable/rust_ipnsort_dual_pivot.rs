@@ -0,0 +1,20 @@
+use std::cmp::Ordering;
+
+use ipnsort;
+use sort_test_tools::Capabilities;
+
+sort_impl!(
+    "rust_ipnsort_dual_pivot_unstable",
+    capabilities = Capabilities {
+        allocates: false,
+        ..Capabilities::default()
+    }
+);
+
+pub fn sort<T: Ord>(data: &mut [T]) {
+    ipnsort::sort_dual_pivot(data);
+}
+
+pub fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(data: &mut [T], compare: F) {
+    ipnsort::sort_by_dual_pivot(data, compare);
+}
@@ -0,0 +1,51 @@
+use std::cmp::Ordering;
+use std::slice;
+
+use ipnsort;
+use sort_test_tools::ffi_types::{F32, F64};
+use sort_test_tools::Capabilities;
+
+sort_impl!(
+    "rust_ipnsort_floats_unstable",
+    capabilities = Capabilities {
+        allocates: false,
+        ..Capabilities::default()
+    }
+);
+
+trait FloatSort: Sized {
+    fn sort(data: &mut [Self]);
+}
+
+// Other types are sorted the same way as rust_ipnsort.
+impl<T: Ord> FloatSort for T {
+    default fn sort(data: &mut [Self]) {
+        ipnsort::sort(data);
+    }
+}
+
+impl FloatSort for F32 {
+    fn sort(data: &mut [Self]) {
+        // SAFETY: F32 is repr(transparent).
+        let floats =
+            unsafe { slice::from_raw_parts_mut(data.as_mut_ptr() as *mut f32, data.len()) };
+        ipnsort::sort_floats(floats);
+    }
+}
+
+impl FloatSort for F64 {
+    fn sort(data: &mut [Self]) {
+        // SAFETY: F64 is repr(transparent).
+        let floats =
+            unsafe { slice::from_raw_parts_mut(data.as_mut_ptr() as *mut f64, data.len()) };
+        ipnsort::sort_floats(floats);
+    }
+}
+
+pub fn sort<T: Ord>(data: &mut [T]) {
+    FloatSort::sort(data);
+}
+
+pub fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(data: &mut [T], compare: F) {
+    ipnsort::sort_by(data, compare);
+}
@@ -0,0 +1,73 @@
+use std::cmp::Ordering;
+
+use ipnsort;
+
+/// Parallel ipnsort with `THREADS` threads, or as many as the system provides if zero. The thread
+/// count is part of the name, e.g. `rust_ipnsort_par_t8_unstable`.
+///
+/// Types that aren't `Send`, and comparison functions that aren't `Fn + Sync`, are sorted
+/// sequentially, the same as rust_ipnsort. All benchmark types are `Send`.
+pub struct SortImpl<const THREADS: usize>;
+
+impl<const THREADS: usize> sort_test_tools::Sort for SortImpl<THREADS> {
+    fn name() -> String {
+        format!("rust_ipnsort_par_t{}_unstable", config::<THREADS>().threads)
+    }
+
+    #[inline]
+    fn sort<T>(arr: &mut [T])
+    where
+        T: Ord,
+    {
+        ParSort::sort(arr, &config::<THREADS>());
+    }
+
+    #[inline]
+    fn sort_by<T, F>(arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        ParSortBy::sort_by(arr, &config::<THREADS>(), compare);
+    }
+}
+
+fn config<const THREADS: usize>() -> ipnsort::ParConfig {
+    let mut config = ipnsort::ParConfig::default();
+    if THREADS != 0 {
+        config.threads = THREADS;
+    }
+
+    config
+}
+
+trait ParSort: Sized {
+    fn sort(data: &mut [Self], config: &ipnsort::ParConfig);
+}
+
+impl<T: Ord> ParSort for T {
+    default fn sort(data: &mut [Self], _config: &ipnsort::ParConfig) {
+        ipnsort::sort(data);
+    }
+}
+
+impl<T: Ord + Send> ParSort for T {
+    fn sort(data: &mut [Self], config: &ipnsort::ParConfig) {
+        ipnsort::par_sort_with_config(data, config, |a, b| a.cmp(b));
+    }
+}
+
+trait ParSortBy<F>: Sized {
+    fn sort_by(data: &mut [Self], config: &ipnsort::ParConfig, compare: F);
+}
+
+impl<T, F: FnMut(&T, &T) -> Ordering> ParSortBy<F> for T {
+    default fn sort_by(data: &mut [Self], _config: &ipnsort::ParConfig, compare: F) {
+        ipnsort::sort_by(data, compare);
+    }
+}
+
+impl<T: Send, F: Fn(&T, &T) -> Ordering + Sync> ParSortBy<F> for T {
+    fn sort_by(data: &mut [Self], config: &ipnsort::ParConfig, compare: F) {
+        ipnsort::par_sort_with_config(data, config, compare);
+    }
+}
@@ -0,0 +1,28 @@
+use std::cmp::Ordering;
+use std::mem::MaybeUninit;
+
+use ipnsort;
+use sort_test_tools::Capabilities;
+
+sort_impl!(
+    "rust_ipnsort_scratch_unstable",
+    capabilities = Capabilities {
+        allocates: false,
+        ..Capabilities::default()
+    }
+);
+
+// The most scratch space the small-sort uses. Merging a presorted prefix or suffix uses all of
+// it, but only if it holds more than the 4 KiB stack buffer of the merge, e.g. for `1k`. A larger
+// buffer would only save rotations there.
+const SCRATCH_LEN: usize = 48;
+
+pub fn sort<T: Ord>(data: &mut [T]) {
+    let mut scratch = [const { MaybeUninit::<T>::uninit() }; SCRATCH_LEN];
+    ipnsort::sort_with_scratch(data, &mut scratch, |a, b| a.lt(b));
+}
+
+pub fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(data: &mut [T], mut compare: F) {
+    let mut scratch = [const { MaybeUninit::<T>::uninit() }; SCRATCH_LEN];
+    ipnsort::sort_with_scratch(data, &mut scratch, |a, b| compare(a, b) == Ordering::Less);
+}
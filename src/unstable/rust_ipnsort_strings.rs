@@ -0,0 +1,37 @@
+use std::cmp::Ordering;
+
+use ipnsort;
+use sort_test_tools::ffi_types::FFIString;
+
+sort_impl!("rust_ipnsort_strings_unstable");
+
+trait StringSort: Sized {
+    fn sort(data: &mut [Self]);
+}
+
+// Other types are sorted the same way as rust_ipnsort.
+impl<T: Ord> StringSort for T {
+    default fn sort(data: &mut [Self]) {
+        ipnsort::sort(data);
+    }
+}
+
+impl StringSort for String {
+    fn sort(data: &mut [Self]) {
+        ipnsort::sort_strings(data);
+    }
+}
+
+impl StringSort for FFIString {
+    fn sort(data: &mut [Self]) {
+        ipnsort::sort_strings(data);
+    }
+}
+
+pub fn sort<T: Ord>(data: &mut [T]) {
+    StringSort::sort(data);
+}
+
+pub fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(data: &mut [T], compare: F) {
+    ipnsort::sort_by(data, compare);
+}
@@ -11,7 +11,15 @@ use std::cmp::Ordering;
 use std::mem::{self, MaybeUninit};
 use std::ptr;
 
-sort_impl!("rust_std_unstable");
+use sort_test_tools::Capabilities;
+
+sort_impl!(
+    "rust_std_unstable",
+    capabilities = Capabilities {
+        allocates: false,
+        ..Capabilities::default()
+    }
+);
 
 /// Sorts the slice, but might not preserve the order of equal elements.
 ///
@@ -1,8 +1,15 @@
 use std::cmp::Ordering;
 
+use sort_test_tools::Capabilities;
 use tiny_sort;
 
-sort_impl!("rust_tinyheapsort_unstable");
+sort_impl!(
+    "rust_tinyheapsort_unstable",
+    capabilities = Capabilities {
+        allocates: false,
+        ..Capabilities::default()
+    }
+);
 
 pub fn sort<T: Ord>(data: &mut [T]) {
     tiny_sort::unstable::sort(data);
@@ -0,0 +1,114 @@
+// A test binary of its own, because it installs the global allocator.
+
+use sort_test_tools::alloc_tracking::{self, CountingAlloc};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+macro_rules! alloc_tests {
+    ($($(#[$attr:meta])* $test_name:ident: $sort_impl:ty,)*) => {
+        $(
+            $(#[$attr])*
+            #[test]
+            fn $test_name() {
+                alloc_tracking::check_sort::<$sort_impl>();
+            }
+        )*
+    };
+}
+
+alloc_tests!(
+    rust_std_stable: sort_comp::stable::rust_std::SortImpl,
+    rust_std_unstable: sort_comp::unstable::rust_std::SortImpl,
+    rust_ipnsort: sort_comp::unstable::rust_ipnsort::SortImpl,
+    rust_ipnsort_dual_pivot: sort_comp::unstable::rust_ipnsort_dual_pivot::SortImpl,
+    rust_ipnsort_floats: sort_comp::unstable::rust_ipnsort_floats::SortImpl,
+    rust_ipnsort_scratch: sort_comp::unstable::rust_ipnsort_scratch::SortImpl,
+    rust_ipnsort_strings: sort_comp::unstable::rust_ipnsort_strings::SortImpl,
+    #[cfg(feature = "rust_wpwoodjr")]
+    rust_wpwoodjr_stable: sort_comp::stable::rust_wpwoodjr::SortImpl,
+    #[cfg(feature = "rust_glidesort")]
+    rust_glidesort_stable: sort_comp::stable::rust_glidesort::SortImpl,
+    #[cfg(feature = "rust_tinysort")]
+    rust_tinymergesort_stable: sort_comp::stable::rust_tinysort::SortImpl,
+    #[cfg(feature = "rust_tinysort")]
+    rust_tinyheapsort_unstable: sort_comp::unstable::rust_tinysort::SortImpl,
+    #[cfg(feature = "rust_radsort")]
+    rust_radsort_radix: sort_comp::other::rust_radsort::SortImpl,
+    #[cfg(feature = "evolution")]
+    timsort_evo0_stable: sort_comp::other::sort_evolution::stable::timsort_evo0::SortImpl,
+    #[cfg(feature = "evolution")]
+    timsort_evo1_stable: sort_comp::other::sort_evolution::stable::timsort_evo1::SortImpl,
+    #[cfg(feature = "evolution")]
+    timsort_evo2_stable: sort_comp::other::sort_evolution::stable::timsort_evo2::SortImpl,
+    #[cfg(feature = "evolution")]
+    timsort_evo3_stable: sort_comp::other::sort_evolution::stable::timsort_evo3::SortImpl,
+    #[cfg(feature = "evolution")]
+    timsort_evo4_stable: sort_comp::other::sort_evolution::stable::timsort_evo4::SortImpl,
+);
+
+#[cfg(feature = "partition")]
+mod partition {
+    use std::mem;
+
+    use sort_comp::other::partition::Partition;
+    use sort_test_tools::alloc_tracking;
+    use sort_test_tools::patterns;
+
+    // Starts with the smallest length, a partition with a scratch cache allocates whenever the
+    // length grows.
+    const TEST_LENS: [usize; 12] = [1, 2, 3, 8, 16, 17, 32, 64, 100, 1_000, 10_000, 100_000];
+
+    fn check_partition<P: Partition>() {
+        for len in TEST_LENS {
+            for pattern in [patterns::random, patterns::ascending, patterns::descending] {
+                let mut v = pattern(len)
+                    .into_iter()
+                    .map(|val| val as u64)
+                    .collect::<Vec<_>>();
+
+                let pivot = v[len / 2];
+                let (_, report) = alloc_tracking::measure(|| P::partition(&mut v, &pivot));
+
+                let scratch_len = P::scratch_len(len);
+                let context = format!("{} of {len} elements of u64", P::name());
+                alloc_tracking::assert_within(
+                    &context,
+                    report,
+                    (scratch_len != 0) as usize,
+                    scratch_len * mem::size_of::<u64>(),
+                );
+            }
+        }
+    }
+
+    macro_rules! partition_alloc_tests {
+        ($($partition:ident),*) => {
+            $(
+                #[test]
+                fn $partition() {
+                    check_partition::<sort_comp::other::partition::$partition::PartitionImpl>();
+                }
+            )*
+        };
+    }
+
+    partition_alloc_tests!(
+        hoare_block,
+        hoare_block_butterfly,
+        hoare_branchy,
+        hoare_branchy_cyclic,
+        hoare_crumsort,
+        hoare_crumsort_rs,
+        hybrid_bitset_partition,
+        hybrid_block_partition,
+        lomuto_branchless,
+        lomuto_branchless_cyclic,
+        lomuto_branchless_cyclic_opt,
+        lomuto_branchy,
+        lomuto_iterleaved,
+        small_partition,
+        stable_2side_fill,
+        sum_is_less
+    );
+}
@@ -1,3 +1,4 @@
+import json
 import sys
 
 # import re
@@ -16,12 +17,22 @@ if __name__ == "__main__":
     bucket_c = []
 
     for line in input.split("\n"):
-        if "is_less" not in line:
+        # Either the output of the ipnsort `trace` feature `JsonLines` collector, or lines of the
+        # form `len: 1000 is_less: 480`.
+        if line.startswith("{"):
+            event = json.loads(line)
+            if event["event"] != "partition":
+                continue
+
+            len = event["len"]
+            is_less = event["mid"]
+        elif "is_less" in line:
+            a, _, b = line.partition("is_less:")
+            len = int(a.partition("len:")[2].strip())
+            is_less = int(b.strip())
+        else:
             continue
 
-        a, _, b = line.partition("is_less:")
-        len = int(a.partition("len:")[2].strip())
-        is_less = int(b.strip())
         len_div_2 = len / 2
 
         # Ideally each partition operation halves the input.